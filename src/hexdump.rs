@@ -0,0 +1,131 @@
+use crate::Storage;
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+const BYTES_PER_LINE: u32 = 16;
+const BYTES_PER_GROUP: u32 = 2;
+
+/// Formats the range `[address..address+length]` of `storage` in the style of `xxd`.
+///
+/// Each line starts with the address of its first byte, followed by the bytes in hexadecimal
+/// (grouped by two) and an ASCII column in which non-printable bytes are shown as `.`.
+///
+/// Bytes that cannot be read (e.g. unmapped ranges of a [`CompositeMemory`] or addresses past
+/// the end of the address space) are shown as `??` in the hexadecimal columns and as `?` in the
+/// ASCII column.
+///
+/// # Examples
+/// ```
+/// use vcpu::hexdump;
+///
+/// let memory = *b"Hello, world!\n";
+/// assert_eq!(
+///     hexdump(&memory, 0, 16),
+///     "00000000: 4865 6c6c 6f2c 2077 6f72 6c64 210a ????  Hello, world!.??\n"
+/// );
+/// ```
+/// [`CompositeMemory`]: ./struct.CompositeMemory.html
+pub fn hexdump(storage: &dyn Storage, address: u32, length: u32) -> String {
+    let mut output = String::new();
+    let end = u64::from(address) + u64::from(length);
+    let mut line_address = u64::from(address);
+
+    while line_address < end {
+        let line_length = (end - line_address).min(u64::from(BYTES_PER_LINE)) as u32;
+        let bytes: Vec<Option<u8>> = (0..line_length)
+            .map(|i| {
+                u32::try_from(line_address + u64::from(i))
+                    .ok()
+                    .and_then(|address| storage.read_byte(address).ok())
+            })
+            .collect();
+
+        write!(output, "{:08x}:", line_address).unwrap();
+
+        for i in 0..BYTES_PER_LINE {
+            if i % BYTES_PER_GROUP == 0 {
+                output.push(' ');
+            }
+
+            match bytes.get(i as usize) {
+                Some(Some(byte)) => write!(output, "{:02x}", byte).unwrap(),
+                Some(None) => output.push_str("??"),
+                None => output.push_str("  "),
+            }
+        }
+
+        output.push_str("  ");
+
+        for byte in bytes {
+            output.push(match byte {
+                Some(b @ 0x20..=0x7E) => b as char,
+                Some(_) => '.',
+                None => '?',
+            });
+        }
+
+        output.push('\n');
+        line_address += u64::from(line_length);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::CompositeMemory;
+
+    #[test]
+    fn hexdump_pattern() {
+        let mut memory = [0u8; 32];
+        for (i, byte) in memory.iter_mut().enumerate() {
+            *byte = 0x30 + i as u8;
+        }
+
+        assert_eq!(
+            hexdump(&memory, 0, 32),
+            "00000000: 3031 3233 3435 3637 3839 3a3b 3c3d 3e3f  0123456789:;<=>?\n\
+             00000010: 4041 4243 4445 4647 4849 4a4b 4c4d 4e4f  @ABCDEFGHIJKLMNO\n"
+        );
+    }
+
+    #[test]
+    fn hexdump_partial_line() {
+        let memory = [0u8, 1u8, 0xFFu8];
+
+        assert_eq!(
+            hexdump(&memory, 0, 3),
+            "00000000: 0001 ff                                  ...\n"
+        );
+    }
+
+    #[test]
+    fn hexdump_unmapped() {
+        let mut memory = CompositeMemory::new();
+        memory.mount(0x104, "f0", *b"abcd").unwrap();
+
+        assert_eq!(
+            hexdump(&memory, 0x100, 12),
+            "00000100: ???? ???? 6162 6364 ???? ????            ????abcd????\n"
+        );
+    }
+
+    #[test]
+    fn hexdump_end_of_address_space() {
+        let mut memory = CompositeMemory::new();
+        memory.mount(0xFFFF_FFF8, "f0", *b"abcdefg").unwrap();
+        // Must not show up after wrapping around.
+        memory.mount(0, "f1", [0x41u8; 32]).unwrap();
+
+        assert_eq!(
+            hexdump(&memory, 0xFFFF_FFF8, 16),
+            "fffffff8: 6162 6364 6566 67?? ???? ???? ???? ????  abcdefg?????????\n"
+        );
+        assert_eq!(
+            hexdump(&memory, 0xFFFF_FFF8, 32),
+            "fffffff8: 6162 6364 6566 67?? ???? ???? ???? ????  abcdefg?????????\n\
+             100000008: ???? ???? ???? ???? ???? ???? ???? ????  ????????????????\n"
+        );
+    }
+}
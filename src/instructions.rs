@@ -1,8 +1,9 @@
 use crate::{constants, Address, Immediate, Word};
-use num::traits::ToPrimitive;
+use num::traits::{FromPrimitive, ToPrimitive};
 use num_derive::{FromPrimitive, ToPrimitive};
-use util::{EnumFromStr, InteropGetName};
-use util_derive::{EnumFromStr, InteropGetName};
+use std::convert::TryFrom;
+use util::{EnumAll, EnumFromStr, EnumGetVariantCount, InteropGetName};
+use util_derive::{EnumAll, EnumFromStr, EnumGetVariantCount, InteropGetName};
 
 /// Processor instruction set.
 ///
@@ -14,7 +15,17 @@ use util_derive::{EnumFromStr, InteropGetName};
 /// | I-Format | opcode     | Rd         | Rs1        | immediate  | immediate | immediate |
 /// | J-Format | opcode     | address    | address    | address    | address   | address   |
 #[derive(
-    Clone, Copy, PartialEq, Eq, Debug, ToPrimitive, FromPrimitive, InteropGetName, EnumFromStr,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Debug,
+    ToPrimitive,
+    FromPrimitive,
+    InteropGetName,
+    EnumFromStr,
+    EnumGetVariantCount,
+    EnumAll,
 )]
 pub enum Opcode {
     /// No-op.
@@ -38,6 +49,9 @@ pub enum Opcode {
     ///
     /// Format: `I`.
     /// Copies register value from `Rs1` to `Rd`.
+    ///
+    /// This is a raw copy of all 32 bits, so it works the same for integer and float values
+    /// (use [`ITOF`](#variant.ITOF)/[`FTOI`](#variant.FTOI) to convert between the two).
     COPY,
     /// Load immediate.
     ///
@@ -45,18 +59,21 @@ pub enum Opcode {
     /// Sets `Rd` to the value of `immediate` (sign bit is extended).
     LI,
     /// Load high immediate.
+    ///
     /// Format: `I`.
-    /// Sets `Rd` to the value of `immediate << 16`.
+    /// Sets `Rd` to the value of `immediate << 16`, i.e. the low 16 bits are cleared.
+    /// Unlike `SHI`, this does not depend on the previous value of `Rd`.
     LHI,
     /// Set low bits.
     ///
     /// Format: `I`.
-    /// Sets (only) the low 16 bits of `Rd` to `immediate`.
+    /// Sets (only) the low 16 bits of `Rd` to `immediate`, keeping the high 16 bits.
+    /// Together with `SHI`, this loads an arbitrary 32 bit value regardless of the previous value of `Rd`.
     SLO,
     /// Set high bits.
     ///
     /// Format: `I`.
-    /// Sets (only) the high 16 bits of `Rd` to `immediate`.
+    /// Sets (only) the high 16 bits of `Rd` to `immediate`, keeping the low 16 bits.
     SHI,
     /// Load byte.
     ///
@@ -227,22 +244,54 @@ pub enum Opcode {
     ///
     /// Format: `I`.
     /// Sets `Rd` to `Rs1` converted from integer to a IEEE 754 floating point value.
+    ///
+    /// This converts the value, not the bit pattern (see [`COPY`](#variant.COPY)).
     ITOF,
     /// Float to int.
     ///
     /// Format: `I`.
     /// Sets `Rd` to `Rs1` converted to from IEEE 754 floating point value to integer.
+    ///
+    /// This converts the value, not the bit pattern (see [`COPY`](#variant.COPY)).
+    /// NaN and values out of range are handled according to the [`FloatConvertMode`](enum.FloatConvertMode.html)
+    /// of the processor, which saturates by default.
     FTOI,
     /// Floating point operation.
     ///
     /// Format: `I`.
     /// Performs floating point operation specified by `funct` (see [`FlopFunct`](enum.FlopFunct.html)).
     FLOP,
+    /// Unsigned int to float.
+    ///
+    /// Format: `I`.
+    /// Sets `Rd` to `Rs1` converted from unsigned integer to a IEEE 754 floating point value.
+    ///
+    /// Unlike [`ITOF`](#variant.ITOF), values of `2^31` and above are converted correctly.
+    UTOF,
+    /// Float to unsigned int.
+    ///
+    /// Format: `I`.
+    /// Sets `Rd` to `Rs1` converted from IEEE 754 floating point value to unsigned integer.
+    ///
+    /// NaN and values out of range are handled according to the [`FloatConvertMode`](enum.FloatConvertMode.html)
+    /// of the processor. By default, values below `0` (including negative infinity) and NaN
+    /// produce `0`, values above `u32::MAX` (including positive infinity) produce `u32::MAX`.
+    FTOU,
 }
 
 /// List of functions used by the [`Opcode::ALU`](enum.Opcode.html#variant.ALU) instruction.
 #[derive(
-    Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, Debug, InteropGetName, EnumFromStr,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    Debug,
+    InteropGetName,
+    EnumFromStr,
+    EnumGetVariantCount,
+    EnumAll,
 )]
 pub enum AluFunct {
     /// Add.
@@ -331,7 +380,17 @@ pub enum AluFunct {
 
 /// List of functions used by the [`Opcode::FLOP`](enum.Opcode.html#variant.FLOP) instruction.
 #[derive(
-    Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, Debug, InteropGetName, EnumFromStr,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    Debug,
+    InteropGetName,
+    EnumFromStr,
+    EnumGetVariantCount,
+    EnumAll,
 )]
 pub enum FlopFunct {
     /// Float add.
@@ -350,11 +409,25 @@ pub enum FlopFunct {
     ///
     /// Sets `Rd` to `Rs1 / Rs2` using IEEE 754 floats.
     FDIV,
+    /// Float remainder.
+    ///
+    /// Sets `Rd` to the remainder of the truncated division `Rs1 / Rs2` (like C's `fmodf`),
+    /// which has the sign of `Rs1`. If `Rs2` is zero, `Rd` is set to NaN.
+    FMOD,
 }
 
 /// List of available registers.
 #[derive(
-    Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive, Debug, InteropGetName, EnumFromStr,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    FromPrimitive,
+    ToPrimitive,
+    Debug,
+    InteropGetName,
+    EnumFromStr,
+    EnumAll,
 )]
 pub enum RegisterId {
     /// Always zero (read only).
@@ -538,6 +611,66 @@ pub fn make_i_instruction(
         | (((immediate as u32) << constants::IMMEDIATE_OFFSET) & constants::IMMEDIATE_MASK)
 }
 
+/// Error type for [`try_make_i_instruction`].
+///
+/// [`try_make_i_instruction`]: ./fn.try_make_i_instruction.html
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum EncodeError {
+    /// The immediate value does not fit into the 16 bits of the immediate field.
+    ImmediateOutOfRange(i32),
+}
+
+/// Constructs an I-format instruction, checking that `immediate` fits into the immediate field.
+///
+/// The immediate value of [`Opcode::SLO`], [`Opcode::SHI`] and the unsigned comparisons like
+/// [`Opcode::SLTUI`] must be in the range of `u16`, for all other opcodes it must be in the range
+/// of `i16`.
+///
+/// # Errors
+/// Returns [`EncodeError::ImmediateOutOfRange`] if the immediate value would be truncated by
+/// [`make_i_instruction`].
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+///
+/// assert_eq!(
+///     try_make_i_instruction(Opcode::ADDI, RegisterId::T0, RegisterId::T0, -1),
+///     Ok(instr_i!(ADDI, T0, T0, -1))
+/// );
+/// assert_eq!(
+///     try_make_i_instruction(Opcode::SLO, RegisterId::T0, RegisterId::ZERO, -1),
+///     Err(EncodeError::ImmediateOutOfRange(-1))
+/// );
+/// ```
+/// [`Opcode::SLO`]: ./enum.Opcode.html#variant.SLO
+/// [`Opcode::SHI`]: ./enum.Opcode.html#variant.SHI
+/// [`Opcode::SLTUI`]: ./enum.Opcode.html#variant.SLTUI
+/// [`EncodeError::ImmediateOutOfRange`]: ./enum.EncodeError.html#variant.ImmediateOutOfRange
+/// [`make_i_instruction`]: ./fn.make_i_instruction.html
+pub fn try_make_i_instruction(
+    oc: Opcode,
+    rd: RegisterId,
+    rs1: RegisterId,
+    immediate: i32,
+) -> Result<Word, EncodeError> {
+    let in_range = match oc {
+        Opcode::SLO
+        | Opcode::SHI
+        | Opcode::SLTUI
+        | Opcode::SGTUI
+        | Opcode::SLEUI
+        | Opcode::SGEUI => u16::try_from(immediate).is_ok(),
+        _ => i16::try_from(immediate).is_ok(),
+    };
+
+    if in_range {
+        Ok(make_i_instruction(oc, rd, rs1, immediate as Immediate))
+    } else {
+        Err(EncodeError::ImmediateOutOfRange(immediate))
+    }
+}
+
 /// Constructs an I-format instruction.
 #[macro_export]
 macro_rules! instr_i {
@@ -568,3 +701,241 @@ macro_rules! nop {
         instr_i!(NOP, ZERO, ZERO, 0)
     };
 }
+
+/// Error type for [`validate_instruction`].
+///
+/// [`validate_instruction`]: ./fn.validate_instruction.html
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum DecodeError {
+    /// The opcode bits do not correspond to any [`Opcode`](enum.Opcode.html).
+    InvalidOpcode(u32),
+
+    /// The funct bits of an [`Opcode::ALU`](enum.Opcode.html#variant.ALU) instruction do not correspond to any [`AluFunct`](enum.AluFunct.html).
+    InvalidAluFunct(u32),
+
+    /// The funct bits of an [`Opcode::FLOP`](enum.Opcode.html#variant.FLOP) instruction do not correspond to any [`FlopFunct`](enum.FlopFunct.html).
+    InvalidFlopFunct(u32),
+}
+
+/// Checks whether `instruction` can be decoded by the processor.
+///
+/// This catches the same opcodes and functs that would cause [`ExitCode::InvalidOpcode`] at runtime,
+/// but without having to execute the instruction.
+///
+/// # Errors
+/// Returns an error if the opcode is unknown, or if the funct of an `ALU` or `FLOP` instruction is unknown.
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+///
+/// assert_eq!(validate_instruction(instr_alu!(ADD, T0, T1, T2)), Ok(()));
+/// assert_eq!(
+///     validate_instruction(instr_r!(ALU, T0, T1, T2, 63)),
+///     Err(DecodeError::InvalidAluFunct(63))
+/// );
+/// ```
+/// [`ExitCode::InvalidOpcode`]: ./enum.ExitCode.html#variant.InvalidOpcode
+pub fn validate_instruction(instruction: Word) -> Result<(), DecodeError> {
+    let opcode_value = (instruction & constants::OPCODE_MASK) >> constants::OPCODE_OFFSET;
+    let funct_value = (instruction & constants::FUNCT_MASK) >> constants::FUNCT_OFFSET;
+
+    match Opcode::from_u32(opcode_value) {
+        Some(Opcode::ALU) => AluFunct::from_u32(funct_value)
+            .map(|_| ())
+            .ok_or(DecodeError::InvalidAluFunct(funct_value)),
+        Some(Opcode::FLOP) => FlopFunct::from_u32(funct_value)
+            .map(|_| ())
+            .ok_or(DecodeError::InvalidFlopFunct(funct_value)),
+        Some(_) => Ok(()),
+        None => Err(DecodeError::InvalidOpcode(opcode_value)),
+    }
+}
+
+/// Describes which register fields of an instruction are read and written (see [`register_roles`]).
+///
+/// [`register_roles`]: ./fn.register_roles.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct RegisterRoles {
+    /// `Rd` is read, e.g. the value stored by `SW`.
+    pub reads_rd: bool,
+    /// `Rd` is written. Writes to `$ZERO` are ignored by the processor.
+    pub writes_rd: bool,
+    pub reads_rs1: bool,
+    pub reads_rs2: bool,
+    /// Registers which are written without being encoded in the instruction, like `$RA` for `JL`.
+    pub implicit_writes: &'static [RegisterId],
+}
+
+/// Returns which register fields an instruction with the given `opcode` and `funct` reads and
+/// writes, e.g. for liveness analysis.
+///
+/// `funct` is only relevant for the [`Opcode::ALU`] instruction, for which `MUL` and `DIV` also
+/// write `$RM`. An unknown (or missing) `funct` is treated like any other `ALU` instruction.
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+///
+/// let roles = register_roles(Opcode::JR, None);
+/// assert!(roles.reads_rs1);
+/// assert!(!roles.writes_rd);
+/// ```
+/// [`Opcode::ALU`]: ./enum.Opcode.html#variant.ALU
+pub fn register_roles(opcode: Opcode, funct: Option<u32>) -> RegisterRoles {
+    const RM: &[RegisterId] = &[RegisterId::RM];
+    const RA: &[RegisterId] = &[RegisterId::RA];
+
+    let unary = RegisterRoles {
+        writes_rd: true,
+        reads_rs1: true,
+        ..Default::default()
+    };
+    let binary = RegisterRoles {
+        reads_rs2: true,
+        ..unary
+    };
+
+    match opcode {
+        Opcode::NOP | Opcode::HALT | Opcode::CALL | Opcode::JMP => RegisterRoles::default(),
+        Opcode::ALU => match funct.and_then(AluFunct::from_u32) {
+            Some(AluFunct::MUL) | Some(AluFunct::DIV) => RegisterRoles {
+                implicit_writes: RM,
+                ..binary
+            },
+            _ => binary,
+        },
+        Opcode::FLOP => binary,
+        Opcode::LI | Opcode::LHI => RegisterRoles {
+            writes_rd: true,
+            ..Default::default()
+        },
+        Opcode::SLO | Opcode::SHI => RegisterRoles {
+            reads_rd: true,
+            writes_rd: true,
+            ..Default::default()
+        },
+        Opcode::SB | Opcode::SH | Opcode::SW => RegisterRoles {
+            reads_rd: true,
+            reads_rs1: true,
+            ..Default::default()
+        },
+        Opcode::MULI | Opcode::DIVI => RegisterRoles {
+            implicit_writes: RM,
+            ..unary
+        },
+        Opcode::BEZ | Opcode::BNZ | Opcode::JR => RegisterRoles {
+            reads_rs1: true,
+            ..Default::default()
+        },
+        Opcode::JL => RegisterRoles {
+            implicit_writes: RA,
+            ..Default::default()
+        },
+        Opcode::JLR => RegisterRoles {
+            reads_rs1: true,
+            implicit_writes: RA,
+            ..Default::default()
+        },
+        // Loads, conversions and all other instructions with an immediate operand.
+        _ => unary,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn register_roles_of_add_and_sw() {
+        assert_eq!(
+            register_roles(Opcode::ALU, Some(enum_to_u32(AluFunct::ADD))),
+            RegisterRoles {
+                reads_rd: false,
+                writes_rd: true,
+                reads_rs1: true,
+                reads_rs2: true,
+                implicit_writes: &[],
+            }
+        );
+        assert_eq!(
+            register_roles(Opcode::SW, None),
+            RegisterRoles {
+                reads_rd: true,
+                writes_rd: false,
+                reads_rs1: true,
+                reads_rs2: false,
+                implicit_writes: &[],
+            }
+        );
+        assert_eq!(
+            register_roles(Opcode::ALU, Some(enum_to_u32(AluFunct::DIV))).implicit_writes,
+            &[RegisterId::RM]
+        );
+        assert_eq!(
+            register_roles(Opcode::LW, None),
+            register_roles(Opcode::ADDI, None)
+        );
+        assert_eq!(register_roles(Opcode::JMP, None), RegisterRoles::default());
+    }
+
+    #[test]
+    fn validate_valid_instructions() {
+        assert_eq!(validate_instruction(nop!()), Ok(()));
+        assert_eq!(validate_instruction(instr_alu!(SGEU, T0, T1, T2)), Ok(()));
+        assert_eq!(validate_instruction(instr_flop!(FDIV, T0, T1, T2)), Ok(()));
+        assert_eq!(validate_instruction(instr_j!(JMP, -4)), Ok(()));
+    }
+
+    #[test]
+    fn validate_invalid_opcode() {
+        assert_eq!(
+            validate_instruction(0xFFFF_FFFF),
+            Err(DecodeError::InvalidOpcode(63))
+        );
+    }
+
+    #[test]
+    fn validate_invalid_alu_funct() {
+        assert_eq!(
+            validate_instruction(instr_r!(ALU, T0, T1, T2, 42)),
+            Err(DecodeError::InvalidAluFunct(42))
+        );
+    }
+
+    #[test]
+    fn validate_invalid_flop_funct() {
+        assert_eq!(
+            validate_instruction(instr_r!(FLOP, T0, T1, T2, 42)),
+            Err(DecodeError::InvalidFlopFunct(42))
+        );
+    }
+
+    #[test]
+    fn try_make_i_instruction_in_range() {
+        assert_eq!(
+            try_make_i_instruction(Opcode::ADDI, RegisterId::T0, RegisterId::T1, -32768),
+            Ok(instr_i!(ADDI, T0, T1, -32768))
+        );
+        assert_eq!(
+            try_make_i_instruction(Opcode::SHI, RegisterId::T0, RegisterId::ZERO, 0xFFFF),
+            Ok(instr_i!(SHI, T0, ZERO, -1))
+        );
+    }
+
+    #[test]
+    fn try_make_i_instruction_out_of_range() {
+        assert_eq!(
+            try_make_i_instruction(Opcode::ADDI, RegisterId::T0, RegisterId::T1, 0x8000),
+            Err(EncodeError::ImmediateOutOfRange(0x8000))
+        );
+        assert_eq!(
+            try_make_i_instruction(Opcode::SLTUI, RegisterId::T0, RegisterId::T1, -1),
+            Err(EncodeError::ImmediateOutOfRange(-1))
+        );
+        assert_eq!(
+            try_make_i_instruction(Opcode::SLO, RegisterId::T0, RegisterId::ZERO, 0x10000),
+            Err(EncodeError::ImmediateOutOfRange(0x10000))
+        );
+    }
+}
@@ -1,10 +1,14 @@
 mod constants;
+mod hexdump;
 mod instructions;
 mod memory;
 mod processor;
 mod register;
 mod storage;
 
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+
 pub type Word = u32;
 pub type Immediate = i16;
 pub type Address = i32;
@@ -12,6 +16,7 @@ pub type Address = i32;
 pub type Endian = util::Endian;
 
 pub use crate::constants::*;
+pub use crate::hexdump::*;
 pub use crate::instructions::*;
 pub use crate::memory::*;
 pub use crate::processor::*;
@@ -1,5 +1,17 @@
 mod composite;
+mod fifo;
 mod io;
+#[cfg(feature = "memmap")]
+mod mmap;
+mod plain;
+mod read_only;
+mod tracing;
 
 pub use composite::*;
+pub use fifo::*;
 pub use io::*;
+#[cfg(feature = "memmap")]
+pub use mmap::*;
+pub use plain::*;
+pub use read_only::*;
+pub use tracing::*;
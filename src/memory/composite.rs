@@ -1,3 +1,4 @@
+use crate::storage::{fnv1a, FNV_OFFSET_BASIS};
 use crate::{Storage, StorageMut};
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
@@ -16,10 +17,29 @@ pub enum MountError {
 
     /// The mount operation failed because another fragment has already been mounted with the same key.
     KeyAlreadyExists,
+
+    /// The alias could not be mounted because no fragment is mounted with the target key.
+    TargetNotFound,
 }
 
 type AdressedFragment = (u32, Box<dyn StorageMut>);
 
+/// An address window which redirects accesses to another fragment (see [`mount_alias`]).
+///
+/// [`mount_alias`]: ./struct.CompositeMemory.html#method.mount_alias
+struct Alias {
+    address: u32,
+    length: u32,
+    key: String,
+    target_key: String,
+}
+
+impl Alias {
+    fn contains(&self, address: u32) -> bool {
+        address >= self.address && address - self.address < self.length
+    }
+}
+
 /// Represents a [`StorageMut`] which consists of "fragments" instead of one contiguous block of memory.
 ///
 /// Fragments are simply represented by [`StorageMut`] trait objects and can be "mounted" at a certain address.
@@ -27,14 +47,19 @@ type AdressedFragment = (u32, Box<dyn StorageMut>);
 ///
 /// The length of a `CompositeMemory` is equal to the last fragment's address plus its length.
 ///
+/// Fragments are always kept sorted by their address, so the index of a fragment
+/// (as returned by [`mount`]) is its position in that order.
+///
 /// Nesting `CompositeMemory` objects is possible, though not recommended since the speed of lookups will suffer.
 /// Flattening the nested objects into one `CompositeMemory` instance is preferable.
 ///
 /// [`StorageMut`]: ../trait.StorageMut.html
+/// [`mount`]: #method.mount
 #[derive(Default)]
 pub struct CompositeMemory {
     fragments: Vec<AdressedFragment>,
     registry: HashMap<String, usize>,
+    aliases: Vec<Alias>,
 }
 
 impl CompositeMemory {
@@ -47,6 +72,9 @@ impl CompositeMemory {
     ///
     /// The `fragment` will occupy the address range `[address..address+fragment.length()]`.
     ///
+    /// Returns the index at which the fragment was inserted into the address-ordered list of
+    /// fragments (see [`iter`]). The indices of fragments at higher addresses are shifted by one.
+    ///
     /// # Errors
     /// Returns an error if another fragment has already been mounted using the specified `key`.
     ///
@@ -64,7 +92,7 @@ impl CompositeMemory {
     /// let mut memory = CompositeMemory::new();
     /// assert_eq!(
     ///     memory.mount(16, "f0", [0u8, 1u8, 2u8, 3u8]),
-    ///     Ok(())
+    ///     Ok(0)
     /// );
     /// assert_eq!(memory.read_word(16), Ok(50462976));
     /// assert_eq!(memory.read_byte(0), Err(()));
@@ -76,12 +104,12 @@ impl CompositeMemory {
     ///
     /// let mut memory = CompositeMemory::new();
     /// assert_eq!(
-    ///     memory.mount(0, "f0", [0u8; 16]),
-    ///     Ok(())
+    ///     memory.mount(16, "f1", [0u8; 16]),
+    ///     Ok(0)
     /// );
     /// assert_eq!(
-    ///     memory.mount(16, "f1", [0u8; 16]),
-    ///     Ok(())
+    ///     memory.mount(0, "f0", [0u8; 16]),
+    ///     Ok(0)
     /// );
     /// ```
     ///
@@ -92,20 +120,21 @@ impl CompositeMemory {
     /// let mut memory = CompositeMemory::new();
     /// assert_eq!(
     ///     memory.mount(0, "f0", [0u8; 16]),
-    ///     Ok(())
+    ///     Ok(0)
     /// );
     /// assert_eq!(
     ///     memory.mount(15, "f1", [0u8; 16]),
     ///     Err(MountError::FragmentIntersection)
     /// );
     /// ```
+    /// [`iter`]: #method.iter
     pub fn mount<S: StorageMut + 'static>(
         &mut self,
         address: u32,
         key: &str,
         fragment: S,
-    ) -> Result<(), MountError> {
-        if self.registry.contains_key(key) {
+    ) -> Result<usize, MountError> {
+        if self.has_key(key) {
             return Err(MountError::KeyAlreadyExists);
         }
 
@@ -113,11 +142,17 @@ impl CompositeMemory {
             .checked_add(fragment.length())
             .expect("Fragment upper bound exceeds valid address range.");
         let index = self.find_mount_index(address, upper_bound)?;
+        if self.intersects_alias(address, upper_bound) {
+            return Err(MountError::FragmentIntersection);
+        }
 
         self.fragments.insert(index, (address, Box::new(fragment)));
+        for i in self.registry.values_mut().filter(|i| **i >= index) {
+            *i += 1;
+        }
         self.registry.insert(key.to_string(), index);
 
-        Ok(())
+        Ok(index)
     }
 
     /// Looks for a fragment mounted as `key` and if found, unmounts and returns it as `Some`.
@@ -130,16 +165,135 @@ impl CompositeMemory {
     /// let mut memory = CompositeMemory::new();
     /// assert_eq!(
     ///     memory.mount(0, "f0", [0u8; 16]),
-    ///     Ok(())
+    ///     Ok(0)
     /// );
     /// assert!(memory.unmount("something").is_none());
     /// assert!(memory.unmount("f0").is_some());
     /// assert!(memory.unmount("f0").is_none());
     /// ```
     pub fn unmount(&mut self, key: &str) -> Option<Box<dyn StorageMut>> {
+        let index = self.registry.remove(key)?;
+        for i in self.registry.values_mut().filter(|i| **i > index) {
+            *i -= 1;
+        }
+        Some(self.fragments.remove(index).1)
+    }
+
+    /// Mounts an alias of the fragment registered as `target_key` at the specified `address`,
+    /// registered with the specified `key`. This makes the same fragment accessible at a
+    /// second address range, without copying it.
+    ///
+    /// The alias occupies the address range `[address..address+length]`, where `length` is the
+    /// length of the target fragment at the time of mounting. Aliases can't be nested.
+    ///
+    /// If the target fragment is unmounted, accesses through the alias fail. Aliases are
+    /// unmounted with [`unmount_alias`].
+    ///
+    /// # Errors
+    /// Returns an error if a fragment or alias has already been mounted using the specified
+    /// `key`, if no fragment is mounted as `target_key`, or if the alias would intersect another
+    /// fragment or alias.
+    ///
+    /// # Panics
+    /// Panics if `address + length` results in integer overflow.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::{CompositeMemory, MountError, Storage, StorageMut};
+    ///
+    /// let mut memory = CompositeMemory::new();
+    /// memory.mount(0, "ram", [0u8; 16]).unwrap();
+    /// assert_eq!(memory.mount_alias(0x100, "mirror", "ram"), Ok(()));
+    ///
+    /// assert_eq!(memory.write_byte(0x104, 42), Ok(()));
+    /// assert_eq!(memory.read_byte(4), Ok(42));
+    /// assert_eq!(
+    ///     memory.mount_alias(0x200, "other", "rom"),
+    ///     Err(MountError::TargetNotFound)
+    /// );
+    /// ```
+    /// [`unmount_alias`]: #method.unmount_alias
+    pub fn mount_alias(
+        &mut self,
+        address: u32,
+        key: &str,
+        target_key: &str,
+    ) -> Result<(), MountError> {
+        if self.has_key(key) {
+            return Err(MountError::KeyAlreadyExists);
+        }
+
+        let target = *self
+            .registry
+            .get(target_key)
+            .ok_or(MountError::TargetNotFound)?;
+        let length = self.fragments[target].1.length();
+        let upper_bound = address
+            .checked_add(length)
+            .expect("Alias upper bound exceeds valid address range.");
+
+        self.find_mount_index(address, upper_bound)?;
+        if self.intersects_alias(address, upper_bound) {
+            return Err(MountError::FragmentIntersection);
+        }
+
+        self.aliases.push(Alias {
+            address,
+            length,
+            key: key.to_string(),
+            target_key: target_key.to_string(),
+        });
+
+        Ok(())
+    }
+
+    /// Unmounts the alias mounted as `key`. Returns `false` if no such alias was found.
+    pub fn unmount_alias(&mut self, key: &str) -> bool {
+        let count = self.aliases.len();
+        self.aliases.retain(|alias| alias.key != key);
+        self.aliases.len() != count
+    }
+
+    /// Returns an iterator over the mounted fragments and their addresses,
+    /// in ascending order of address.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::{CompositeMemory, Storage};
+    ///
+    /// let mut memory = CompositeMemory::new();
+    /// memory.mount(32, "f1", [0u8; 4]).unwrap();
+    /// memory.mount(0, "f0", [0u8; 16]).unwrap();
+    ///
+    /// let fragments: Vec<_> = memory.iter().map(|(address, f)| (address, f.length())).collect();
+    /// assert_eq!(fragments, vec![(0, 16), (32, 4)]);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &dyn StorageMut)> {
+        self.fragments
+            .iter()
+            .map(|(address, fragment)| (*address, fragment.deref()))
+    }
+
+    /// Returns the key of the fragment at `index` in the address-ordered list of fragments
+    /// (see [`iter`]), or `None` if `index` is out of range.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::CompositeMemory;
+    ///
+    /// let mut memory = CompositeMemory::new();
+    /// memory.mount(32, "f1", [0u8; 4]).unwrap();
+    /// memory.mount(0, "f0", [0u8; 16]).unwrap();
+    ///
+    /// assert_eq!(memory.fragment_key(1), Some("f1"));
+    /// assert_eq!(memory.fragment_key(2), None);
+    /// ```
+    /// [`iter`]: #method.iter
+    pub fn fragment_key(&self, index: usize) -> Option<&str> {
         self.registry
-            .remove(key)
-            .map(|i| self.fragments.remove(i).1)
+            .iter()
+            .find(|(_, i)| **i == index)
+            .map(|(key, _)| key.as_str())
     }
 
     fn find_mount_index(&self, address: u32, upper_bound: u32) -> Result<usize, MountError> {
@@ -171,35 +325,56 @@ impl CompositeMemory {
         }
     }
 
-    fn get_fragment(&self, address: u32) -> Option<(&dyn StorageMut, u32)> {
-        let index = self.get_index(address)?;
-        if index >= self.fragments.len() {
-            return None;
-        }
+    fn has_key(&self, key: &str) -> bool {
+        self.registry.contains_key(key) || self.aliases.iter().any(|alias| alias.key == key)
+    }
 
-        let (frag_addr, fragment) = &self.fragments[index];
-        Some((fragment.deref(), address - frag_addr))
+    fn intersects_alias(&self, address: u32, upper_bound: u32) -> bool {
+        self.aliases
+            .iter()
+            .any(|alias| address < alias.address + alias.length && alias.address < upper_bound)
     }
 
-    fn get_fragment_mut(&mut self, address: u32) -> Option<(&mut dyn StorageMut, u32)> {
+    /// Returns the index of the fragment responsible for `address` (following aliases)
+    /// together with the address relative to the fragment.
+    fn resolve(&self, address: u32) -> Option<(usize, u32)> {
+        if let Some(alias) = self.aliases.iter().find(|alias| alias.contains(address)) {
+            let index = *self.registry.get(&alias.target_key)?;
+            return Some((index, address - alias.address));
+        }
+
         let index = self.get_index(address)?;
         if index >= self.fragments.len() {
             return None;
         }
 
-        let (frag_addr, fragment) = &mut self.fragments[index];
-        Some((fragment.deref_mut(), address - *frag_addr))
+        Some((index, address - self.fragments[index].0))
+    }
+
+    fn get_fragment(&self, address: u32) -> Option<(&dyn StorageMut, u32)> {
+        let (index, local_address) = self.resolve(address)?;
+        Some((self.fragments[index].1.deref(), local_address))
+    }
+
+    fn get_fragment_mut(&mut self, address: u32) -> Option<(&mut dyn StorageMut, u32)> {
+        let (index, local_address) = self.resolve(address)?;
+        Some((self.fragments[index].1.deref_mut(), local_address))
     }
 }
 
 impl Storage for CompositeMemory {
     fn length(&self) -> u32 {
-        if !self.fragments.is_empty() {
+        let fragments_end = if !self.fragments.is_empty() {
             let (address, frag) = &self.fragments[self.fragments.len() - 1];
             address + frag.length()
         } else {
             0
-        }
+        };
+
+        self.aliases
+            .iter()
+            .map(|alias| alias.address + alias.length)
+            .fold(fragments_end, u32::max)
     }
 
     fn check_range(&self, address: u32, length: u32) -> bool {
@@ -214,6 +389,15 @@ impl Storage for CompositeMemory {
         let (fragment, local_address) = self.get_fragment(address).ok_or(())?;
         fragment.read(local_address, size)
     }
+
+    /// Unlike the other accesses, the range may span several fragments,
+    /// as long as they are mounted without gaps between them.
+    fn checksum(&self, address: u32, length: u32) -> Result<u64, ()> {
+        let end = address.checked_add(length).ok_or(())?;
+        (address..end).try_fold(FNV_OFFSET_BASIS, |hash, a| {
+            Ok(fnv1a(hash, self.read_byte(a)?))
+        })
+    }
 }
 
 impl StorageMut for CompositeMemory {
@@ -221,18 +405,73 @@ impl StorageMut for CompositeMemory {
         let (fragment, local_address) = self.get_fragment_mut(address).ok_or(())?;
         fragment.write(local_address, size, value)
     }
+
+    fn write_io(&mut self, address: u32, size: u32, value: u32) -> Result<bool, ()> {
+        let (fragment, local_address) = self.get_fragment_mut(address).ok_or(())?;
+        fragment.write_io(local_address, size, value)
+    }
+
+    /// Like [`checksum`], the range may span several fragments, as long as they are mounted
+    /// without gaps between them and all of them are writable.
+    ///
+    /// [`checksum`]: #method.checksum
+    fn is_writable(&self, address: u32, length: u32) -> bool {
+        let mut address = address;
+        let mut remaining = length;
+
+        loop {
+            let (fragment, local_address) = match self.get_fragment(address) {
+                Some(found) => found,
+                None => return false,
+            };
+            let part = remaining.min(fragment.length().saturating_sub(local_address));
+            if !fragment.is_writable(local_address, part) {
+                return false;
+            }
+
+            remaining -= part;
+            if remaining == 0 {
+                return true;
+            }
+            if part == 0 {
+                return false;
+            }
+            address = match address.checked_add(part) {
+                Some(next) => next,
+                None => return false,
+            };
+        }
+    }
+
+    /// Zeroes each fragment on its own (see [`StorageMut::zero_fill`]), so IO devices are left
+    /// unchanged and unmapped ranges are skipped.
+    ///
+    /// [`StorageMut::zero_fill`]: ../trait.StorageMut.html#method.zero_fill
+    fn zero_fill(&mut self) {
+        for (_, fragment) in &mut self.fragments {
+            fragment.zero_fill();
+        }
+    }
+
+    /// Borrows a range from a single fragment.
+    ///
+    /// Returns an error if the range spans more than one fragment.
+    fn borrow_slice_mut(&mut self, address: u32, length: u32) -> Result<&mut [u8], ()> {
+        let (fragment, local_address) = self.get_fragment_mut(address).ok_or(())?;
+        fragment.borrow_slice_mut(local_address, length)
+    }
 }
 
 #[test]
 fn find_mount_index() {
     let mut memory = CompositeMemory::new();
     assert_eq!(memory.find_mount_index(0, 16), Ok(0));
-    assert_eq!(memory.mount(0, "f0", [0u8; 16]), Ok(()));
+    assert_eq!(memory.mount(0, "f0", [0u8; 16]), Ok(0));
     assert_eq!(
         memory.find_mount_index(8, 24),
         Err(MountError::FragmentIntersection)
     );
-    assert_eq!(memory.mount(20, "f1", [0u8; 16]), Ok(()));
+    assert_eq!(memory.mount(20, "f1", [0u8; 16]), Ok(1));
     assert_eq!(memory.find_mount_index(16, 20), Ok(1));
     assert_eq!(memory.find_mount_index(18, 20), Ok(1));
     assert_eq!(memory.find_mount_index(40, 44), Ok(2));
@@ -249,8 +488,8 @@ fn find_mount_index() {
 #[test]
 fn access_mounted_fragment() {
     let mut comp = CompositeMemory::new();
-    assert_eq!(comp.mount(0, "f0", vec![0u8; 64]), Ok(()));
-    assert_eq!(comp.mount(0xF1ED_0000, "f1", vec![0u8; 1]), Ok(()));
+    assert_eq!(comp.mount(0, "f0", vec![0u8; 64]), Ok(0));
+    assert_eq!(comp.mount(0xF1ED_0000, "f1", vec![0u8; 1]), Ok(1));
     assert_eq!(comp.write_byte(0xF1ED_0001, 1), Err(()));
     assert_eq!(comp.write_byte(0xF1ED_0000, 1), Ok(()));
     let unmount_result = comp.unmount("f1");
@@ -258,3 +497,124 @@ fn access_mounted_fragment() {
     let fragment = unmount_result.unwrap();
     assert_eq!(fragment.read_byte(0), Ok(1));
 }
+
+#[test]
+fn borrow_slice_mut() {
+    let mut comp = CompositeMemory::new();
+    assert_eq!(comp.mount(0, "f0", vec![0u8; 16]), Ok(0));
+    assert_eq!(comp.mount(16, "f1", vec![0u8; 16]), Ok(1));
+
+    let slice = comp.borrow_slice_mut(20, 8).unwrap();
+    assert_eq!(slice.len(), 8);
+    slice[4..8].copy_from_slice(&[0xEF, 0xBE, 0xAD, 0xDE]);
+    assert_eq!(comp.read_word(24), Ok(0xDEAD_BEEF));
+
+    assert!(comp.borrow_slice_mut(12, 8).is_err());
+    assert!(comp.borrow_slice_mut(40, 1).is_err());
+}
+
+#[test]
+fn iter_sorted_by_address() {
+    let mut comp = CompositeMemory::new();
+    assert_eq!(comp.mount(0x200, "c", vec![3u8; 4]), Ok(0));
+    assert_eq!(comp.mount(0x000, "a", vec![1u8; 4]), Ok(0));
+    assert_eq!(comp.mount(0x100, "b", vec![2u8; 4]), Ok(1));
+
+    let fragments: Vec<_> = comp
+        .iter()
+        .map(|(address, fragment)| (address, fragment.read_byte(0).unwrap()))
+        .collect();
+    assert_eq!(fragments, vec![(0x000, 1), (0x100, 2), (0x200, 3)]);
+}
+
+#[test]
+fn unmount_after_out_of_order_mount() {
+    let mut comp = CompositeMemory::new();
+    comp.mount(0x100, "b", vec![2u8; 4]).unwrap();
+    comp.mount(0x000, "a", vec![1u8; 4]).unwrap();
+    comp.mount(0x200, "c", vec![3u8; 4]).unwrap();
+
+    assert_eq!(comp.unmount("b").unwrap().read_byte(0), Ok(2));
+    assert_eq!(comp.unmount("c").unwrap().read_byte(0), Ok(3));
+    assert_eq!(comp.unmount("a").unwrap().read_byte(0), Ok(1));
+    assert_eq!(comp.iter().count(), 0);
+}
+
+#[test]
+fn checksum_across_fragments() {
+    let mut comp = CompositeMemory::new();
+    assert_eq!(comp.mount(0, "f0", vec![0u8; 16]), Ok(0));
+    assert_eq!(comp.mount(16, "f1", vec![0u8; 16]), Ok(1));
+    assert_eq!(comp.mount(64, "f2", vec![0u8; 16]), Ok(2));
+
+    let mut plain = vec![0u8; 32];
+    for address in 8..24 {
+        comp.write_byte(address, address as u8).unwrap();
+        plain.write_byte(address, address as u8).unwrap();
+    }
+
+    assert_eq!(comp.checksum(4, 24), plain.checksum(4, 24));
+    assert_eq!(comp.checksum(0, 32), plain.checksum(0, 32));
+
+    comp.write_byte(17, 0xFF).unwrap();
+    assert_ne!(comp.checksum(4, 24), plain.checksum(4, 24));
+    assert_eq!(comp.checksum(18, 6), plain.checksum(18, 6));
+
+    assert_eq!(comp.checksum(24, 16), Err(()));
+    assert_eq!(comp.checksum(u32::MAX, 2), Err(()));
+}
+
+#[test]
+fn alias() {
+    let mut memory = CompositeMemory::new();
+    assert_eq!(memory.mount(0, "ram", vec![0u8; 64]), Ok(0));
+    assert_eq!(memory.mount_alias(0x8000_0000, "ram_mirror", "ram"), Ok(()));
+    assert_eq!(memory.length(), 0x8000_0040);
+
+    assert_eq!(memory.write_word(0x8000_0010, 0xDEAD_BEEF), Ok(()));
+    assert_eq!(memory.read_word(0x10), Ok(0xDEAD_BEEF));
+    assert_eq!(memory.write_half(0x20, 0x1234), Ok(()));
+    assert_eq!(memory.read_half(0x8000_0020), Ok(0x1234));
+
+    assert_eq!(memory.read_word(0x8000_003E), Err(()));
+    assert_eq!(memory.read_byte(0x8000_0040), Err(()));
+
+    assert_eq!(
+        memory.mount(0x8000_0020, "io", [0u8; 4]),
+        Err(MountError::FragmentIntersection)
+    );
+    assert_eq!(
+        memory.mount_alias(0x20, "overlap", "ram"),
+        Err(MountError::FragmentIntersection)
+    );
+    assert_eq!(
+        memory.mount_alias(0x100, "ram_mirror", "ram"),
+        Err(MountError::KeyAlreadyExists)
+    );
+    assert_eq!(
+        memory.mount_alias(0x100, "rom_mirror", "rom"),
+        Err(MountError::TargetNotFound)
+    );
+
+    assert!(memory.unmount_alias("ram_mirror"));
+    assert!(!memory.unmount_alias("ram_mirror"));
+    assert_eq!(memory.read_word(0x8000_0010), Err(()));
+    assert_eq!(memory.length(), 64);
+}
+
+#[test]
+fn try_write_from_across_fragments() {
+    let mut comp = CompositeMemory::new();
+    assert_eq!(comp.mount(0, "f0", vec![0u8; 8]), Ok(0));
+    assert_eq!(comp.mount(8, "f1", vec![0u8; 8]), Ok(1));
+
+    assert!(comp.is_writable(4, 12));
+    assert_eq!(comp.try_write_from(6, &[1, 2, 3, 4]), Ok(()));
+
+    // The range straddles the end of the mapped memory, so nothing may be written.
+    assert!(!comp.is_writable(12, 8));
+    assert_eq!(comp.try_write_from(12, &[9; 8]), Err(()));
+    assert_eq!(comp.read_word(12), Ok(0));
+    let bytes: Vec<_> = (0..16).map(|a| comp.read_byte(a).unwrap()).collect();
+    assert_eq!(bytes, [0, 0, 0, 0, 0, 0, 1, 2, 3, 4, 0, 0, 0, 0, 0, 0]);
+}
@@ -0,0 +1,203 @@
+use crate::{Endian, Storage, StorageMut};
+use byteorder::ByteOrder;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+
+/// Represents a [`StorageMut`] which models a simple byte FIFO device (e.g. a UART).
+///
+/// The device consists of two word-sized registers:
+///
+/// Offset | Register | Description
+/// -------|----------|------------
+/// `0`    | Data     | Writing pushes the least significant byte onto the queue (it is dropped if the queue is full). Reading pops a byte from the queue (or returns `0` if the queue is empty).
+/// `4`    | Status   | Read only. Bit `0` is set if the queue is empty, bit `1` is set if it is full.
+///
+/// Note that *every* read of the data register pops a byte, including reads by debugging tools.
+///
+/// [`StorageMut`]: ../trait.StorageMut.html
+pub struct FifoDevice {
+    queue: RefCell<VecDeque<u8>>,
+    capacity: usize,
+}
+
+impl FifoDevice {
+    /// Address of the data register.
+    pub const DATA_REGISTER: u32 = 0;
+    /// Address of the status register.
+    pub const STATUS_REGISTER: u32 = 4;
+    /// Status bit which is set while the queue is empty.
+    pub const STATUS_EMPTY: u32 = 0b01;
+    /// Status bit which is set while the queue is full.
+    pub const STATUS_FULL: u32 = 0b10;
+
+    const LENGTH: u32 = 8;
+
+    /// Constructs a device whose queue can hold up to `capacity` bytes.
+    pub fn new(capacity: u32) -> FifoDevice {
+        FifoDevice {
+            queue: RefCell::new(VecDeque::with_capacity(capacity as usize)),
+            capacity: capacity as usize,
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity as u32
+    }
+
+    /// Returns the number of bytes currently in the queue.
+    pub fn len(&self) -> u32 {
+        self.queue.borrow().len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.borrow().is_empty()
+    }
+
+    /// Pushes `value` onto the queue from the host side. Returns `false` if the queue is full.
+    pub fn push(&mut self, value: u8) -> bool {
+        let queue = self.queue.get_mut();
+        if queue.len() < self.capacity {
+            queue.push_back(value);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Pops a byte from the queue from the host side.
+    pub fn pop(&mut self) -> Option<u8> {
+        self.queue.get_mut().pop_front()
+    }
+
+    fn status(&self) -> u32 {
+        let len = self.queue.borrow().len();
+        let mut status = 0;
+
+        if len == 0 {
+            status |= FifoDevice::STATUS_EMPTY;
+        }
+
+        if len >= self.capacity {
+            status |= FifoDevice::STATUS_FULL;
+        }
+
+        status
+    }
+}
+
+impl Storage for FifoDevice {
+    fn length(&self) -> u32 {
+        FifoDevice::LENGTH
+    }
+
+    fn check_range(&self, address: u32, length: u32) -> bool {
+        address <= FifoDevice::LENGTH && length <= FifoDevice::LENGTH - address
+    }
+
+    fn read(&self, address: u32, size: u32) -> Result<u32, ()> {
+        assert!((1..=4).contains(&size));
+
+        if !self.check_range(address, size) {
+            return Err(());
+        }
+
+        if address < FifoDevice::STATUS_REGISTER {
+            Ok(self.queue.borrow_mut().pop_front().unwrap_or(0).into())
+        } else {
+            let mut status = [0u8; 4];
+            Endian::write_u32(&mut status, self.status());
+            status.read(address - FifoDevice::STATUS_REGISTER, size)
+        }
+    }
+}
+
+impl StorageMut for FifoDevice {
+    fn write(&mut self, address: u32, size: u32, value: u32) -> Result<(), ()> {
+        assert!((1..=4).contains(&size));
+
+        if !self.check_range(address, size) {
+            return Err(());
+        }
+
+        if address < FifoDevice::STATUS_REGISTER {
+            self.push(value as u8);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FifoDevice;
+    use crate::*;
+
+    #[test]
+    fn fifo_order() {
+        let mut fifo = FifoDevice::new(4);
+
+        for byte in b"abc" {
+            assert_eq!(fifo.write_byte(FifoDevice::DATA_REGISTER, *byte), Ok(()));
+        }
+
+        assert_eq!(fifo.len(), 3);
+
+        for byte in b"abc" {
+            assert_eq!(fifo.read_byte(FifoDevice::DATA_REGISTER), Ok(*byte));
+        }
+
+        assert_eq!(fifo.read_byte(FifoDevice::DATA_REGISTER), Ok(0));
+        assert!(fifo.is_empty());
+    }
+
+    #[test]
+    fn status_transitions() {
+        let mut fifo = FifoDevice::new(2);
+        let status = |fifo: &FifoDevice| fifo.read_word(FifoDevice::STATUS_REGISTER).unwrap();
+
+        assert_eq!(status(&fifo), FifoDevice::STATUS_EMPTY);
+
+        fifo.write_byte(FifoDevice::DATA_REGISTER, 1).unwrap();
+        assert_eq!(status(&fifo), 0);
+
+        fifo.write_byte(FifoDevice::DATA_REGISTER, 2).unwrap();
+        assert_eq!(status(&fifo), FifoDevice::STATUS_FULL);
+
+        fifo.write_byte(FifoDevice::DATA_REGISTER, 3).unwrap();
+        assert_eq!(fifo.len(), 2);
+
+        assert_eq!(fifo.read_byte(FifoDevice::DATA_REGISTER), Ok(1));
+        assert_eq!(status(&fifo), 0);
+
+        assert_eq!(fifo.read_byte(FifoDevice::DATA_REGISTER), Ok(2));
+        assert_eq!(status(&fifo), FifoDevice::STATUS_EMPTY);
+
+        assert_eq!(fifo.write_word(FifoDevice::STATUS_REGISTER, 0), Ok(()));
+        assert_eq!(status(&fifo), FifoDevice::STATUS_EMPTY);
+        assert_eq!(fifo.read_byte(8), Err(()));
+    }
+
+    #[test]
+    fn mounted_in_composite_memory() {
+        let instructions = instructions_from_words(&[
+            instr_i!(LI, T0, ZERO, 0x41),
+            instr_i!(SB, T0, ZERO, 0x100),
+            instr_i!(LI, T0, ZERO, 0x42),
+            instr_i!(SB, T0, ZERO, 0x100),
+            instr_i!(LB, T1, ZERO, 0x100),
+            instr_i!(LW, T2, ZERO, 0x104),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+
+        let mut memory = CompositeMemory::new();
+        memory.mount(0x100, "fifo", FifoDevice::new(16)).unwrap();
+
+        let mut processor = Processor::default();
+        assert_eq!(processor.run(&instructions, &mut memory), ExitCode::Halted);
+
+        assert_eq!(processor.register(RegisterId::T1).u(), 0x41);
+        assert_eq!(processor.register(RegisterId::T2).u(), 0);
+        assert_eq!(memory.read_byte(0x100), Ok(0x42));
+        assert_eq!(memory.read_word(0x104), Ok(FifoDevice::STATUS_EMPTY));
+    }
+}
@@ -27,6 +27,35 @@ impl<H: IOHandler> IOMemory<H> {
         &mut self.memory
     }
 
+    /// Writes `value` to the backing memory like [`write`], but without consulting or notifying
+    /// the handler, e.g. to provide input which the program reads from an IO register.
+    ///
+    /// # Panics
+    /// Panics if `size < 1` or `size > 4`.
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+size]` is not addressable.
+    ///
+    /// [`write`]: ../trait.StorageMut.html#tymethod.write
+    pub fn poke(&mut self, address: u32, size: u32, value: u32) -> Result<(), ()> {
+        self.memory.write(address, size, value)
+    }
+
+    /// Reads from the backing memory. Reads never involve the handler, so this is the same as
+    /// [`read`], provided for symmetry with [`poke`].
+    ///
+    /// # Panics
+    /// Panics if `size < 1` or `size > 4`.
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+size]` is not addressable.
+    ///
+    /// [`read`]: ../trait.Storage.html#tymethod.read
+    /// [`poke`]: #method.poke
+    pub fn peek(&self, address: u32, size: u32) -> Result<u32, ()> {
+        self.memory.read(address, size)
+    }
+
     pub fn resize(&mut self, size: u32) {
         self.memory.resize(size as usize, u8::default())
     }
@@ -48,11 +77,22 @@ impl<H: IOHandler> Storage for IOMemory<H> {
 
 impl<H: IOHandler> StorageMut for IOMemory<H> {
     fn write(&mut self, address: u32, size: u32, value: u32) -> Result<(), ()> {
+        self.write_io(address, size, value).map(|_| ())
+    }
+
+    fn write_io(&mut self, address: u32, size: u32, value: u32) -> Result<bool, ()> {
         if self.handler.can_write(&self.memory, address, size) {
             self.memory.write(address, size, value)?;
             self.handler.on_write(&self.memory, address, size);
+            Ok(true)
+        } else {
+            Ok(false)
         }
-        Ok(())
+    }
+
+    fn is_writable(&self, address: u32, length: u32) -> bool {
+        self.memory.check_range(address, length)
+            && self.handler.can_write(&self.memory, address, length)
     }
 }
 
@@ -128,4 +168,72 @@ mod tests {
         assert_eq!(address, 4u32);
         assert_eq!(value, 923u32);
     }
+
+    #[test]
+    fn run_until_io() {
+        let handler = DelegateIOHandler::new(|_, address, _| address < 4, |_, _, _| {});
+
+        let instructions = instructions_from_words(&[
+            instr_i!(LI, T0, ZERO, 65),
+            instr_i!(SW, T0, ZERO, 0),
+            instr_i!(SB, T0, ZERO, 0x104),
+            instr_i!(SH, T0, ZERO, 0x102),
+            instr_i!(SB, T0, ZERO, 4),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+
+        let mut memory = CompositeMemory::new();
+        memory.mount(0, "ram", [0u8; 8]).unwrap();
+        memory
+            .mount(0x100, "io", IOMemory::new(8, handler))
+            .unwrap();
+
+        assert!(memory.is_writable(0x102, 2));
+        assert!(memory.check_range(0x104, 1));
+        assert!(!memory.is_writable(0x104, 1));
+
+        let mut processor = Processor::default();
+
+        assert_eq!(
+            processor.run_until_io(&instructions, &mut memory),
+            RunOutcome::IoWrite {
+                address: 0x102,
+                size: 2
+            }
+        );
+        assert_eq!(processor.program_counter(), 16);
+        assert_eq!(memory.read_byte(0x104), Ok(0));
+        assert_eq!(memory.read_half(0x102), Ok(65));
+
+        assert_eq!(
+            processor.run_until_io(&instructions, &mut memory),
+            RunOutcome::Stopped(ExitCode::Halted)
+        );
+        assert_eq!(memory.read_word(0), Ok(65));
+        assert_eq!(memory.read_byte(4), Ok(65));
+    }
+
+    #[test]
+    fn poke_input() {
+        let writes = Rc::new(Cell::new(0));
+        let wref = Rc::clone(&writes);
+
+        let handler =
+            DelegateIOHandler::new(|_, _, _| true, move |_, _, _| wref.set(wref.get() + 1));
+
+        let instructions =
+            instructions_from_words(&[instr_i!(LW, T0, ZERO, 8), instr_i!(HALT, ZERO, ZERO, 0)]);
+
+        let mut processor = Processor::default();
+        let mut memory = IOMemory::new(16, handler);
+
+        assert_eq!(memory.poke(8, 4, 0xCAFE_F00D), Ok(()));
+        assert_eq!(memory.peek(8, 4), Ok(0xCAFE_F00D));
+        assert_eq!(memory.poke(14, 4, 1), Err(()));
+        assert_eq!(memory.peek(14, 4), Err(()));
+
+        assert_eq!(processor.run(&instructions, &mut memory), ExitCode::Halted);
+        assert_eq!(processor.register(RegisterId::T0).u(), 0xCAFE_F00D);
+        assert_eq!(writes.get(), 0);
+    }
 }
@@ -0,0 +1,113 @@
+use crate::{Storage, StorageMut};
+use memmap2::MmapMut;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+/// Represents a [`StorageMut`] which is backed by a memory-mapped file.
+///
+/// This allows very large memories to be simulated without keeping them in main memory,
+/// and to share their contents between runs. Writes go directly to the mapped pages and are
+/// written back to the file by the operating system, or explicitly by calling [`flush`].
+///
+/// [`StorageMut`]: ../trait.StorageMut.html
+/// [`flush`]: #method.flush
+pub struct MmapStorage {
+    map: MmapMut,
+}
+
+impl MmapStorage {
+    /// Opens (or creates) the file at `path`, resizes it to `size` bytes and maps it into memory.
+    pub fn open<P: AsRef<Path>>(path: P, size: u32) -> io::Result<MmapStorage> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.set_len(u64::from(size))?;
+        MmapStorage::from_file(&file)
+    }
+
+    /// Maps the whole of `file` into memory. The file must be opened for reading and writing.
+    pub fn from_file(file: &File) -> io::Result<MmapStorage> {
+        let map = unsafe { MmapMut::map_mut(file)? };
+        Ok(MmapStorage { map })
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.map
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.map
+    }
+
+    /// Writes all outstanding modifications back to the underlying file.
+    pub fn flush(&self) -> io::Result<()> {
+        self.map.flush()
+    }
+}
+
+impl Storage for MmapStorage {
+    fn length(&self) -> u32 {
+        self.data().length()
+    }
+
+    fn check_range(&self, address: u32, length: u32) -> bool {
+        self.data().check_range(address, length)
+    }
+
+    fn read(&self, address: u32, size: u32) -> Result<u32, ()> {
+        self.data().read(address, size)
+    }
+
+    fn checksum(&self, address: u32, length: u32) -> Result<u64, ()> {
+        self.data().checksum(address, length)
+    }
+}
+
+impl StorageMut for MmapStorage {
+    fn write(&mut self, address: u32, size: u32, value: u32) -> Result<(), ()> {
+        self.data_mut().write(address, size, value)
+    }
+
+    fn borrow_slice_mut(&mut self, address: u32, length: u32) -> Result<&mut [u8], ()> {
+        self.map.borrow_slice_mut(address, length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MmapStorage;
+    use crate::*;
+
+    #[test]
+    fn write_remap_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.bin");
+
+        {
+            let mut storage = MmapStorage::open(&path, 64).unwrap();
+            assert_eq!(storage.length(), 64);
+            assert_eq!(storage.write_word(8, 0xDEAD_BEEF), Ok(()));
+            storage.flush().unwrap();
+        }
+
+        let storage = MmapStorage::open(&path, 64).unwrap();
+        assert_eq!(storage.read_word(8), Ok(0xDEAD_BEEF));
+        assert_eq!(storage.read_word(0), Ok(0));
+    }
+
+    #[test]
+    fn out_of_range() {
+        let file = tempfile::tempfile().unwrap();
+        file.set_len(16).unwrap();
+
+        let mut storage = MmapStorage::from_file(&file).unwrap();
+        assert!(storage.check_range(12, 4));
+        assert!(!storage.check_range(13, 4));
+        assert_eq!(storage.write_word(16, 1), Err(()));
+        assert_eq!(storage.read_byte(16), Err(()));
+    }
+}
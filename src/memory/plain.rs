@@ -0,0 +1,69 @@
+/// Represents a plain, byte-addressable [`StorageMut`] of a fixed length.
+///
+/// This is a thin wrapper around a `Vec<u8>`, which gives it a name in signatures. Any other
+/// byte container (like `Vec<u8>`, arrays or mutable slices) can be used as storage as well.
+///
+/// # Examples
+/// ```
+/// use vcpu::{PlainMemory, Storage, StorageMut};
+///
+/// let mut memory = PlainMemory::new(8);
+/// assert_eq!(memory.write_word(4, 0xDEAD_BEEF), Ok(()));
+/// assert_eq!(memory.read_half(6), Ok(0xDEAD));
+/// assert_eq!(memory.length(), 8);
+/// ```
+/// [`StorageMut`]: ../trait.StorageMut.html
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct PlainMemory(Vec<u8>);
+
+impl PlainMemory {
+    /// Constructs a memory of `size` bytes which are initialized to zero.
+    pub fn new(size: u32) -> PlainMemory {
+        PlainMemory(vec![0; size as usize])
+    }
+
+    /// Constructs a memory containing `bytes`.
+    pub fn from_bytes(bytes: Vec<u8>) -> PlainMemory {
+        PlainMemory(bytes)
+    }
+
+    /// Returns the underlying bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for PlainMemory {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsMut<[u8]> for PlainMemory {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlainMemory;
+    use crate::*;
+
+    #[test]
+    fn run_program() {
+        let mut memory = PlainMemory::new(64);
+        let instructions = instructions_from_words(&[
+            instr_i!(LI, T0, ZERO, 42),
+            instr_i!(SW, T0, ZERO, 60),
+            instr_i!(LW, T1, ZERO, 60),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+
+        let mut processor = Processor::new();
+        assert_eq!(processor.run(&instructions, &mut memory), ExitCode::Halted);
+        assert_eq!(processor.register(RegisterId::T1).u(), 42);
+        assert_eq!(memory.read_word(60), Ok(42));
+        assert_eq!(&memory.into_bytes()[60..], &[42, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{Storage, StorageMut};
+
+/// Wraps a [`Storage`] to make it read-only, e.g. to mount a ROM in a [`CompositeMemory`].
+///
+/// Every write fails, so a store instruction into this storage stops the processor with
+/// [`ExitCode::BadMemoryAccess`].
+///
+/// # Examples
+/// ```
+/// use vcpu::{ReadOnlyMemory, Storage, StorageMut};
+///
+/// let mut rom = ReadOnlyMemory::new([1u8, 2, 3, 4]);
+/// assert_eq!(rom.read_word(0), Ok(0x0403_0201));
+/// assert_eq!(rom.write_byte(0, 5), Err(()));
+/// assert!(!rom.is_writable(0, 4));
+/// ```
+/// [`Storage`]: ../trait.Storage.html
+/// [`CompositeMemory`]: ../struct.CompositeMemory.html
+/// [`ExitCode::BadMemoryAccess`]: ../enum.ExitCode.html#variant.BadMemoryAccess
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ReadOnlyMemory<S: Storage>(S);
+
+impl<S: Storage> ReadOnlyMemory<S> {
+    pub fn new(storage: S) -> ReadOnlyMemory<S> {
+        ReadOnlyMemory(storage)
+    }
+
+    /// Returns the wrapped storage.
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S: Storage> Storage for ReadOnlyMemory<S> {
+    fn length(&self) -> u32 {
+        self.0.length()
+    }
+
+    fn check_range(&self, address: u32, length: u32) -> bool {
+        self.0.check_range(address, length)
+    }
+
+    fn read(&self, address: u32, size: u32) -> Result<u32, ()> {
+        self.0.read(address, size)
+    }
+
+    fn checksum(&self, address: u32, length: u32) -> Result<u64, ()> {
+        self.0.checksum(address, length)
+    }
+}
+
+impl<S: Storage> StorageMut for ReadOnlyMemory<S> {
+    fn write(&mut self, _address: u32, _size: u32, _value: u32) -> Result<(), ()> {
+        Err(())
+    }
+
+    fn is_writable(&self, _address: u32, _length: u32) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReadOnlyMemory;
+    use crate::*;
+
+    #[test]
+    fn writable_ranges() {
+        let mut memory = CompositeMemory::new();
+        memory
+            .mount(0, "rom", ReadOnlyMemory::new([7u8; 8]))
+            .unwrap();
+        memory.mount(8, "ram", PlainMemory::new(8)).unwrap();
+
+        assert!(memory.check_range(0, 4));
+        assert!(!memory.is_writable(0, 4));
+        assert_eq!(memory.read_byte(3), Ok(7));
+
+        assert!(memory.check_range(8, 8));
+        assert!(memory.is_writable(8, 8));
+        assert!(!memory.is_writable(12, 8));
+        assert!(!memory.is_writable(16, 1));
+
+        let instructions = instructions_from_words(&[
+            instr_i!(SW, ZERO, ZERO, 8),
+            instr_i!(SW, ZERO, ZERO, 0),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+        let mut processor = Processor::new();
+        assert_eq!(
+            processor.run(&instructions, &mut memory),
+            ExitCode::BadMemoryAccess
+        );
+    }
+}
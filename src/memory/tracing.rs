@@ -0,0 +1,163 @@
+use crate::{Storage, StorageMut};
+use std::cell::{Ref, RefCell};
+
+/// A single access recorded by a [`TracingStorage`].
+///
+/// [`TracingStorage`]: ./struct.TracingStorage.html
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MemoryAccess {
+    pub address: u32,
+    pub size: u32,
+    /// The value that was read or written.
+    pub value: u32,
+    pub is_write: bool,
+}
+
+/// Wraps a [`StorageMut`] and records every successful read and write, e.g. to find out how a
+/// program uses memory.
+///
+/// The wrapper is transparent: all accesses are forwarded to the wrapped storage, including IO
+/// writes, so it can also be mounted in a [`CompositeMemory`]. Failed accesses are not recorded.
+///
+/// # Examples
+/// ```
+/// use vcpu::{MemoryAccess, Storage, StorageMut, TracingStorage};
+///
+/// let mut memory = TracingStorage::new([0u8; 8]);
+/// memory.write_half(4, 0x1234).unwrap();
+/// assert_eq!(memory.read_byte(5), Ok(0x12));
+/// assert!(memory.read_word(8).is_err());
+///
+/// assert_eq!(
+///     &memory.accesses()[..],
+///     &[
+///         MemoryAccess { address: 4, size: 2, value: 0x1234, is_write: true },
+///         MemoryAccess { address: 5, size: 1, value: 0x12, is_write: false },
+///     ]
+/// );
+/// ```
+/// [`StorageMut`]: ../trait.StorageMut.html
+/// [`CompositeMemory`]: ../struct.CompositeMemory.html
+#[derive(Debug, Default)]
+pub struct TracingStorage<S: StorageMut> {
+    inner: S,
+    accesses: RefCell<Vec<MemoryAccess>>,
+}
+
+impl<S: StorageMut> TracingStorage<S> {
+    pub fn new(storage: S) -> TracingStorage<S> {
+        TracingStorage {
+            inner: storage,
+            accesses: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the recorded accesses in the order they were performed.
+    pub fn accesses(&self) -> Ref<'_, [MemoryAccess]> {
+        Ref::map(self.accesses.borrow(), Vec::as_slice)
+    }
+
+    /// Removes and returns the recorded accesses.
+    pub fn take_accesses(&mut self) -> Vec<MemoryAccess> {
+        self.accesses.take()
+    }
+
+    /// Returns the wrapped storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Returns the wrapped storage, discarding the recorded accesses.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn record(&self, address: u32, size: u32, value: u32, is_write: bool) {
+        self.accesses.borrow_mut().push(MemoryAccess {
+            address,
+            size,
+            value,
+            is_write,
+        });
+    }
+}
+
+impl<S: StorageMut> Storage for TracingStorage<S> {
+    fn length(&self) -> u32 {
+        self.inner.length()
+    }
+
+    fn check_range(&self, address: u32, length: u32) -> bool {
+        self.inner.check_range(address, length)
+    }
+
+    fn read(&self, address: u32, size: u32) -> Result<u32, ()> {
+        let value = self.inner.read(address, size)?;
+        self.record(address, size, value, false);
+        Ok(value)
+    }
+
+    fn checksum(&self, address: u32, length: u32) -> Result<u64, ()> {
+        self.inner.checksum(address, length)
+    }
+}
+
+impl<S: StorageMut> StorageMut for TracingStorage<S> {
+    fn write(&mut self, address: u32, size: u32, value: u32) -> Result<(), ()> {
+        self.inner.write(address, size, value)?;
+        self.record(address, size, value, true);
+        Ok(())
+    }
+
+    fn write_io(&mut self, address: u32, size: u32, value: u32) -> Result<bool, ()> {
+        let is_io = self.inner.write_io(address, size, value)?;
+        self.record(address, size, value, true);
+        Ok(is_io)
+    }
+
+    fn is_writable(&self, address: u32, length: u32) -> bool {
+        self.inner.is_writable(address, length)
+    }
+
+    fn zero_fill(&mut self) {
+        self.inner.zero_fill()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn trace_loop_writes() {
+        let iterations = 32i16;
+        let instructions = instructions_from_words(&[
+            instr_i!(SLTI, T2, T0, iterations),
+            instr_i!(BEZ, ZERO, T2, jmp_addr_i16(5)),
+            instr_i!(SLLI, T1, T0, 2),
+            instr_i!(SW, T0, T1, 0),
+            instr_i!(ADDI, T0, T0, 1),
+            instr_j!(JMP, jmp_addr_i32(-5)),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+
+        let mut memory = TracingStorage::new(PlainMemory::new(1024));
+        let mut processor = Processor::new();
+        assert_eq!(processor.run(&instructions, &mut memory), ExitCode::Halted);
+
+        let expected: Vec<_> = (0..iterations as u32)
+            .map(|i| MemoryAccess {
+                address: i * constants::WORD_BYTES,
+                size: constants::WORD_BYTES,
+                value: i,
+                is_write: true,
+            })
+            .collect();
+        assert_eq!(&memory.accesses()[..], &expected[..]);
+        assert_eq!(memory.inner().read_word(124), Ok(31));
+
+        assert_eq!(memory.take_accesses(), expected);
+        assert!(memory.accesses().is_empty());
+    }
+}
@@ -1,13 +1,27 @@
+mod cost;
+mod decode;
+mod float_mode;
+mod frame_layout;
 mod logic;
+mod owned;
 
-use crate::StorageMut;
-use crate::{constants, register_index, Address, Endian, Immediate, Register, RegisterId, Word};
-use logic::TickResult;
-use util::InteropGetName;
-use util_derive::InteropGetName;
+use crate::{
+    constants, enum_to_u32, register_index, Address, Endian, Immediate, Register, RegisterId, Word,
+};
+use crate::{Storage, StorageMut};
+use util::{EnumAll, EnumFromStr, InteropGetName};
+use util_derive::{EnumFromStr, InteropGetName};
 
 use byteorder::ByteOrder;
 use num_derive::{FromPrimitive, ToPrimitive};
+use std::collections::BTreeSet;
+
+pub use cost::*;
+pub use decode::*;
+pub use float_mode::*;
+pub use frame_layout::*;
+pub use logic::TickOutcome;
+pub use owned::*;
 
 pub const fn jmp_addr_i16(offset: i16) -> Immediate {
     offset * (constants::WORD_BYTES as i16)
@@ -17,43 +31,144 @@ pub const fn jmp_addr_i32(offset: i32) -> Address {
     offset * (constants::WORD_BYTES as i32)
 }
 
+/// Converts the index of an instruction word to its address in bytes.
+pub const fn word_index_to_address(index: u32) -> u32 {
+    index * constants::WORD_BYTES
+}
+
+/// Converts an address in bytes to the index of the word at that address.
+///
+/// # Errors
+/// Returns an error if `address` is not aligned to word boundaries.
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+///
+/// assert_eq!(address_to_word_index(word_index_to_address(3)), Ok(3));
+/// assert_eq!(address_to_word_index(6), Err(()));
+/// ```
+pub fn address_to_word_index(address: u32) -> Result<u32, ()> {
+    if address.is_multiple_of(constants::WORD_BYTES) {
+        Ok(address / constants::WORD_BYTES)
+    } else {
+        Err(())
+    }
+}
+
 pub fn instructions_from_words(vec: &[Word]) -> Vec<u8> {
     let mut byte_vec = vec![0; vec.len() * constants::WORD_BYTES as usize];
     Endian::write_u32_into(&vec[..], &mut byte_vec[..]);
     byte_vec
 }
 
-fn get_next_pc(pc: u32, instr_len: u32) -> u32 {
-    let result = pc.wrapping_add(constants::WORD_BYTES);
-    if result < instr_len {
-        result
-    } else {
-        0
-    }
+/// Returns the address of the instruction following `pc`.
+///
+/// Execution does not wrap around: after the last instruction, the program counter points past
+/// the end of the instructions, so the next tick stops with [`ExitCode::BadProgramCounter`].
+///
+/// [`ExitCode::BadProgramCounter`]: ./enum.ExitCode.html#variant.BadProgramCounter
+fn get_next_pc(pc: u32) -> u32 {
+    pc.wrapping_add(constants::WORD_BYTES)
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, FromPrimitive, ToPrimitive, InteropGetName)]
+/// Reason why the processor stopped.
+///
+/// The numeric values of the variants are part of the stable ABI (e.g. the states returned by
+/// the C API), so they must never change. New variants get new values.
+#[derive(
+    PartialEq, Eq, Debug, Clone, Copy, FromPrimitive, ToPrimitive, InteropGetName, EnumFromStr,
+)]
 pub enum ExitCode {
     /// HALT instruction was executed (Normal shutdown).
-    Halted,
+    Halted = 0,
     /// Attempted integer division by zero.
-    DivisionByZero,
+    DivisionByZero = 1,
     /// Attempted to access main memory at invalid address.
-    BadMemoryAccess,
+    BadMemoryAccess = 2,
     /// Jump address was not aligned to word boundaries.
-    BadAlignment,
+    BadAlignment = 3,
     /// Jump address was out of instruction memory range.
-    BadJump,
+    BadJump = 4,
     /// Opcode or funct was not recognized.
-    InvalidOpcode,
-    /// Program counter is out of instruction memory range.
-    BadProgramCounter,
+    InvalidOpcode = 5,
+    /// Program counter is out of instruction memory range, e.g. because execution ran past the
+    /// last instruction.
+    BadProgramCounter = 6,
 }
 
+impl std::fmt::Display for ExitCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+impl ExitCode {
+    /// Returns whether this exit code can be intercepted by a trap handler
+    /// (see [`Processor::set_trap_handler`]).
+    ///
+    /// Only faults raised by the execution of an instruction are trappable:
+    /// [`DivisionByZero`], [`BadMemoryAccess`] and [`InvalidOpcode`].
+    ///
+    /// [`Processor::set_trap_handler`]: ./struct.Processor.html#method.set_trap_handler
+    /// [`DivisionByZero`]: #variant.DivisionByZero
+    /// [`BadMemoryAccess`]: #variant.BadMemoryAccess
+    /// [`InvalidOpcode`]: #variant.InvalidOpcode
+    pub fn is_trappable(self) -> bool {
+        matches!(
+            self,
+            ExitCode::DivisionByZero | ExitCode::BadMemoryAccess | ExitCode::InvalidOpcode
+        )
+    }
+}
+
+/// Result of [`Processor::run_until_io`].
+///
+/// [`Processor::run_until_io`]: ./struct.Processor.html#method.run_until_io
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RunOutcome {
+    /// The processor stopped with the given exit code.
+    Stopped(ExitCode),
+    /// An instruction wrote `size` bytes at `address` to an IO device
+    /// (see [`StorageMut::write_io`]). The program counter already points to the next instruction.
+    ///
+    /// [`StorageMut::write_io`]: ./trait.StorageMut.html#method.write_io
+    IoWrite { address: u32, size: u32 },
+}
+
+/// Result of [`Processor::run_to`].
+///
+/// [`Processor::run_to`]: ./struct.Processor.html#method.run_to
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RunToOutcome {
+    /// The processor stopped with the given exit code.
+    Stopped(ExitCode),
+    /// The program counter reached the target address. The instruction at that address has not
+    /// been executed yet.
+    ReachedTarget,
+    /// The maximum number of instructions was executed without reaching the target address.
+    TickLimit,
+}
+
+/// The virtual processor, which executes instructions on its registers and a [`StorageMut`].
+///
+/// Cloning a processor forks its execution state, e.g. for what-if analysis.
+///
+/// [`StorageMut`]: ./trait.StorageMut.html
+#[derive(Clone, PartialEq)]
 pub struct Processor {
     registers: [Register; constants::REGISTER_COUNT],
     program_counter: u32,
     state: Option<ExitCode>,
+    cost_table: CostTable,
+    total_cycles: u64,
+    trap_handler: Option<u32>,
+    breakpoints: BTreeSet<u32>,
+    float_mode: FloatMode,
+    frame_layout: FrameLayout,
+    checked_addresses: bool,
+    last_instruction: Option<Word>,
+    coverage: Option<Vec<bool>>,
 }
 
 impl Processor {
@@ -69,6 +184,33 @@ impl Processor {
         &mut self.registers
     }
 
+    /// Returns a copy of all registers viewed as `i32`.
+    pub fn registers_as_i32(&self) -> [i32; constants::REGISTER_COUNT] {
+        let mut values = [0; constants::REGISTER_COUNT];
+        for (value, register) in values.iter_mut().zip(self.registers.iter()) {
+            *value = register.i();
+        }
+        values
+    }
+
+    /// Returns a copy of all registers viewed as `u32`.
+    pub fn registers_as_u32(&self) -> [u32; constants::REGISTER_COUNT] {
+        let mut values = [0; constants::REGISTER_COUNT];
+        for (value, register) in values.iter_mut().zip(self.registers.iter()) {
+            *value = register.u();
+        }
+        values
+    }
+
+    /// Returns a copy of all registers viewed as `f32`.
+    pub fn registers_as_f32(&self) -> [f32; constants::REGISTER_COUNT] {
+        let mut values = [0.0; constants::REGISTER_COUNT];
+        for (value, register) in values.iter_mut().zip(self.registers.iter()) {
+            *value = register.f();
+        }
+        values
+    }
+
     pub fn register(&self, id: RegisterId) -> &Register {
         &self.registers[register_index(id)]
     }
@@ -81,6 +223,82 @@ impl Processor {
         self.program_counter
     }
 
+    /// Returns the most recently fetched instruction word, or `None` if no instruction was
+    /// fetched since the processor was created or reset.
+    ///
+    /// After the processor stopped with a fault like [`ExitCode::InvalidOpcode`], this is the
+    /// offending instruction.
+    ///
+    /// [`ExitCode::InvalidOpcode`]: ./enum.ExitCode.html#variant.InvalidOpcode
+    pub fn last_instruction(&self) -> Option<Word> {
+        self.last_instruction
+    }
+
+    /// Starts recording which instructions are executed, for an instruction buffer of
+    /// `instr_len` bytes. Any previously recorded coverage is discarded.
+    pub fn enable_coverage(&mut self, instr_len: u32) {
+        self.coverage = Some(vec![false; (instr_len / constants::WORD_BYTES) as usize]);
+    }
+
+    /// Returns for every instruction (by index in words) whether it was executed since coverage
+    /// was enabled, or `None` if it is not enabled.
+    ///
+    /// An instruction counts as executed once it was fetched, even if it caused a fault.
+    /// Resetting the processor clears the coverage, but keeps recording it.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::*;
+    ///
+    /// let instructions = instructions_from_words(&[
+    ///     instr_j!(JMP, 8),
+    ///     instr_i!(ADDI, T0, T0, 1),
+    ///     instr_i!(HALT, ZERO, ZERO, 0),
+    /// ]);
+    ///
+    /// let mut processor = Processor::new();
+    /// assert_eq!(processor.coverage(), None);
+    ///
+    /// processor.enable_coverage(instructions.len() as u32);
+    /// processor.run(&instructions, &mut [0u8; 0]);
+    /// assert_eq!(processor.coverage(), Some(&[true, false, true][..]));
+    /// ```
+    pub fn coverage(&self) -> Option<&[bool]> {
+        self.coverage.as_deref()
+    }
+
+    /// Moves the program counter to `pc`, e.g. to implement "set next statement" in a debugger.
+    ///
+    /// If `instr_len` is given, `pc` must also lie within the instruction memory of that length.
+    /// If the processor was halted normally, its state is cleared so that execution can be resumed.
+    /// Any other (fault) state is kept.
+    ///
+    /// # Errors
+    /// Returns [`ExitCode::BadAlignment`] if `pc` is not aligned to word boundaries and
+    /// [`ExitCode::BadJump`] if it is out of range. The program counter is not changed in that case.
+    ///
+    /// [`ExitCode::BadAlignment`]: ./enum.ExitCode.html#variant.BadAlignment
+    /// [`ExitCode::BadJump`]: ./enum.ExitCode.html#variant.BadJump
+    pub fn set_program_counter(&mut self, pc: u32, instr_len: Option<u32>) -> Result<(), ExitCode> {
+        if !pc.is_multiple_of(constants::WORD_BYTES) {
+            return Err(ExitCode::BadAlignment);
+        }
+
+        if let Some(len) = instr_len {
+            if pc >= len {
+                return Err(ExitCode::BadJump);
+            }
+        }
+
+        self.program_counter = pc;
+
+        if self.state == Some(ExitCode::Halted) {
+            self.state = None;
+        }
+
+        Ok(())
+    }
+
     pub fn state(&self) -> Option<ExitCode> {
         self.state
     }
@@ -89,66 +307,347 @@ impl Processor {
         self.state.is_some()
     }
 
-    pub fn tick(&mut self, instructions: &[u8], storage: &mut dyn StorageMut) -> Option<ExitCode> {
-        if !self.is_stopped() {
-            self.state = self.get_new_state(instructions, storage);
+    /// Returns the address of the instruction that caused the processor to stop with an error.
+    ///
+    /// The program counter is not advanced when an instruction faults, so this is simply the
+    /// current program counter if the state is anything other than `None`, [`ExitCode::Halted`]
+    /// or [`ExitCode::BadProgramCounter`]. The latter means that no instruction could be fetched,
+    /// e.g. because the program ran past its last instruction.
+    ///
+    /// [`ExitCode::Halted`]: ./enum.ExitCode.html#variant.Halted
+    /// [`ExitCode::BadProgramCounter`]: ./enum.ExitCode.html#variant.BadProgramCounter
+    pub fn fault_pc(&self) -> Option<u32> {
+        match self.state {
+            None | Some(ExitCode::Halted) | Some(ExitCode::BadProgramCounter) => None,
+            Some(_) => Some(self.program_counter),
+        }
+    }
+
+    /// Clears a recoverable fault, so that a supervisor can retry the faulting instruction
+    /// after fixing its cause, e.g. by mapping the missing memory.
+    ///
+    /// Only [`ExitCode::BadMemoryAccess`] and [`ExitCode::DivisionByZero`] are recoverable. The
+    /// program counter still points to the faulting instruction, so it is executed again by the
+    /// next tick. Returns `false` (and keeps the state) if the processor is running, halted or
+    /// stopped by any other fault.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::*;
+    ///
+    /// let instructions = instructions_from_words(&[instr_i!(LW, T0, ZERO, 0)]);
+    /// let mut processor = Processor::new();
+    ///
+    /// assert_eq!(processor.run(&instructions, &mut [0u8; 0]), ExitCode::BadMemoryAccess);
+    /// assert!(processor.resume_after_fault());
+    /// assert_eq!(processor.tick(&instructions, &mut [42u8, 0, 0, 0]), None);
+    /// assert_eq!(processor.register(RegisterId::T0).u(), 42);
+    /// ```
+    /// [`ExitCode::BadMemoryAccess`]: ./enum.ExitCode.html#variant.BadMemoryAccess
+    /// [`ExitCode::DivisionByZero`]: ./enum.ExitCode.html#variant.DivisionByZero
+    pub fn resume_after_fault(&mut self) -> bool {
+        match self.state {
+            Some(ExitCode::BadMemoryAccess) | Some(ExitCode::DivisionByZero) => {
+                self.state = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the table used to determine the cost of each executed instruction.
+    pub fn cost_table(&self) -> &CostTable {
+        &self.cost_table
+    }
+
+    /// Replaces the table used to determine the cost of each executed instruction.
+    pub fn set_cost_table(&mut self, cost_table: CostTable) {
+        self.cost_table = cost_table;
+    }
+
+    /// Returns the sum of the costs of all instructions executed since the last reset.
+    ///
+    /// With the default [`CostTable`](struct.CostTable.html), this is the number of executed instructions.
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    pub fn float_mode(&self) -> FloatMode {
+        self.float_mode
+    }
+
+    /// Sets how the results of floating point instructions are post-processed.
+    pub fn set_float_mode(&mut self, float_mode: FloatMode) {
+        self.float_mode = float_mode;
+    }
+
+    pub fn frame_layout(&self) -> FrameLayout {
+        self.frame_layout
+    }
+
+    /// Sets the layout of stack frames assumed by [`stack_trace`].
+    ///
+    /// [`stack_trace`]: #method.stack_trace
+    pub fn set_frame_layout(&mut self, frame_layout: FrameLayout) {
+        self.frame_layout = frame_layout;
+    }
+
+    /// Reconstructs the call stack by following the chain of frame pointers, starting at `$FP`,
+    /// e.g. to find out how a faulted program got to the faulting instruction.
+    ///
+    /// Returns the saved return address of each frame, innermost first. This only works if all
+    /// functions maintain `$FP` and save `$RA` according to the [`frame_layout`]. The walk stops
+    /// at a frame pointer of `0`, at a frame which cannot be read from `storage`, or when a
+    /// frame is visited again.
+    ///
+    /// [`frame_layout`]: #method.frame_layout
+    pub fn stack_trace(&self, storage: &dyn Storage) -> Vec<u32> {
+        let layout = self.frame_layout;
+        let mut visited = BTreeSet::new();
+        let mut return_addresses = Vec::new();
+        let mut frame = self.register(RegisterId::FP).u();
+
+        while frame != 0 && visited.insert(frame) {
+            let read = |offset: i32| storage.read_word(frame.wrapping_add(offset as u32));
+            match (
+                read(layout.return_address_offset),
+                read(layout.previous_frame_offset),
+            ) {
+                (Ok(return_address), Ok(previous_frame)) => {
+                    return_addresses.push(return_address);
+                    frame = previous_frame;
+                }
+                _ => break,
+            }
         }
 
+        return_addresses
+    }
+
+    pub fn checked_addresses(&self) -> bool {
+        self.checked_addresses
+    }
+
+    /// Sets whether the effective address of loads and stores (`Rs1 + immediate`) is checked
+    /// for overflow.
+    ///
+    /// By default, the computation wraps around, so e.g. an offset of `4` from address
+    /// `0xFFFF_FFFE` accesses address `2`. With checking enabled, such an access fails with
+    /// [`ExitCode::BadMemoryAccess`] instead.
+    ///
+    /// [`ExitCode::BadMemoryAccess`]: ./enum.ExitCode.html#variant.BadMemoryAccess
+    pub fn set_checked_addresses(&mut self, checked: bool) {
+        self.checked_addresses = checked;
+    }
+
+    /// Returns the address of the trap handler, if one is set.
+    pub fn trap_handler(&self) -> Option<u32> {
+        self.trap_handler
+    }
+
+    /// Sets the address of the trap handler.
+    ///
+    /// When an instruction raises a [trappable] fault, the processor does not stop. Instead, it
+    /// sets register `RM` to the exit code of the fault, register `RA` to the address of the
+    /// faulting instruction and jumps to `address`. The handler can return to the instruction
+    /// following the faulting one by adding the word size to `RA` and jumping to it.
+    ///
+    /// If `address` is not a valid jump target when the trap occurs, the processor stops with
+    /// [`ExitCode::BadAlignment`] or [`ExitCode::BadJump`].
+    ///
+    /// [trappable]: ./enum.ExitCode.html#method.is_trappable
+    /// [`ExitCode::BadAlignment`]: ./enum.ExitCode.html#variant.BadAlignment
+    /// [`ExitCode::BadJump`]: ./enum.ExitCode.html#variant.BadJump
+    pub fn set_trap_handler(&mut self, address: u32) {
+        self.trap_handler = Some(address);
+    }
+
+    /// Removes the trap handler, so that every fault stops the processor again.
+    pub fn clear_trap_handler(&mut self) {
+        self.trap_handler = None;
+    }
+
+    /// Sets a breakpoint at the instruction `address`, so that [`run_to_breakpoint`] pauses
+    /// before executing it.
+    ///
+    /// [`run_to_breakpoint`]: #method.run_to_breakpoint
+    pub fn set_breakpoint(&mut self, address: u32) {
+        self.breakpoints.insert(address);
+    }
+
+    /// Removes the breakpoint at `address`. Returns whether a breakpoint was set at that address.
+    pub fn clear_breakpoint(&mut self, address: u32) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn has_breakpoint(&self, address: u32) -> bool {
+        self.breakpoints.contains(&address)
+    }
+
+    /// Executes one instruction and returns the state of the processor afterwards.
+    ///
+    /// Once the processor is stopped, further ticks don't execute anything and keep returning
+    /// the same exit code, until the processor is [`reset`] or a recoverable fault is cleared
+    /// with [`resume_after_fault`].
+    ///
+    /// [`reset`]: #method.reset
+    /// [`resume_after_fault`]: #method.resume_after_fault
+    pub fn tick(&mut self, instructions: &[u8], storage: &mut dyn StorageMut) -> Option<ExitCode> {
+        self.step(instructions, storage);
         self.state
     }
 
+    /// Executes one instruction unless the processor is stopped.
+    ///
+    /// Returns the address and size of the IO write performed by the instruction, if any.
+    fn step(&mut self, instructions: &[u8], storage: &mut dyn StorageMut) -> Option<(u32, u32)> {
+        if self.is_stopped() {
+            return None;
+        }
+
+        match self.get_new_state(instructions, storage) {
+            Ok(io_write) => io_write,
+            Err(exit_code) => {
+                self.state = Some(exit_code);
+                None
+            }
+        }
+    }
+
+    /// Executes `word` as if it were the instruction at the current program counter, without
+    /// fetching it from an instruction buffer.
+    ///
+    /// Only registers and `storage` are modified. The program counter, state and cycle count
+    /// stay unchanged, and neither jumps nor links nor traps are performed; the returned
+    /// [`TickOutcome`] describes what the processor would have done instead.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::*;
+    ///
+    /// let mut processor = Processor::new();
+    /// let outcome = processor.execute_word(instr_i!(ADDI, T0, ZERO, 5), &mut [0u8; 0]);
+    ///
+    /// assert_eq!(outcome, TickOutcome::Next);
+    /// assert_eq!(processor.register(RegisterId::T0).i(), 5);
+    /// ```
+    /// [`TickOutcome`]: ./enum.TickOutcome.html
+    pub fn execute_word(&mut self, word: Word, storage: &mut dyn StorageMut) -> TickOutcome {
+        logic::tick(
+            &mut self.registers,
+            storage,
+            word,
+            self.program_counter,
+            self.float_mode,
+            self.checked_addresses,
+        )
+    }
+
+    /// Resets the registers, program counter and statistics, so that the program can be run
+    /// again from the start. To zero the memory as well, use an [`OwnedProcessor`].
+    ///
+    /// [`OwnedProcessor`]: ./struct.OwnedProcessor.html
     pub fn reset(&mut self) {
         self.registers = [Default::default(); constants::REGISTER_COUNT];
         self.program_counter = 0u32;
         self.state = None;
+        self.total_cycles = 0;
+        self.last_instruction = None;
+        if let Some(coverage) = &mut self.coverage {
+            coverage.iter_mut().for_each(|executed| *executed = false);
+        }
     }
 
     fn get_new_state(
         &mut self,
         instructions: &[u8],
         storage: &mut dyn StorageMut,
-    ) -> Option<ExitCode> {
+    ) -> Result<Option<(u32, u32)>, ExitCode> {
         let instr_len = instructions.len() as u32;
-        if self.program_counter + constants::WORD_BYTES > instr_len {
-            Some(ExitCode::BadProgramCounter)
+        if self
+            .program_counter
+            .checked_add(constants::WORD_BYTES)
+            .is_none_or(|end| end > instr_len)
+        {
+            Err(ExitCode::BadProgramCounter)
         } else {
+            // Instructions are fetched from their own memory, so stores can never change them.
             let pc = self.program_counter as usize;
 
             let instruction =
                 Endian::read_u32(&instructions[pc..(pc + constants::WORD_BYTES as usize)]);
+            self.last_instruction = Some(instruction);
+            let index = address_to_word_index(self.program_counter);
+            if let Some(executed) = self
+                .coverage
+                .as_mut()
+                .zip(index.ok())
+                .and_then(|(coverage, index)| coverage.get_mut(index as usize))
+            {
+                *executed = true;
+            }
+
+            self.total_cycles += u64::from(self.cost_table.instruction_cost(instruction));
 
             let tick_result = logic::tick(
                 &mut self.registers,
                 storage,
                 instruction,
                 self.program_counter,
+                self.float_mode,
+                self.checked_addresses,
             );
 
             match tick_result {
-                TickResult::Next => {
-                    self.program_counter = get_next_pc(self.program_counter, instr_len);
-                    None
+                TickOutcome::Next => {
+                    self.program_counter = get_next_pc(self.program_counter);
+                    Ok(None)
                 }
-                TickResult::Jump(new_pc, link) => {
-                    if (new_pc % (constants::WORD_BYTES as u32)) != 0 {
-                        Some(ExitCode::BadAlignment)
-                    } else if new_pc >= instr_len {
-                        Some(ExitCode::BadJump)
-                    } else {
+                TickOutcome::IoWrite { address, size } => {
+                    self.program_counter = get_next_pc(self.program_counter);
+                    Ok(Some((address, size)))
+                }
+                TickOutcome::Jump { target, link } => self.jump(target, link, instr_len),
+                TickOutcome::Stop(exit_code) => match self.trap_handler {
+                    Some(handler) if exit_code.is_trappable() => {
                         let old_pc = self.program_counter;
-                        if link {
-                            self.register_mut(RegisterId::RA)
-                                .set_u(get_next_pc(old_pc, instr_len));
-                        }
-                        self.program_counter = new_pc;
-                        None
+                        self.register_mut(RegisterId::RM)
+                            .set_u(enum_to_u32(exit_code));
+                        self.register_mut(RegisterId::RA).set_u(old_pc);
+                        self.jump(handler, false, instr_len)
                     }
-                }
-                TickResult::Stop(exit_code) => Some(exit_code),
+                    _ => Err(exit_code),
+                },
+            }
+        }
+    }
+
+    fn jump(
+        &mut self,
+        new_pc: u32,
+        link: bool,
+        instr_len: u32,
+    ) -> Result<Option<(u32, u32)>, ExitCode> {
+        if !new_pc.is_multiple_of(constants::WORD_BYTES) {
+            Err(ExitCode::BadAlignment)
+        } else if new_pc >= instr_len {
+            Err(ExitCode::BadJump)
+        } else {
+            let old_pc = self.program_counter;
+            if link {
+                self.register_mut(RegisterId::RA).set_u(get_next_pc(old_pc));
             }
+            self.program_counter = new_pc;
+            Ok(None)
         }
     }
 
+    /// Executes instructions until the processor stops and returns the exit code.
+    ///
+    /// If the processor is already stopped, the stored exit code is returned immediately without
+    /// executing anything (see [`tick`]).
+    ///
+    /// [`tick`]: #method.tick
     pub fn run(&mut self, instructions: &[u8], storage: &mut dyn StorageMut) -> ExitCode {
         loop {
             if let Some(exit_code) = self.tick(instructions, storage) {
@@ -156,6 +655,152 @@ impl Processor {
             }
         }
     }
+
+    /// Runs like [`run`], but executes at most `max_ticks` instructions, e.g. to protect the
+    /// host from programs that never halt.
+    ///
+    /// Returns `None` if the processor is still running after `max_ticks` instructions.
+    /// Calling this (or any other run method) again resumes execution.
+    ///
+    /// [`run`]: #method.run
+    pub fn run_bounded(
+        &mut self,
+        instructions: &[u8],
+        storage: &mut dyn StorageMut,
+        max_ticks: u64,
+    ) -> Option<ExitCode> {
+        for _ in 0..max_ticks {
+            if let Some(exit_code) = self.tick(instructions, storage) {
+                return Some(exit_code);
+            }
+        }
+
+        self.state
+    }
+
+    /// Runs like [`run`], but returns control to the caller after every write to an IO device,
+    /// so that the device can be serviced before execution is resumed by calling this again.
+    ///
+    /// [`run`]: #method.run
+    pub fn run_until_io(
+        &mut self,
+        instructions: &[u8],
+        storage: &mut dyn StorageMut,
+    ) -> RunOutcome {
+        loop {
+            if let Some((address, size)) = self.step(instructions, storage) {
+                return RunOutcome::IoWrite { address, size };
+            }
+
+            if let Some(exit_code) = self.state {
+                return RunOutcome::Stopped(exit_code);
+            }
+        }
+    }
+
+    /// Runs like [`run`], but pauses when the program counter reaches `target_pc`, e.g. to
+    /// "run to cursor" in a debugger. At most `max_ticks` instructions are executed.
+    ///
+    /// Like with [`run_to_breakpoint`], at least one instruction is executed before checking the
+    /// program counter, so calling this again with the same target runs e.g. one more iteration
+    /// of a loop.
+    ///
+    /// # Errors
+    /// Returns [`ExitCode::BadAlignment`] if `target_pc` is not aligned to word boundaries,
+    /// without executing any instruction.
+    ///
+    /// [`run`]: #method.run
+    /// [`run_to_breakpoint`]: #method.run_to_breakpoint
+    /// [`ExitCode::BadAlignment`]: ./enum.ExitCode.html#variant.BadAlignment
+    pub fn run_to(
+        &mut self,
+        instructions: &[u8],
+        storage: &mut dyn StorageMut,
+        target_pc: u32,
+        max_ticks: u64,
+    ) -> Result<RunToOutcome, ExitCode> {
+        if !target_pc.is_multiple_of(constants::WORD_BYTES) {
+            return Err(ExitCode::BadAlignment);
+        }
+
+        for _ in 0..max_ticks {
+            if let Some(exit_code) = self.tick(instructions, storage) {
+                return Ok(RunToOutcome::Stopped(exit_code));
+            }
+
+            if self.program_counter == target_pc {
+                return Ok(RunToOutcome::ReachedTarget);
+            }
+        }
+
+        Ok(match self.state {
+            Some(exit_code) => RunToOutcome::Stopped(exit_code),
+            None => RunToOutcome::TickLimit,
+        })
+    }
+
+    /// Runs like [`run`], but calls `observer` with the id, old value and new value of every
+    /// register whose value is changed by an instruction, e.g. to watch a register in a data-flow
+    /// debugger.
+    ///
+    /// This includes the implicit writes to `$RM` and `$RA`, e.g. by `MUL`, `JL` or when
+    /// jumping to the trap handler. Writes which leave the value unchanged are not reported.
+    ///
+    /// [`run`]: #method.run
+    pub fn run_with_observer<F>(
+        &mut self,
+        instructions: &[u8],
+        storage: &mut dyn StorageMut,
+        mut observer: F,
+    ) -> ExitCode
+    where
+        F: FnMut(RegisterId, Register, Register),
+    {
+        loop {
+            let old_registers = self.registers;
+            let exit_code = self.tick(instructions, storage);
+
+            for ((&id, old), new) in RegisterId::all()
+                .iter()
+                .zip(old_registers.iter())
+                .zip(self.registers.iter())
+            {
+                if old.u() != new.u() {
+                    observer(id, *old, *new);
+                }
+            }
+
+            if let Some(exit_code) = exit_code {
+                return exit_code;
+            }
+        }
+    }
+
+    /// Runs like [`run`], but pauses when the program counter reaches a breakpoint.
+    ///
+    /// At least one instruction is executed before checking for breakpoints, so calling this
+    /// again after hitting a breakpoint resumes execution.
+    ///
+    /// Returns `None` if a breakpoint was hit (the program counter then points to the
+    /// instruction at the breakpoint, which has not been executed yet), or the exit code
+    /// if the processor stopped.
+    ///
+    /// [`run`]: #method.run
+    pub fn run_to_breakpoint(
+        &mut self,
+        instructions: &[u8],
+        storage: &mut dyn StorageMut,
+    ) -> Option<ExitCode> {
+        loop {
+            if let Some(exit_code) = self.tick(instructions, storage) {
+                return Some(exit_code);
+            }
+
+            if self.has_breakpoint(self.program_counter) {
+                return None;
+            }
+        }
+    }
 }
 
 impl Default for Processor {
@@ -164,6 +809,15 @@ impl Default for Processor {
             registers: [Default::default(); constants::REGISTER_COUNT],
             program_counter: 0u32,
             state: None,
+            cost_table: Default::default(),
+            total_cycles: 0,
+            trap_handler: None,
+            breakpoints: BTreeSet::new(),
+            float_mode: Default::default(),
+            frame_layout: Default::default(),
+            checked_addresses: false,
+            last_instruction: None,
+            coverage: None,
         }
     }
 }
@@ -0,0 +1,90 @@
+use crate::{constants, enum_to_u32, AluFunct, FlopFunct, Opcode, Word};
+use num::FromPrimitive;
+use util::EnumGetVariantCount;
+
+/// Returns the default cost (in cycles) of an instruction, which is `1` for every instruction.
+///
+/// `funct` is only relevant for the [`Opcode::ALU`] and [`Opcode::FLOP`] instructions.
+///
+/// [`Opcode::ALU`]: ./enum.Opcode.html#variant.ALU
+/// [`Opcode::FLOP`]: ./enum.Opcode.html#variant.FLOP
+pub fn default_cost(_opcode: Opcode, _funct: Option<u32>) -> u32 {
+    1
+}
+
+/// Table of instruction costs (in cycles) used by the [`Processor`] to accumulate [`total_cycles`].
+///
+/// `ALU` and `FLOP` instructions are looked up by their funct, all other instructions by their opcode.
+///
+/// # Examples
+/// ```
+/// use vcpu::{AluFunct, CostTable, Opcode};
+///
+/// let mut table = CostTable::default();
+/// table.set_alu_cost(AluFunct::DIV, 10);
+///
+/// assert_eq!(table.cost(Opcode::ALU, Some(AluFunct::DIV as u32)), 10);
+/// assert_eq!(table.cost(Opcode::ALU, Some(AluFunct::ADD as u32)), 1);
+/// assert_eq!(table.cost(Opcode::NOP, None), 1);
+/// ```
+/// [`Processor`]: ./struct.Processor.html
+/// [`total_cycles`]: ./struct.Processor.html#method.total_cycles
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CostTable {
+    opcodes: Vec<u32>,
+    alu: Vec<u32>,
+    flop: Vec<u32>,
+}
+
+impl CostTable {
+    /// Constructs a table in which every instruction costs `cost` cycles.
+    pub fn uniform(cost: u32) -> CostTable {
+        CostTable {
+            opcodes: vec![cost; Opcode::variant_count()],
+            alu: vec![cost; AluFunct::variant_count()],
+            flop: vec![cost; FlopFunct::variant_count()],
+        }
+    }
+
+    /// Returns the cost of an instruction with the given `opcode` and `funct`.
+    ///
+    /// For `ALU` and `FLOP` instructions with an unknown (or missing) `funct`, the cost of the opcode is returned.
+    pub fn cost(&self, opcode: Opcode, funct: Option<u32>) -> u32 {
+        let funct_cost = match opcode {
+            Opcode::ALU => funct.and_then(|f| self.alu.get(f as usize)),
+            Opcode::FLOP => funct.and_then(|f| self.flop.get(f as usize)),
+            _ => None,
+        };
+
+        *funct_cost.unwrap_or(&self.opcodes[enum_to_u32(opcode) as usize])
+    }
+
+    /// Sets the cost of all instructions with the given `opcode`.
+    pub fn set_opcode_cost(&mut self, opcode: Opcode, cost: u32) {
+        self.opcodes[enum_to_u32(opcode) as usize] = cost;
+    }
+
+    /// Sets the cost of `ALU` instructions with the given `funct`.
+    pub fn set_alu_cost(&mut self, funct: AluFunct, cost: u32) {
+        self.alu[enum_to_u32(funct) as usize] = cost;
+    }
+
+    /// Sets the cost of `FLOP` instructions with the given `funct`.
+    pub fn set_flop_cost(&mut self, funct: FlopFunct, cost: u32) {
+        self.flop[enum_to_u32(funct) as usize] = cost;
+    }
+
+    /// Returns the cost of an encoded `instruction`, or `0` if its opcode is unknown.
+    pub(crate) fn instruction_cost(&self, instruction: Word) -> u32 {
+        let opcode = (instruction & constants::OPCODE_MASK) >> constants::OPCODE_OFFSET;
+        let funct = (instruction & constants::FUNCT_MASK) >> constants::FUNCT_OFFSET;
+
+        Opcode::from_u32(opcode).map_or(0, |opcode| self.cost(opcode, Some(funct)))
+    }
+}
+
+impl Default for CostTable {
+    fn default() -> CostTable {
+        CostTable::uniform(default_cost(Opcode::NOP, None))
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{constants, AluFunct, DecodeError, FlopFunct, Opcode, RegisterId, Word};
+use num::FromPrimitive;
+
+/// Extracts the 16 bit immediate value of an `I` format instruction.
+///
+/// Returns the immediate both as a signed and as an unsigned value, since instructions differ in
+/// how they interpret it: most use the signed value (sign-extended to 32 bits), while the
+/// unsigned comparisons (e.g. `SLTUI`) and the set-bits instructions (`SLO`, `SHI`) use the
+/// unsigned value (zero-extended to 32 bits).
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+///
+/// assert_eq!(extract_immediate(instr_i!(ADDI, T0, T0, -1)), (-1, 0xFFFF));
+/// assert_eq!(extract_immediate(instr_i!(ADDI, T0, T0, 42)), (42, 42));
+/// ```
+pub fn extract_immediate(instruction: Word) -> (i16, u16) {
+    let immediate = (instruction & constants::IMMEDIATE_MASK) >> constants::IMMEDIATE_OFFSET;
+    (immediate as i16, immediate as u16)
+}
+
+/// Extracts the 26 bit address of a `J` format instruction and sign-extends it to 32 bits.
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+///
+/// assert_eq!(sign_extend_address(instr_j!(JMP, -8)), -8);
+/// assert_eq!(sign_extend_address(instr_j!(JMP, 8)), 8);
+/// ```
+pub fn sign_extend_address(instruction: Word) -> i32 {
+    let mut address = (instruction & constants::ADDRESS_MASK) >> constants::ADDRESS_OFFSET;
+
+    if (address & constants::ADDRESS_SIGN_MASK) != 0 {
+        address |= constants::ADDRESS_EXTENSION;
+    }
+
+    address as i32
+}
+
+fn register(instruction: Word, mask: u32, offset: u32) -> RegisterId {
+    RegisterId::from_u32((instruction & mask) >> offset).unwrap()
+}
+
+/// Converts `instruction` back to assembly text in the syntax accepted by `vasm`.
+///
+/// Registers are written with their upper-case names (e.g. `$T0`), immediates in decimal,
+/// and jump and branch targets as offsets in bytes relative to the instruction.
+///
+/// # Errors
+/// Returns the same errors as [`validate_instruction`].
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+///
+/// assert_eq!(disassemble(instr_alu!(ADD, T0, T1, T2)), Ok("ADD $T0, $T1, $T2".to_owned()));
+/// assert_eq!(disassemble(instr_i!(LW, T0, SP, -4)), Ok("LW $T0, -4($SP)".to_owned()));
+/// assert_eq!(disassemble(instr_j!(JMP, -8)), Ok("JMP -8".to_owned()));
+/// ```
+/// [`validate_instruction`]: ./fn.validate_instruction.html
+pub fn disassemble(instruction: Word) -> Result<String, DecodeError> {
+    let opcode_value = (instruction & constants::OPCODE_MASK) >> constants::OPCODE_OFFSET;
+    let funct_value = (instruction & constants::FUNCT_MASK) >> constants::FUNCT_OFFSET;
+    let opcode = Opcode::from_u32(opcode_value).ok_or(DecodeError::InvalidOpcode(opcode_value))?;
+
+    let rd = register(instruction, constants::RD_MASK, constants::RD_OFFSET);
+    let rs1 = register(instruction, constants::RS1_MASK, constants::RS1_OFFSET);
+    let rs2 = register(instruction, constants::RS2_MASK, constants::RS2_OFFSET);
+    let (imm_i, imm_u) = extract_immediate(instruction);
+
+    Ok(match opcode {
+        Opcode::ALU => {
+            let funct =
+                AluFunct::from_u32(funct_value).ok_or(DecodeError::InvalidAluFunct(funct_value))?;
+            format!("{} ${}, ${}, ${}", funct, rd, rs1, rs2)
+        }
+        Opcode::FLOP => {
+            let funct = FlopFunct::from_u32(funct_value)
+                .ok_or(DecodeError::InvalidFlopFunct(funct_value))?;
+            format!("{} ${}, ${}, ${}", funct, rd, rs1, rs2)
+        }
+        Opcode::NOP | Opcode::HALT | Opcode::CALL => opcode.to_string(),
+        Opcode::COPY | Opcode::FLIP | Opcode::ITOF | Opcode::FTOI | Opcode::UTOF | Opcode::FTOU => {
+            format!("{} ${}, ${}", opcode, rd, rs1)
+        }
+        Opcode::LI | Opcode::LHI => format!("{} ${}, {}", opcode, rd, imm_i),
+        Opcode::SLO | Opcode::SHI => format!("{} ${}, {}", opcode, rd, imm_u),
+        Opcode::LB | Opcode::LH | Opcode::LW | Opcode::SB | Opcode::SH | Opcode::SW => {
+            format!("{} ${}, {}(${})", opcode, rd, imm_i, rs1)
+        }
+        Opcode::SLTUI | Opcode::SGTUI | Opcode::SLEUI | Opcode::SGEUI => {
+            format!("{} ${}, ${}, {}", opcode, rd, rs1, imm_u)
+        }
+        Opcode::BEZ | Opcode::BNZ => format!("{} ${}, {}", opcode, rs1, imm_i),
+        Opcode::JMP | Opcode::JL => format!("{} {}", opcode, sign_extend_address(instruction)),
+        Opcode::JR | Opcode::JLR => format!("{} ${}", opcode, rs1),
+        _ => format!("{} ${}, ${}, {}", opcode, rd, rs1, imm_i),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn negative_immediate() {
+        assert_eq!(extract_immediate(0x0000_FFFF), (-1, 0xFFFF));
+        assert_eq!(extract_immediate(0xFFFF_8000), (-0x8000, 0x8000));
+        assert_eq!(
+            i32::from(extract_immediate(0x0000_FFFF).0) as u32,
+            0xFFFF_FFFF
+        );
+        assert_eq!(u32::from(extract_immediate(0x0000_FFFF).1), 0x0000_FFFF);
+    }
+
+    #[test]
+    fn positive_immediate() {
+        assert_eq!(extract_immediate(0xFFFF_7FFF), (0x7FFF, 0x7FFF));
+        assert_eq!(extract_immediate(0x0000_0000), (0, 0));
+    }
+
+    #[test]
+    fn negative_address() {
+        assert_eq!(sign_extend_address(constants::ADDRESS_MASK), -1);
+        assert_eq!(
+            sign_extend_address(constants::ADDRESS_SIGN_MASK),
+            (constants::ADDRESS_SIGN_MASK | constants::ADDRESS_EXTENSION) as i32
+        );
+        assert_eq!(
+            sign_extend_address(constants::ADDRESS_SIGN_MASK),
+            -(1 << 25)
+        );
+    }
+
+    #[test]
+    fn positive_address() {
+        assert_eq!(
+            sign_extend_address(constants::ADDRESS_SIGN_MASK - 1),
+            (1 << 25) - 1
+        );
+        assert_eq!(sign_extend_address(!constants::ADDRESS_MASK), 0);
+    }
+
+    #[test]
+    fn disassemble_formats() {
+        assert_eq!(disassemble(nop!()), Ok("NOP".to_owned()));
+        assert_eq!(
+            disassemble(instr_flop!(FMUL, S0, A1, V0)),
+            Ok("FMUL $S0, $A1, $V0".to_owned())
+        );
+        assert_eq!(
+            disassemble(instr_i!(ADDI, SP, SP, -4)),
+            Ok("ADDI $SP, $SP, -4".to_owned())
+        );
+        assert_eq!(
+            disassemble(instr_i!(SLTUI, T0, T1, -1)),
+            Ok("SLTUI $T0, $T1, 65535".to_owned())
+        );
+        assert_eq!(
+            disassemble(instr_i!(SLO, T0, ZERO, -2)),
+            Ok("SLO $T0, 65534".to_owned())
+        );
+        assert_eq!(
+            disassemble(instr_i!(BNZ, ZERO, T3, -12)),
+            Ok("BNZ $T3, -12".to_owned())
+        );
+        assert_eq!(
+            disassemble(instr_i!(JR, ZERO, RA, 0)),
+            Ok("JR $RA".to_owned())
+        );
+        assert_eq!(
+            disassemble(instr_i!(COPY, T0, T1, 0)),
+            Ok("COPY $T0, $T1".to_owned())
+        );
+        assert_eq!(
+            disassemble(instr_r!(ALU, T0, T1, T2, 63)),
+            Err(DecodeError::InvalidAluFunct(63))
+        );
+        assert_eq!(
+            disassemble(0xFFFF_FFFF),
+            Err(DecodeError::InvalidOpcode(63))
+        );
+    }
+
+    #[test]
+    fn all_variants_round_trip() {
+        use util::EnumAll;
+        use RegisterId::*;
+
+        for &opcode in Opcode::all() {
+            let instruction = make_i_instruction(opcode, T0, T1, 0);
+            let decoded = (instruction & constants::OPCODE_MASK) >> constants::OPCODE_OFFSET;
+            assert_eq!(Opcode::from_u32(decoded), Some(opcode));
+
+            if opcode != Opcode::ALU && opcode != Opcode::FLOP {
+                let text = disassemble(instruction).unwrap();
+                assert!(text.starts_with(&opcode.to_string()), "{}", text);
+            }
+        }
+
+        for &funct in AluFunct::all() {
+            let text = disassemble(make_alu_instruction(funct, T0, T1, T2)).unwrap();
+            assert_eq!(text, format!("{} $T0, $T1, $T2", funct));
+        }
+
+        for &funct in FlopFunct::all() {
+            let text = disassemble(make_flop_instruction(funct, T0, T1, T2)).unwrap();
+            assert_eq!(text, format!("{} $T0, $T1, $T2", funct));
+        }
+
+        for &register in RegisterId::all() {
+            let text = disassemble(make_i_instruction(Opcode::COPY, register, ZERO, 0)).unwrap();
+            assert_eq!(text, format!("COPY ${}, $ZERO", register));
+        }
+    }
+}
@@ -0,0 +1,64 @@
+/// Controls how the results of floating point instructions are post-processed.
+///
+/// The default mode leaves all results exactly as computed by the host.
+/// Flushing denormals makes results independent of whether the host FPU supports subnormal
+/// numbers, which is useful for deterministic replay across platforms.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FloatMode {
+    /// Replace subnormal results of `FLOP` instructions with zero (keeping the sign).
+    pub flush_denormals: bool,
+    /// How `FTOI` and `FTOU` handle values which cannot be represented by the result type.
+    pub convert: FloatConvertMode,
+}
+
+impl FloatMode {
+    pub(crate) fn apply(self, value: f32) -> f32 {
+        if self.flush_denormals && value.is_subnormal() {
+            0.0f32.copysign(value)
+        } else {
+            value
+        }
+    }
+}
+
+/// Controls how float to integer conversions (`FTOI` and `FTOU`) handle NaN and values which
+/// are out of range of the result type.
+///
+/// Integer to float conversions (`ITOF` and `UTOF`) cannot overflow. They round to the nearest
+/// float in every mode.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FloatConvertMode {
+    /// Values (including infinities) are clamped to the range of the result type.
+    /// NaN produces `0`.
+    #[default]
+    Saturating,
+    /// Values are truncated and then wrapped modulo `2^32`, like integer arithmetic.
+    /// Infinities and NaN produce `0`.
+    Wrapping,
+}
+
+impl FloatConvertMode {
+    pub(crate) fn to_i32(self, value: f32) -> i32 {
+        match self {
+            // `as` saturates, and converts NaN to 0.
+            FloatConvertMode::Saturating => value as i32,
+            FloatConvertMode::Wrapping => wrap(value) as i32,
+        }
+    }
+
+    pub(crate) fn to_u32(self, value: f32) -> u32 {
+        match self {
+            FloatConvertMode::Saturating => value as u32,
+            FloatConvertMode::Wrapping => wrap(value),
+        }
+    }
+}
+
+fn wrap(value: f32) -> u32 {
+    if value.is_finite() {
+        // Every integer up to `2^32` is exact in an f64.
+        f64::from(value).trunc().rem_euclid(4_294_967_296.0) as u32
+    } else {
+        0
+    }
+}
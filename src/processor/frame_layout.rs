@@ -0,0 +1,29 @@
+/// Describes where a function saves the return address and the previous frame pointer,
+/// relative to its frame pointer (`$FP`). Used by [`Processor::stack_trace`].
+///
+/// The default layout matches this prologue, after which `$FP` points to the saved `$FP`
+/// and the saved `$RA` is located right above it:
+///
+/// ```text
+/// PUSH $RA
+/// PUSH $FP
+/// COPY $FP, $SP
+/// ```
+///
+/// [`Processor::stack_trace`]: ./struct.Processor.html#method.stack_trace
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FrameLayout {
+    /// Offset of the saved return address from `$FP` (in bytes).
+    pub return_address_offset: i32,
+    /// Offset of the saved frame pointer of the caller from `$FP` (in bytes).
+    pub previous_frame_offset: i32,
+}
+
+impl Default for FrameLayout {
+    fn default() -> FrameLayout {
+        FrameLayout {
+            return_address_offset: 4,
+            previous_frame_offset: 0,
+        }
+    }
+}
@@ -1,17 +1,31 @@
 use num::FromPrimitive;
 use std::num::Wrapping;
 
+use super::decode::{extract_immediate, sign_extend_address};
+use super::FloatMode;
 use crate::{
     constants, register_index, AluFunct, ExitCode, FlopFunct, Opcode, Register, RegisterId,
     StorageMut, Word,
 };
 
-pub enum TickResult {
+/// Result of executing a single instruction (see [`Processor::execute_word`]).
+///
+/// [`Processor::execute_word`]: ./struct.Processor.html#method.execute_word
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TickOutcome {
+    /// Execution continues with the next instruction.
     Next,
-    Jump(u32, bool),
+    /// Execution continues at `target`. If `link` is set, the address of the next instruction
+    /// has to be stored in register `RA`.
+    Jump { target: u32, link: bool },
+    /// The instruction stopped the processor with the given exit code.
     Stop(ExitCode),
+    /// Like `Next`, but the instruction wrote `size` bytes at `address` to an IO device.
+    IoWrite { address: u32, size: u32 },
 }
 
+// `$ZERO` is read-only. The assembler never emits instructions that write to it, but encoded
+// instructions can come from anywhere, so the processor keeps checking on every write.
 fn write_i(registers: &mut [Register], id: usize, value: Wrapping<i32>) {
     if id != 0 {
         registers[id].set_i(value.0);
@@ -32,7 +46,7 @@ fn write_f(registers: &mut [Register], id: usize, value: f32) {
 
 fn mul(registers: &mut [Register], id: usize, factor1: Wrapping<i32>, factor2: Wrapping<i32>) {
     let product = factor1.0 as i64 * factor2.0 as i64;
-    registers[id].set_i(product as i32);
+    write_i(registers, id, Wrapping(product as i32));
     registers[register_index(RegisterId::RM)]
         .set_i((product >> (std::mem::size_of::<i32>() * 8)) as i32);
 }
@@ -65,21 +79,48 @@ fn set_if(registers: &mut [Register], id: usize, condition: bool) {
     );
 }
 
+/// Computes the effective address `base + offset` of a load or store.
+///
+/// Returns `None` if `checked` is set and the computation overflows, otherwise the result wraps.
+fn effective_address(base: u32, offset: i16, checked: bool) -> Option<u32> {
+    if checked {
+        base.checked_add_signed(i32::from(offset))
+    } else {
+        Some(base.wrapping_add_signed(i32::from(offset)))
+    }
+}
+
 fn load(
     registers: &mut [Register],
     storage: &dyn StorageMut,
     id: usize,
-    address: Wrapping<u32>,
+    address: Option<u32>,
     size: u32,
 ) -> bool {
-    storage
-        .read(address.0, size)
+    address
+        .and_then(|address| storage.read(address, size).ok())
         .map(|v| write_u(registers, id, Wrapping(v)))
-        .is_ok()
+        .is_some()
+}
+
+fn store(storage: &mut dyn StorageMut, address: Option<u32>, size: u32, value: u32) -> TickOutcome {
+    let address = match address {
+        Some(address) => address,
+        None => return TickOutcome::Stop(ExitCode::BadMemoryAccess),
+    };
+
+    match storage.write_io(address, size, value) {
+        Ok(false) => TickOutcome::Next,
+        Ok(true) => TickOutcome::IoWrite { address, size },
+        Err(_) => TickOutcome::Stop(ExitCode::BadMemoryAccess),
+    }
 }
 
-fn jump(new_addr: Wrapping<u32>, link: bool) -> TickResult {
-    TickResult::Jump(new_addr.0, link)
+fn jump(new_addr: Wrapping<u32>, link: bool) -> TickOutcome {
+    TickOutcome::Jump {
+        target: new_addr.0,
+        link,
+    }
 }
 
 pub fn tick(
@@ -87,7 +128,9 @@ pub fn tick(
     storage: &mut dyn StorageMut,
     instruction: Word,
     program_counter: u32,
-) -> TickResult {
+    float_mode: FloatMode,
+    checked_addresses: bool,
+) -> TickOutcome {
     let op_code = (instruction & constants::OPCODE_MASK) >> constants::OPCODE_OFFSET;
     let op_code = FromPrimitive::from_u32(op_code);
 
@@ -109,20 +152,13 @@ pub fn tick(
         let rs1f = rs1.f();
         let rs2f = rs2.f();
 
-        let imm_i16 =
-            ((instruction & constants::IMMEDIATE_MASK) >> constants::IMMEDIATE_OFFSET) as i16;
-        let imm_u16 = imm_i16 as u16;
+        let (imm_i16, imm_u16) = extract_immediate(instruction);
         let imm_u = Wrapping(imm_u16 as u32);
         let imm_i = Wrapping(imm_i16 as i32);
         let imm_u_ex = Wrapping(imm_i.0 as u32);
 
-        let mut address = (instruction & constants::ADDRESS_MASK) >> constants::ADDRESS_OFFSET;
-
-        if (address & constants::ADDRESS_SIGN_MASK) != 0 {
-            address |= constants::ADDRESS_EXTENSION;
-        }
-
-        let address = Wrapping(address as u32);
+        let address = Wrapping(sign_extend_address(instruction) as u32);
+        let mem_address = effective_address(rs1u.0, imm_i16, checked_addresses);
 
         match op_code {
             Opcode::NOP => {}
@@ -147,7 +183,7 @@ pub fn tick(
 
                         AluFunct::DIV => {
                             if !div(registers, rdid, rs1i, rs2i) {
-                                return TickResult::Stop(ExitCode::DivisionByZero);
+                                return TickOutcome::Stop(ExitCode::DivisionByZero);
                             }
                         }
 
@@ -216,12 +252,12 @@ pub fn tick(
                         }
                     }
                 } else {
-                    return TickResult::Stop(ExitCode::InvalidOpcode);
+                    return TickOutcome::Stop(ExitCode::InvalidOpcode);
                 }
             }
 
             Opcode::HALT => {
-                return TickResult::Stop(ExitCode::Halted);
+                return TickOutcome::Stop(ExitCode::Halted);
             }
 
             Opcode::CALL => {
@@ -251,66 +287,43 @@ pub fn tick(
             }
 
             Opcode::LB => {
-                if !load(
-                    registers,
-                    storage,
-                    rdid,
-                    rs1u + imm_u_ex,
-                    constants::BYTE_BYTES,
-                ) {
-                    return TickResult::Stop(ExitCode::BadMemoryAccess);
+                if !load(registers, storage, rdid, mem_address, constants::BYTE_BYTES) {
+                    return TickOutcome::Stop(ExitCode::BadMemoryAccess);
                 }
             }
 
             Opcode::LH => {
-                if !load(
-                    registers,
-                    storage,
-                    rdid,
-                    rs1u + imm_u_ex,
-                    constants::HALF_BYTES,
-                ) {
-                    return TickResult::Stop(ExitCode::BadMemoryAccess);
+                if !load(registers, storage, rdid, mem_address, constants::HALF_BYTES) {
+                    return TickOutcome::Stop(ExitCode::BadMemoryAccess);
                 }
             }
 
             Opcode::LW => {
-                if !load(
-                    registers,
-                    storage,
-                    rdid,
-                    rs1u + imm_u_ex,
-                    constants::WORD_BYTES,
-                ) {
-                    return TickResult::Stop(ExitCode::BadMemoryAccess);
+                if !load(registers, storage, rdid, mem_address, constants::WORD_BYTES) {
+                    return TickOutcome::Stop(ExitCode::BadMemoryAccess);
                 }
             }
 
             Opcode::SB => {
-                if storage
-                    .write_byte((rs1u + imm_u_ex).0, rd.u() as u8)
-                    .is_err()
-                {
-                    return TickResult::Stop(ExitCode::BadMemoryAccess);
-                }
+                return store(
+                    storage,
+                    mem_address,
+                    constants::BYTE_BYTES,
+                    rd.u() as u8 as u32,
+                );
             }
 
             Opcode::SH => {
-                if storage
-                    .write_half((rs1u + imm_u_ex).0, rd.u() as u16)
-                    .is_err()
-                {
-                    return TickResult::Stop(ExitCode::BadMemoryAccess);
-                }
+                return store(
+                    storage,
+                    mem_address,
+                    constants::HALF_BYTES,
+                    rd.u() as u16 as u32,
+                );
             }
 
             Opcode::SW => {
-                if storage
-                    .write_word((rs1u + imm_u_ex).0, rd.u() as u32)
-                    .is_err()
-                {
-                    return TickResult::Stop(ExitCode::BadMemoryAccess);
-                }
+                return store(storage, mem_address, constants::WORD_BYTES, rd.u());
             }
 
             Opcode::ADDI => {
@@ -327,7 +340,7 @@ pub fn tick(
 
             Opcode::DIVI => {
                 if !div(registers, rdid, rs1i, imm_i) {
-                    return TickResult::Stop(ExitCode::DivisionByZero);
+                    return TickOutcome::Stop(ExitCode::DivisionByZero);
                 }
             }
 
@@ -429,44 +442,33 @@ pub fn tick(
 
             Opcode::ITOF => write_f(registers, rdid, rs1i.0 as f32),
 
-            Opcode::FTOI => {
-                let i = if rs1f.is_finite() {
-                    rs1f as i32
-                } else {
-                    i32::MIN
-                };
-                write_i(registers, rdid, Wrapping(i));
-            }
+            Opcode::FTOI => write_i(registers, rdid, Wrapping(float_mode.convert.to_i32(rs1f))),
+
+            Opcode::UTOF => write_f(registers, rdid, rs1u.0 as f32),
+
+            Opcode::FTOU => write_u(registers, rdid, Wrapping(float_mode.convert.to_u32(rs1f))),
 
             Opcode::FLOP => {
                 let funct_value = (instruction & constants::FUNCT_MASK) >> constants::FUNCT_OFFSET;
                 let funct = FlopFunct::from_u32(funct_value);
                 if let Some(funct) = funct {
-                    match funct {
-                        FlopFunct::FADD => {
-                            write_f(registers, rdid, rs1f + rs2f);
-                        }
-
-                        FlopFunct::FSUB => {
-                            write_f(registers, rdid, rs1f - rs2f);
-                        }
-
-                        FlopFunct::FMUL => {
-                            write_f(registers, rdid, rs1f * rs2f);
-                        }
-
-                        FlopFunct::FDIV => {
-                            write_f(registers, rdid, rs1f / rs2f);
-                        }
-                    }
+                    let result = match funct {
+                        FlopFunct::FADD => rs1f + rs2f,
+                        FlopFunct::FSUB => rs1f - rs2f,
+                        FlopFunct::FMUL => rs1f * rs2f,
+                        FlopFunct::FDIV => rs1f / rs2f,
+                        FlopFunct::FMOD => rs1f % rs2f,
+                    };
+
+                    write_f(registers, rdid, float_mode.apply(result));
                 } else {
-                    return TickResult::Stop(ExitCode::InvalidOpcode);
+                    return TickOutcome::Stop(ExitCode::InvalidOpcode);
                 }
             }
         }
     } else {
-        return TickResult::Stop(ExitCode::InvalidOpcode);
+        return TickOutcome::Stop(ExitCode::InvalidOpcode);
     }
 
-    TickResult::Next
+    TickOutcome::Next
 }
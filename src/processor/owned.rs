@@ -0,0 +1,84 @@
+use super::{ExitCode, Processor};
+use crate::StorageMut;
+
+/// A [`Processor`] together with the storage it runs on, e.g. to reuse both from a pool.
+///
+/// Unlike [`Processor::reset`], [`reset`] zeroes the storage as well, except for IO devices
+/// (see [`StorageMut::zero_fill`]).
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+///
+/// let instructions = instructions_from_words(&[
+///     instr_i!(LI, T0, ZERO, 1),
+///     instr_i!(SW, T0, ZERO, 0),
+///     instr_i!(HALT, ZERO, ZERO, 0),
+/// ]);
+///
+/// let mut owned = OwnedProcessor::new([0u8; 4]);
+/// assert_eq!(owned.run(&instructions), ExitCode::Halted);
+/// assert_eq!(owned.memory(), &[1, 0, 0, 0]);
+///
+/// owned.reset();
+/// assert_eq!(owned.memory(), &[0, 0, 0, 0]);
+/// assert_eq!(owned.processor().state(), None);
+/// ```
+/// [`Processor`]: ./struct.Processor.html
+/// [`Processor::reset`]: ./struct.Processor.html#method.reset
+/// [`reset`]: #method.reset
+/// [`StorageMut::zero_fill`]: ./trait.StorageMut.html#method.zero_fill
+#[derive(Clone, PartialEq)]
+pub struct OwnedProcessor<S: StorageMut> {
+    processor: Processor,
+    memory: S,
+}
+
+impl<S: StorageMut> OwnedProcessor<S> {
+    /// Constructs a default processor which runs on `memory`.
+    pub fn new(memory: S) -> OwnedProcessor<S> {
+        OwnedProcessor::with_processor(Processor::default(), memory)
+    }
+
+    /// Combines an already configured `processor` with `memory`.
+    pub fn with_processor(processor: Processor, memory: S) -> OwnedProcessor<S> {
+        OwnedProcessor { processor, memory }
+    }
+
+    pub fn processor(&self) -> &Processor {
+        &self.processor
+    }
+
+    pub fn processor_mut(&mut self) -> &mut Processor {
+        &mut self.processor
+    }
+
+    pub fn memory(&self) -> &S {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut S {
+        &mut self.memory
+    }
+
+    /// Returns the processor and the memory.
+    pub fn into_inner(self) -> (Processor, S) {
+        (self.processor, self.memory)
+    }
+
+    /// Runs the processor on the memory until it stops (see [`Processor::run`]).
+    ///
+    /// [`Processor::run`]: ./struct.Processor.html#method.run
+    pub fn run(&mut self, instructions: &[u8]) -> ExitCode {
+        self.processor.run(instructions, &mut self.memory)
+    }
+
+    /// Resets the processor (see [`Processor::reset`]) and zeroes the memory, except for IO
+    /// devices.
+    ///
+    /// [`Processor::reset`]: ./struct.Processor.html#method.reset
+    pub fn reset(&mut self) {
+        self.processor.reset();
+        self.memory.zero_fill();
+    }
+}
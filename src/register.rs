@@ -1,3 +1,18 @@
+/// A processor register, which holds 32 bits that can be viewed as `i32`, `u32` or `f32`.
+///
+/// All views share the same bits, so reading a different view than the one that was last
+/// written is a bitcast (like [`f32::to_bits`]/[`f32::from_bits`]), not a value conversion.
+///
+/// # Examples
+/// ```
+/// use vcpu::Register;
+///
+/// let register = Register::from(1.0f32);
+/// assert_eq!(register.to_bits(), 0x3F80_0000);
+/// assert_eq!(Register::from_bits(0xFFFF_FFFF).i(), -1);
+/// ```
+/// [`f32::to_bits`]: https://doc.rust-lang.org/std/primitive.f32.html#method.to_bits
+/// [`f32::from_bits`]: https://doc.rust-lang.org/std/primitive.f32.html#method.from_bits
 #[derive(Clone, Copy)]
 pub union Register {
     i: i32,
@@ -6,16 +21,30 @@ pub union Register {
 }
 
 impl Register {
+    /// Constructs a register from its raw bits.
+    pub fn from_bits(bits: u32) -> Register {
+        Register { u: bits }
+    }
+
+    /// Returns the raw bits of the register.
+    pub fn to_bits(self) -> u32 {
+        // All fields are plain 32 bit values without invalid bit patterns.
+        unsafe { self.u }
+    }
+
+    /// Returns the bits of the register as `i32`.
     pub fn i(self) -> i32 {
-        unsafe { self.i }
+        self.to_bits() as i32
     }
 
+    /// Returns the bits of the register as `u32`.
     pub fn u(self) -> u32 {
-        unsafe { self.u }
+        self.to_bits()
     }
 
+    /// Returns the bits of the register as `f32`.
     pub fn f(self) -> f32 {
-        unsafe { self.f }
+        f32::from_bits(self.to_bits())
     }
 
     pub fn set_i(&mut self, value: i32) {
@@ -55,6 +84,24 @@ impl From<f32> for Register {
     }
 }
 
+impl From<Register> for i32 {
+    fn from(r: Register) -> i32 {
+        r.i()
+    }
+}
+
+impl From<Register> for u32 {
+    fn from(r: Register) -> u32 {
+        r.u()
+    }
+}
+
+impl From<Register> for f32 {
+    fn from(r: Register) -> f32 {
+        r.f()
+    }
+}
+
 impl PartialEq for Register {
     fn eq(&self, rhs: &Register) -> bool {
         self.u() == rhs.u()
@@ -70,3 +117,33 @@ impl std::fmt::Debug for Register {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn float_bits() {
+        assert_eq!(Register::from(1.0f32).to_bits(), 0x3F80_0000);
+        assert_eq!(Register::from_bits(0x3F80_0000).f(), 1.0f32);
+        assert_eq!(Register::from(-0.0f32).to_bits(), 0x8000_0000);
+    }
+
+    #[test]
+    fn integer_bits() {
+        assert_eq!(Register::from(-1i32).to_bits(), 0xFFFF_FFFF);
+        assert_eq!(Register::from_bits(0x8000_0000).i(), i32::MIN);
+        assert_eq!(Register::from(42u32).i(), 42);
+    }
+
+    #[test]
+    fn typed_reads() {
+        let register = Register::from_bits(0x4049_0FDB);
+        assert_eq!(u32::from(register), 0x4049_0FDB);
+        assert_eq!(i32::from(register), 0x4049_0FDB);
+        assert_eq!(f32::from(register), std::f32::consts::PI);
+
+        let value: f32 = register.into();
+        assert_eq!(value, std::f32::consts::PI);
+    }
+}
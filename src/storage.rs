@@ -1,5 +1,13 @@
 use super::{constants, Endian};
 use byteorder::ByteOrder;
+use std::convert::TryFrom;
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+pub(crate) fn fnv1a(hash: u64, byte: u8) -> u64 {
+    (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+}
 
 /// Represents a memory storage unit with basic read operations.
 pub trait Storage {
@@ -110,6 +118,96 @@ pub trait Storage {
     fn read_word(&self, address: u32) -> Result<u32, ()> {
         Ok(self.read(address, constants::WORD_BYTES)?)
     }
+
+    /// Reads [`BYTE_BYTES`] bytes starting at the specified address and returns the result as `i8`.
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+BYTE_BYTES]` is not addressable.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::Storage;
+    ///
+    /// let memory = [0xFFu8, 0x7Fu8];
+    /// assert_eq!(memory.read_i8(0), Ok(-1));
+    /// assert_eq!(memory.read_i8(1), Ok(127));
+    /// assert_eq!(memory.read_i8(2), Err(()));
+    /// ```
+    /// [`BYTE_BYTES`]: ../constants/constant.BYTE_BYTES.html
+    fn read_i8(&self, address: u32) -> Result<i8, ()> {
+        Ok(self.read_byte(address)? as i8)
+    }
+
+    /// Reads [`HALF_BYTES`] bytes starting at the specified address and converts the result to `i16`.
+    ///
+    /// The conversion is always performed with the endianness defined by the [`Endian`] type alias.
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+HALF_BYTES]` is not addressable.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::Storage;
+    ///
+    /// let memory = [0xFFu8, 0xFFu8, 0x00u8, 0x80u8];
+    /// assert_eq!(memory.read_i16(0), Ok(-1));
+    /// assert_eq!(memory.read_i16(2), Ok(i16::min_value()));
+    /// assert_eq!(memory.read_i16(3), Err(()));
+    /// ```
+    /// [`HALF_BYTES`]: ../constants/constant.HALF_BYTES.html
+    /// [`Endian`]: ../type.Endian.html
+    fn read_i16(&self, address: u32) -> Result<i16, ()> {
+        Ok(self.read_half(address)? as i16)
+    }
+
+    /// Reads [`WORD_BYTES`] bytes starting at the specified address and converts the result to `i32`.
+    ///
+    /// The conversion is always performed with the endianness defined by the [`Endian`] type alias.
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+WORD_BYTES]` is not addressable.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::Storage;
+    ///
+    /// let memory = [0xFEu8, 0xFFu8, 0xFFu8, 0xFFu8];
+    /// assert_eq!(memory.read_i32(0), Ok(-2));
+    /// assert_eq!(memory.read_i32(1), Err(()));
+    /// ```
+    /// [`WORD_BYTES`]: ../constants/constant.WORD_BYTES.html
+    /// [`Endian`]: ../type.Endian.html
+    fn read_i32(&self, address: u32) -> Result<i32, ()> {
+        Ok(self.read_word(address)? as i32)
+    }
+
+    /// Computes a 64 bit FNV-1a hash of the bytes in the range `[address..address+length]`.
+    ///
+    /// Equal contents always produce equal checksums, regardless of the type of storage,
+    /// so this can be used to compare memory states without copying them.
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+length]` is not addressable.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::Storage;
+    ///
+    /// let a = [1u8, 2u8, 3u8, 4u8];
+    /// let b = vec![0u8, 2u8, 3u8, 4u8];
+    /// assert_eq!(a.checksum(1, 3), b.checksum(1, 3));
+    /// assert_ne!(a.checksum(0, 4), b.checksum(0, 4));
+    /// assert_eq!(a.checksum(2, 4), Err(()));
+    /// ```
+    fn checksum(&self, address: u32, length: u32) -> Result<u64, ()> {
+        if !self.check_range(address, length) {
+            return Err(());
+        }
+
+        (address..address + length).try_fold(FNV_OFFSET_BASIS, |hash, a| {
+            Ok(fnv1a(hash, self.read_byte(a)?))
+        })
+    }
 }
 
 impl<T> Storage for T
@@ -122,7 +220,7 @@ where
 
     fn check_range(&self, address: u32, length: u32) -> bool {
         let len = self.as_ref().len() as u32;
-        address <= len && (address + length) <= len
+        address <= len && length <= len - address
     }
 
     fn read(&self, address: u32, size: u32) -> Result<u32, ()> {
@@ -137,6 +235,16 @@ where
             Err(())
         }
     }
+
+    fn checksum(&self, address: u32, length: u32) -> Result<u64, ()> {
+        if self.check_range(address, length) {
+            Ok(self.as_ref()[address as usize..(address + length) as usize]
+                .iter()
+                .fold(FNV_OFFSET_BASIS, |hash, byte| fnv1a(hash, *byte)))
+        } else {
+            Err(())
+        }
+    }
 }
 
 /// Represents a mutable memory storage unit with basic read and write operations.
@@ -225,6 +333,128 @@ pub trait StorageMut: Storage {
     fn write_word(&mut self, address: u32, value: u32) -> Result<(), ()> {
         self.write(address, constants::WORD_BYTES, value)
     }
+
+    /// Writes like [`write`], and additionally returns whether the write was accepted by an IO device.
+    ///
+    /// The default implementation never reports IO writes. [`IOMemory`] reports every write that its
+    /// handler accepts, and [`CompositeMemory`] forwards to the fragment the address belongs to.
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+size]` is not addressable.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::{Storage, StorageMut};
+    ///
+    /// let mut memory = [0u8; 4];
+    /// assert_eq!(memory.write_io(0, 2, 32938), Ok(false));
+    /// assert_eq!(memory.read_half(0), Ok(32938));
+    /// ```
+    /// [`write`]: #tymethod.write
+    /// [`IOMemory`]: ../struct.IOMemory.html
+    /// [`CompositeMemory`]: ../struct.CompositeMemory.html
+    fn write_io(&mut self, address: u32, size: u32, value: u32) -> Result<bool, ()> {
+        self.write(address, size, value).map(|_| false)
+    }
+
+    /// Checks whether writes to the range `[address..address+length]` can succeed, e.g. to show
+    /// memory protection in a debugger. Unlike [`check_range`], this is `false` for read-only
+    /// storage and for IO devices which don't accept writes to the range.
+    ///
+    /// The default implementation is the same as [`check_range`].
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::{ReadOnlyMemory, Storage, StorageMut};
+    ///
+    /// let memory = [0u8; 4];
+    /// assert!(memory.check_range(0, 4) && memory.is_writable(0, 4));
+    ///
+    /// let rom = ReadOnlyMemory::new(memory);
+    /// assert!(rom.check_range(0, 4) && !rom.is_writable(0, 4));
+    /// ```
+    /// [`check_range`]: ./trait.Storage.html#tymethod.check_range
+    fn is_writable(&self, address: u32, length: u32) -> bool {
+        self.check_range(address, length)
+    }
+
+    /// Writes all bytes of `src` starting at `address`, or none of them.
+    ///
+    /// Before writing anything, the whole range is checked with [`is_writable`], so a write
+    /// that would fail part of the way (e.g. at the end of a fragment of a [`CompositeMemory`])
+    /// leaves the storage unchanged.
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+src.len()]` is not writable.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::{Storage, StorageMut};
+    ///
+    /// let mut memory = [0u8; 8];
+    /// assert_eq!(memory.try_write_from(4, &[1, 2, 3, 4]), Ok(()));
+    /// assert_eq!(memory.try_write_from(6, &[5, 6, 7, 8]), Err(()));
+    /// assert_eq!(memory, [0, 0, 0, 0, 1, 2, 3, 4]);
+    /// ```
+    /// [`is_writable`]: #method.is_writable
+    /// [`CompositeMemory`]: ../struct.CompositeMemory.html
+    fn try_write_from(&mut self, address: u32, src: &[u8]) -> Result<(), ()> {
+        let length = u32::try_from(src.len()).map_err(|_| ())?;
+        if !self.is_writable(address, length) {
+            return Err(());
+        }
+
+        for (offset, byte) in (0..length).zip(src) {
+            self.write_byte(address + offset, *byte)?;
+        }
+
+        Ok(())
+    }
+
+    /// Borrows the range `[address..address+length]` as a mutable slice for editing in place.
+    ///
+    /// The default implementation always returns an error, since not every storage is backed by
+    /// contiguous memory, or it may need to observe every write (like [`IOMemory`]).
+    ///
+    /// # Errors
+    /// Returns an error if the range `[address..address+length]` is not addressable,
+    /// or cannot be borrowed as one contiguous slice.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::{Storage, StorageMut};
+    ///
+    /// let mut memory = [0u8; 8];
+    /// memory.borrow_slice_mut(2, 4).unwrap().copy_from_slice(&[1, 2, 3, 4]);
+    /// assert_eq!(memory.read_word(2), Ok(0x0403_0201));
+    /// assert!(memory.borrow_slice_mut(6, 4).is_err());
+    /// ```
+    /// [`IOMemory`]: ../struct.IOMemory.html
+    fn borrow_slice_mut(&mut self, _address: u32, _length: u32) -> Result<&mut [u8], ()> {
+        Err(())
+    }
+
+    /// Sets all bytes of the storage to zero, e.g. when a processor holding it is reset.
+    ///
+    /// The default implementation zeroes the slice borrowed with [`borrow_slice_mut`].
+    /// Storages which can't be borrowed, like IO devices, are left unchanged, so that no
+    /// write handlers are triggered.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::StorageMut;
+    ///
+    /// let mut memory = [1u8, 2, 3, 4];
+    /// memory.zero_fill();
+    /// assert_eq!(memory, [0; 4]);
+    /// ```
+    /// [`borrow_slice_mut`]: #method.borrow_slice_mut
+    fn zero_fill(&mut self) {
+        let length = self.length();
+        if let Ok(slice) = self.borrow_slice_mut(0, length) {
+            slice.iter_mut().for_each(|byte| *byte = 0);
+        }
+    }
 }
 
 impl<T> StorageMut for T
@@ -245,4 +475,12 @@ where
             Err(())
         }
     }
+
+    fn borrow_slice_mut(&mut self, address: u32, length: u32) -> Result<&mut [u8], ()> {
+        if self.check_range(address, length) {
+            Ok(&mut self.as_mut()[address as usize..(address + length) as usize])
+        } else {
+            Err(())
+        }
+    }
 }
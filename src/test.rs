@@ -1,4 +1,5 @@
 use super::*;
+use std::rc::Rc;
 
 macro_rules! instr {
     (a $opcode:ident $rd:ident $rs1:ident $rs2:ident) => {
@@ -35,6 +36,20 @@ fn wrapping_arithmetic() {
     assert_eq!(c, 0u32);
 }
 
+#[test]
+fn tiny_storage_out_of_range() {
+    let mut memory = [0u8; 2];
+
+    assert_eq!(memory.read(0, 4), Err(()));
+    assert_eq!(memory.write(0, 4, 0x1234_5678), Err(()));
+    assert_eq!(memory.read_word(1), Err(()));
+    assert_eq!(memory, [0u8; 2]);
+
+    assert!(!memory.check_range(1, u32::MAX));
+    assert!(!memory.check_range(u32::MAX, 4));
+    assert!(memory.check_range(2, 0));
+}
+
 #[allow(dead_code)]
 fn test_instructions_me(
     mem_size: u32,
@@ -151,3 +166,858 @@ fn truncation_in_store_instruction() {
 }
 
 mod instructions;
+
+#[test]
+fn trap_handler_recovers_from_bad_memory_access() {
+    let instructions = instructions_from_words(&instructions![
+        (i LI T0 ZERO 1),
+        (i LW T1 ZERO 0x100),
+        (i LI T2 ZERO 3),
+        (i HALT ZERO ZERO 0),
+        (i COPY T3 RM 0),
+        (i ADDI RA RA 4),
+        (i JR ZERO RA 0)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    processor.set_trap_handler(16);
+    assert_eq!(processor.trap_handler(), Some(16));
+    assert_eq!(processor.run(&instructions, &mut storage), ExitCode::Halted);
+
+    assert_eq!(processor.register(RegisterId::T0).i(), 1);
+    assert_eq!(processor.register(RegisterId::T1).i(), 0);
+    assert_eq!(processor.register(RegisterId::T2).i(), 3);
+    assert_eq!(
+        processor.register(RegisterId::T3).u(),
+        enum_to_u32(ExitCode::BadMemoryAccess)
+    );
+}
+
+#[test]
+fn trap_handler_not_set() {
+    let instructions = instructions_from_words(&instructions![
+        (i LW T1 ZERO 0x100),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    assert_eq!(
+        processor.run(&instructions, &mut storage),
+        ExitCode::BadMemoryAccess
+    );
+}
+
+#[test]
+fn trap_handler_out_of_range() {
+    let instructions = instructions_from_words(&instructions![
+        (i LW T1 ZERO 0x100),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    processor.set_trap_handler(64);
+    assert_eq!(
+        processor.run(&instructions, &mut storage),
+        ExitCode::BadJump
+    );
+}
+
+#[test]
+fn set_program_counter_skips_instructions() {
+    let instructions = instructions_from_words(&instructions![
+        (i LI T0 ZERO 1),
+        (i LI T1 ZERO 2),
+        (i LI T2 ZERO 3),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    assert_eq!(processor.tick(&instructions, &mut storage), None);
+    assert_eq!(
+        processor.set_program_counter(8, Some(instructions.len() as u32)),
+        Ok(())
+    );
+    assert_eq!(processor.run(&instructions, &mut storage), ExitCode::Halted);
+
+    assert_eq!(processor.register(RegisterId::T0).i(), 1);
+    assert_eq!(processor.register(RegisterId::T1).i(), 0);
+    assert_eq!(processor.register(RegisterId::T2).i(), 3);
+}
+
+#[test]
+fn set_program_counter_resumes_after_halt() {
+    let instructions = instructions_from_words(&instructions![
+        (i ADDI T0 T0 1),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    assert_eq!(processor.run(&instructions, &mut storage), ExitCode::Halted);
+    assert_eq!(processor.set_program_counter(0, None), Ok(()));
+    assert!(!processor.is_stopped());
+    assert_eq!(processor.run(&instructions, &mut storage), ExitCode::Halted);
+
+    assert_eq!(processor.register(RegisterId::T0).i(), 2);
+}
+
+#[test]
+fn set_program_counter_invalid() {
+    let mut processor = Processor::default();
+
+    assert_eq!(
+        processor.set_program_counter(6, None),
+        Err(ExitCode::BadAlignment)
+    );
+    assert_eq!(
+        processor.set_program_counter(16, Some(16)),
+        Err(ExitCode::BadJump)
+    );
+    assert_eq!(processor.program_counter(), 0);
+}
+
+#[test]
+fn set_program_counter_end_of_address_space() {
+    let instructions = instructions_from_words(&[instr_i!(HALT, ZERO, ZERO, 0)]);
+    let mut processor = Processor::default();
+
+    assert_eq!(processor.set_program_counter(u32::MAX - 3, None), Ok(()));
+    assert_eq!(
+        processor.tick(&instructions, &mut empty_storage!()),
+        Some(ExitCode::BadProgramCounter)
+    );
+    assert_eq!(processor.program_counter(), u32::MAX - 3);
+}
+
+#[test]
+fn total_cycles_default_cost() {
+    let instructions = instructions_from_words(&instructions![
+        (i LI T0 ZERO 10),
+        (i LI T1 ZERO 2),
+        (a DIV T2 T0 T1),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let (processor, _) = test_instructions(&instructions[..]);
+
+    assert_eq!(processor.total_cycles(), 4);
+}
+
+#[test]
+fn total_cycles_custom_cost() {
+    let instructions = instructions_from_words(&instructions![
+        (i LI T0 ZERO 10),
+        (i LI T1 ZERO 2),
+        (a DIV T2 T0 T1),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut table = CostTable::default();
+    table.set_alu_cost(AluFunct::DIV, 10);
+
+    let mut processor = Processor::default();
+    processor.set_cost_table(table);
+
+    let mut storage = empty_storage!();
+    assert_eq!(processor.run(&instructions, &mut storage), ExitCode::Halted);
+    assert_eq!(processor.total_cycles(), 13);
+
+    processor.reset();
+    assert_eq!(processor.total_cycles(), 0);
+}
+
+#[test]
+fn exit_code_name_round_trip() {
+    use num::FromPrimitive;
+    use util::InteropGetName;
+
+    let mut value = 0;
+    while let Some(exit_code) = ExitCode::from_u32(value) {
+        let name = exit_code.to_string();
+        assert_eq!(name.parse::<ExitCode>(), Ok(exit_code));
+
+        let interop_name = exit_code.interop_name();
+        assert_eq!(&interop_name[..interop_name.len() - 1], name.as_bytes());
+
+        value += 1;
+    }
+
+    assert_eq!(value, 7);
+    assert!("Crashed".parse::<ExitCode>().is_err());
+}
+
+#[test]
+fn stores_do_not_modify_instructions() {
+    let instructions = instructions_from_words(&instructions![
+        (i LI T0 ZERO 0x1234),
+        (i SW T0 ZERO 12),
+        (i NOP ZERO ZERO 0),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = [0u8; 16];
+
+    assert_eq!(processor.run(&instructions, &mut storage), ExitCode::Halted);
+    assert_eq!(storage.read_word(12), Ok(0x1234));
+    assert_eq!(processor.program_counter(), 12);
+}
+
+fn fault_pc_after_run(instructions: &[Word]) -> (ExitCode, Option<u32>) {
+    let instructions = instructions_from_words(instructions);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    let exit_code = processor.run(&instructions, &mut storage);
+    (exit_code, processor.fault_pc())
+}
+
+#[test]
+fn fault_pc_division_by_zero() {
+    assert_eq!(
+        fault_pc_after_run(&instructions![
+            (i LI T0 ZERO 5),
+            (a DIV T1 T0 ZERO),
+            (i HALT ZERO ZERO 0)
+        ]),
+        (ExitCode::DivisionByZero, Some(4))
+    );
+}
+
+#[test]
+fn fault_pc_bad_memory_access() {
+    assert_eq!(
+        fault_pc_after_run(&instructions![
+            (i NOP ZERO ZERO 0),
+            (i NOP ZERO ZERO 0),
+            (i LW T0 ZERO 0x100),
+            (i HALT ZERO ZERO 0)
+        ]),
+        (ExitCode::BadMemoryAccess, Some(8))
+    );
+}
+
+#[test]
+fn fault_pc_bad_jump() {
+    assert_eq!(
+        fault_pc_after_run(&instructions![
+            (i NOP ZERO ZERO 0),
+            (j JMP jmp_addr_i32(10)),
+            (i HALT ZERO ZERO 0)
+        ]),
+        (ExitCode::BadJump, Some(4))
+    );
+}
+
+#[test]
+fn fault_pc_halted() {
+    assert_eq!(
+        fault_pc_after_run(&instructions![(i HALT ZERO ZERO 0)]),
+        (ExitCode::Halted, None)
+    );
+}
+
+#[test]
+fn run_to_breakpoint() {
+    let instructions = instructions_from_words(&instructions![
+        (i LI T0 ZERO 0),
+        (i ADDI T0 T0 1),
+        (i SLTI T1 T0 3),
+        (i BNZ ZERO T1 jmp_addr_i16(-2)),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    processor.set_breakpoint(8);
+    assert!(processor.has_breakpoint(8));
+
+    for i in 1..=3 {
+        assert_eq!(
+            processor.run_to_breakpoint(&instructions, &mut storage),
+            None
+        );
+        assert_eq!(processor.program_counter(), 8);
+        assert_eq!(processor.register(RegisterId::T0).i(), i);
+    }
+
+    assert!(processor.clear_breakpoint(8));
+    assert!(!processor.clear_breakpoint(8));
+
+    assert_eq!(
+        processor.run_to_breakpoint(&instructions, &mut storage),
+        Some(ExitCode::Halted)
+    );
+}
+
+#[test]
+fn run_bounded() {
+    let instructions = instructions_from_words(&instructions![
+        (i ADDI T0 T0 1),
+        (i SLTI T1 T0 10),
+        (i BNZ ZERO T1 jmp_addr_i16(-2)),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    assert_eq!(processor.run_bounded(&instructions, &mut storage, 9), None);
+    assert_eq!(processor.register(RegisterId::T0).i(), 3);
+    assert!(!processor.is_stopped());
+
+    assert_eq!(
+        processor.run_bounded(&instructions, &mut storage, 1000),
+        Some(ExitCode::Halted)
+    );
+    assert_eq!(processor.register(RegisterId::T0).i(), 10);
+    assert_eq!(processor.total_cycles(), 31);
+    assert_eq!(
+        processor.run_bounded(&instructions, &mut storage, 0),
+        Some(ExitCode::Halted)
+    );
+}
+
+fn run_with_address_mode(instruction: Word, base: u32, checked: bool) -> (ExitCode, [u8; 4]) {
+    let instructions = instructions_from_words(&[instruction, instr_i!(HALT, ZERO, ZERO, 0)]);
+
+    let mut processor = Processor::default();
+    processor.set_checked_addresses(checked);
+    processor.register_mut(RegisterId::T0).set_u(base);
+    processor.register_mut(RegisterId::T1).set_u(0xAB);
+
+    let mut storage = [0u8; 4];
+    let exit_code = processor.run(&instructions, &mut storage);
+    (exit_code, storage)
+}
+
+#[test]
+fn effective_address_wraps() {
+    assert_eq!(
+        run_with_address_mode(instr_i!(SB, T1, T0, 2), u32::MAX, false),
+        (ExitCode::Halted, [0, 0xAB, 0, 0])
+    );
+    assert_eq!(
+        run_with_address_mode(instr_i!(LB, T1, T0, 2), u32::MAX, false),
+        (ExitCode::Halted, [0; 4])
+    );
+    assert_eq!(
+        run_with_address_mode(instr_i!(SW, T1, T0, -4), 0, false),
+        (ExitCode::BadMemoryAccess, [0; 4])
+    );
+}
+
+#[test]
+fn effective_address_checked() {
+    assert_eq!(
+        run_with_address_mode(instr_i!(SB, T1, T0, 2), u32::MAX, true),
+        (ExitCode::BadMemoryAccess, [0; 4])
+    );
+    assert_eq!(
+        run_with_address_mode(instr_i!(LW, T1, T0, 2), u32::MAX, true),
+        (ExitCode::BadMemoryAccess, [0; 4])
+    );
+    assert_eq!(
+        run_with_address_mode(instr_i!(SB, T1, T0, -1), 3, true),
+        (ExitCode::Halted, [0, 0, 0xAB, 0])
+    );
+}
+
+#[test]
+fn execute_word() {
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    assert_eq!(
+        processor.execute_word(instr_i!(ADDI, T0, T0, 7), &mut storage),
+        TickOutcome::Next
+    );
+    assert_eq!(
+        processor.execute_word(instr_i!(ADDI, T0, T0, 7), &mut storage),
+        TickOutcome::Next
+    );
+    assert_eq!(processor.register(RegisterId::T0).i(), 14);
+
+    processor.set_program_counter(16, None).unwrap();
+    assert_eq!(
+        processor.execute_word(instr_j!(JMP, jmp_addr_i32(-2)), &mut storage),
+        TickOutcome::Jump {
+            target: 8,
+            link: false
+        }
+    );
+    assert_eq!(
+        processor.execute_word(instr_j!(JL, jmp_addr_i32(3)), &mut storage),
+        TickOutcome::Jump {
+            target: 28,
+            link: true
+        }
+    );
+    assert_eq!(
+        processor.execute_word(instr_i!(DIVI, T0, T0, 0), &mut storage),
+        TickOutcome::Stop(ExitCode::DivisionByZero)
+    );
+
+    assert_eq!(processor.program_counter(), 16);
+    assert_eq!(processor.register(RegisterId::RA).u(), 0);
+    assert_eq!(processor.state(), None);
+    assert_eq!(processor.total_cycles(), 0);
+}
+
+#[test]
+fn last_instruction_of_invalid_opcode() {
+    let invalid = 0xFFFF_FFFF;
+    let instructions = instructions_from_words(&[instr_i!(ADDI, T0, T0, 1), invalid]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+    assert_eq!(processor.last_instruction(), None);
+
+    processor.tick(&instructions, &mut storage);
+    assert_eq!(
+        processor.last_instruction(),
+        Some(instr_i!(ADDI, T0, T0, 1))
+    );
+
+    assert_eq!(
+        processor.run(&instructions, &mut storage),
+        ExitCode::InvalidOpcode
+    );
+    assert_eq!(processor.last_instruction(), Some(invalid));
+
+    processor.reset();
+    assert_eq!(processor.last_instruction(), None);
+}
+
+#[test]
+fn run_to() {
+    let instructions = instructions_from_words(&instructions![
+        (i ADDI T0 T0 1),
+        (i SLTI T1 T0 3),
+        (i BNZ ZERO T1 jmp_addr_i16(-2)),
+        (i HALT ZERO ZERO 0)
+    ]);
+    let branch = 2 * WORD_BYTES;
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    assert_eq!(
+        processor.run_to(&instructions, &mut storage, branch, 100),
+        Ok(RunToOutcome::ReachedTarget)
+    );
+    assert_eq!(processor.program_counter(), branch);
+    assert_eq!(processor.register(RegisterId::T0).i(), 1);
+    assert_eq!(processor.register(RegisterId::T1).i(), 1);
+
+    assert_eq!(
+        processor.run_to(&instructions, &mut storage, branch, 100),
+        Ok(RunToOutcome::ReachedTarget)
+    );
+    assert_eq!(processor.register(RegisterId::T0).i(), 2);
+
+    let halt = 3 * WORD_BYTES;
+    assert_eq!(
+        processor.run_to(&instructions, &mut storage, halt, 1),
+        Ok(RunToOutcome::TickLimit)
+    );
+    assert_eq!(
+        processor.run_to(&instructions, &mut storage, halt, 100),
+        Ok(RunToOutcome::ReachedTarget)
+    );
+    assert_eq!(
+        processor.run_to(&instructions, &mut storage, halt, 100),
+        Ok(RunToOutcome::Stopped(ExitCode::Halted))
+    );
+    assert_eq!(processor.register(RegisterId::T0).i(), 3);
+}
+
+#[test]
+fn run_to_unaligned() {
+    let mut processor = Processor::default();
+    assert_eq!(
+        processor.run_to(&[], &mut empty_storage!(), 2, 1),
+        Err(ExitCode::BadAlignment)
+    );
+    assert_eq!(processor.state(), None);
+}
+
+#[test]
+fn coverage() {
+    let instructions = instructions_from_words(&instructions![
+        (i ADDI T0 T0 1),
+        (i SLTI T1 T0 10),
+        (i BNZ ZERO T1 jmp_addr_i16(-2)),
+        (i HALT ZERO ZERO 0),
+        (i ADDI T0 T0 1)
+    ]);
+
+    let mut processor = Processor::default();
+    let mut storage = empty_storage!();
+
+    processor.enable_coverage(instructions.len() as u32);
+    assert_eq!(processor.coverage(), Some(&[false; 5][..]));
+
+    assert_eq!(processor.run(&instructions, &mut storage), ExitCode::Halted);
+    assert_eq!(
+        processor.coverage(),
+        Some(&[true, true, true, true, false][..])
+    );
+
+    processor.reset();
+    assert_eq!(processor.coverage(), Some(&[false; 5][..]));
+}
+
+#[test]
+fn float_conversion_modes() {
+    fn convert(opcode: Opcode, mode: FloatConvertMode, value: u32) -> u32 {
+        let mut processor = Processor::default();
+        processor.set_float_mode(FloatMode {
+            convert: mode,
+            ..Default::default()
+        });
+        processor.register_mut(RegisterId::T1).set_u(value);
+        processor.execute_word(
+            make_i_instruction(opcode, RegisterId::T0, RegisterId::T1, 0),
+            &mut empty_storage!(),
+        );
+        processor.register(RegisterId::T0).u()
+    }
+
+    let two_31 = 2_147_483_648f32;
+    let two_32 = 4_294_967_296f32;
+
+    for &mode in &[FloatConvertMode::Saturating, FloatConvertMode::Wrapping] {
+        assert_eq!(
+            convert(Opcode::ITOF, mode, i32::MAX as u32),
+            two_31.to_bits()
+        );
+        assert_eq!(convert(Opcode::ITOF, mode, u32::MAX), (-1f32).to_bits());
+        assert_eq!(convert(Opcode::ITOF, mode, 1 << 31), (-two_31).to_bits());
+        assert_eq!(
+            convert(Opcode::UTOF, mode, i32::MAX as u32),
+            two_31.to_bits()
+        );
+        assert_eq!(convert(Opcode::UTOF, mode, u32::MAX), two_32.to_bits());
+        assert_eq!(convert(Opcode::UTOF, mode, 1 << 31), two_31.to_bits());
+    }
+
+    // Inputs with the expected results of FTOI and FTOU, each when saturating and when wrapping.
+    let cases = [
+        (-1f32, [-1i32 as u32, -1i32 as u32, 0, u32::MAX]),
+        (two_31, [i32::MAX as u32, 1 << 31, 1 << 31, 1 << 31]),
+        (two_32, [i32::MAX as u32, 0, u32::MAX, 0]),
+        (two_32 + 512.0, [i32::MAX as u32, 512, u32::MAX, 512]),
+        (f32::NAN, [0, 0, 0, 0]),
+        (f32::INFINITY, [i32::MAX as u32, 0, u32::MAX, 0]),
+        (f32::NEG_INFINITY, [i32::MIN as u32, 0, 0, 0]),
+    ];
+
+    for &(input, expected) in &cases {
+        let results = [
+            convert(Opcode::FTOI, FloatConvertMode::Saturating, input.to_bits()),
+            convert(Opcode::FTOI, FloatConvertMode::Wrapping, input.to_bits()),
+            convert(Opcode::FTOU, FloatConvertMode::Saturating, input.to_bits()),
+            convert(Opcode::FTOU, FloatConvertMode::Wrapping, input.to_bits()),
+        ];
+        assert_eq!(results, expected, "input: {}", input);
+    }
+}
+
+#[test]
+fn register_views() {
+    let mut processor = Processor::default();
+    processor.register_mut(RegisterId::T0).set_i(-1);
+    processor.register_mut(RegisterId::T1).set_u(0x8000_0000);
+    processor.register_mut(RegisterId::S0).set_f(1.5);
+
+    let i = processor.registers_as_i32();
+    let u = processor.registers_as_u32();
+    let f = processor.registers_as_f32();
+
+    for index in 0..constants::REGISTER_COUNT {
+        assert_eq!(i[index] as u32, u[index]);
+        assert_eq!(f[index].to_bits(), u[index]);
+        assert_eq!(processor.registers()[index].u(), u[index]);
+    }
+
+    assert_eq!(i[register_index(RegisterId::T0)], -1);
+    assert_eq!(u[register_index(RegisterId::T1)], 0x8000_0000);
+    assert_eq!(f[register_index(RegisterId::S0)], 1.5);
+    assert_eq!(u[register_index(RegisterId::ZERO)], 0);
+}
+
+#[test]
+fn owned_memory() {
+    let instructions = instructions_from_words(&instructions![
+        (i LW T0 ZERO 0),
+        (i ADDI T0 T0 1),
+        (i SW T0 ZERO 0),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut owned = OwnedProcessor::new([0u8; 4]);
+
+    assert_eq!(owned.run(&instructions), ExitCode::Halted);
+    assert_eq!(owned.memory(), &[1, 0, 0, 0]);
+    let registers = *owned.processor().registers();
+
+    owned.reset();
+    assert_eq!(owned.memory(), &[0, 0, 0, 0]);
+
+    assert_eq!(owned.run(&instructions), ExitCode::Halted);
+    assert_eq!(owned.memory(), &[1, 0, 0, 0]);
+    assert_eq!(*owned.processor().registers(), registers);
+
+    // Owned mode must not keep plain processors from being sent to other threads.
+    fn is_send<T: Send>() {}
+    is_send::<Processor>();
+    is_send::<OwnedProcessor<PlainMemory>>();
+}
+
+#[test]
+fn reset_owned_composite_memory() {
+    let instructions = instructions_from_words(&instructions![
+        (i LI T0 ZERO 7),
+        (i SHI T1 ZERO 0xF000u16 as i16),
+        (i SW T0 ZERO 0),
+        (i SW T0 T1 0),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let writes = Rc::new(std::cell::Cell::new(0));
+    let writes_ref = Rc::clone(&writes);
+    let handler = DelegateIOHandler::new(
+        |_, _, _| true,
+        move |_, _, _| writes_ref.set(writes_ref.get() + 1),
+    );
+
+    let mut memory = CompositeMemory::new();
+    memory.mount(0, "ram", PlainMemory::new(16)).unwrap();
+    memory
+        .mount(0xF000_0000, "device", IOMemory::new(4, handler))
+        .unwrap();
+    let mut owned = OwnedProcessor::new(memory);
+
+    assert_eq!(owned.run(&instructions), ExitCode::Halted);
+    assert_eq!(writes.get(), 1);
+
+    // The device lies at a high address and must neither be written nor iterated up to.
+    owned.reset();
+    assert_eq!(owned.memory().read_word(0), Ok(0));
+    assert_eq!(owned.memory().read_word(0xF000_0000), Ok(7));
+    assert_eq!(writes.get(), 1);
+}
+
+#[test]
+fn stack_trace() {
+    // Three frames at 0x30, 0x20 and 0x10, each with the saved $FP at offset 0 and the saved $RA
+    // at offset 4. The outermost frame points to frame pointer 0.
+    let mut storage = [0u8; 64];
+    let frames = [(0x30, 0x20, 0x14), (0x20, 0x10, 0x28), (0x10, 0, 0x08)];
+    for &(frame, previous_frame, return_address) in &frames {
+        storage.write_word(frame, previous_frame).unwrap();
+        storage.write_word(frame + 4, return_address).unwrap();
+    }
+
+    let mut processor = Processor::default();
+    processor.register_mut(RegisterId::FP).set_u(0x30);
+    assert_eq!(processor.stack_trace(&storage), vec![0x14, 0x28, 0x08]);
+
+    // A frame pointing to itself ends the walk.
+    storage.write_word(0x10, 0x10).unwrap();
+    processor.set_frame_layout(Default::default());
+    assert_eq!(processor.stack_trace(&storage), vec![0x14, 0x28, 0x08]);
+
+    processor.register_mut(RegisterId::FP).set_u(0x100);
+    assert_eq!(processor.stack_trace(&storage), Vec::<u32>::new());
+
+    // Frames which save $RA and $FP below the frame pointer.
+    let mut storage = [0u8; 64];
+    storage.write_word(0x38, 0x1C).unwrap();
+    storage.write_word(0x3C, 0x20).unwrap();
+    storage.write_word(0x18, 0x04).unwrap();
+
+    processor.set_frame_layout(FrameLayout {
+        return_address_offset: -8,
+        previous_frame_offset: -4,
+    });
+    processor.register_mut(RegisterId::FP).set_u(0x40);
+    assert_eq!(processor.stack_trace(&storage), vec![0x1C, 0x04]);
+}
+
+#[test]
+fn word_index_address_conversion() {
+    for &index in &[0, 1, 7, u32::MAX / WORD_BYTES] {
+        let address = word_index_to_address(index);
+        assert_eq!(address, index * WORD_BYTES);
+        assert_eq!(address_to_word_index(address), Ok(index));
+    }
+
+    for &address in &[1, 2, 3, 5, u32::MAX] {
+        assert_eq!(address_to_word_index(address), Err(()));
+    }
+}
+
+#[test]
+fn observe_register_writes() {
+    let instructions = instructions_from_words(&instructions![
+        (i ADDI T0 ZERO 3),
+        (i ADDI T1 ZERO 1),
+        (i ADDI T0 T0 0),
+        (a MUL T0 T0 T0),
+        (i SUBI T0 T0 1),
+        (i BNZ ZERO T0 jmp_addr_i16(-1)),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut values = Vec::new();
+    let mut rm_written = false;
+    let mut processor = Processor::default();
+    let exit_code = processor.run_with_observer(
+        &instructions,
+        &mut empty_storage!(),
+        |id, old, new| match id {
+            RegisterId::T0 => values.push((old.i(), new.i())),
+            RegisterId::RM => rm_written = true,
+            _ => {}
+        },
+    );
+
+    assert_eq!(exit_code, ExitCode::Halted);
+    let mut expected = vec![(0, 3), (3, 9)];
+    expected.extend((0..9).rev().map(|value| (value + 1, value)));
+    assert_eq!(values, expected);
+    // The high word of 3 * 3 is zero, so $RM never changes.
+    assert!(!rm_written);
+}
+
+#[test]
+fn exit_code_values() {
+    use num::{FromPrimitive, ToPrimitive};
+
+    let values = [
+        (ExitCode::Halted, 0),
+        (ExitCode::DivisionByZero, 1),
+        (ExitCode::BadMemoryAccess, 2),
+        (ExitCode::BadAlignment, 3),
+        (ExitCode::BadJump, 4),
+        (ExitCode::InvalidOpcode, 5),
+        (ExitCode::BadProgramCounter, 6),
+    ];
+
+    for &(exit_code, value) in &values {
+        assert_eq!(exit_code as i32, value);
+        assert_eq!(exit_code.to_i32(), Some(value));
+        assert_eq!(ExitCode::from_i32(value), Some(exit_code));
+    }
+    assert_eq!(ExitCode::from_i32(values.len() as i32), None);
+}
+
+#[test]
+fn clone_mid_execution() {
+    let instructions = instructions_from_words(&instructions![
+        (i ADDI T0 T0 1),
+        (i SLTI T1 T0 10),
+        (i BNZ ZERO T1 jmp_addr_i16(-2)),
+        (i HALT ZERO ZERO 0)
+    ]);
+    let mut storage = empty_storage!();
+
+    let mut processor = Processor::default();
+    for _ in 0..6 {
+        processor.tick(&instructions, &mut storage);
+    }
+
+    let mut fork = processor.clone();
+    assert!(fork == processor);
+
+    assert_eq!(fork.run(&instructions, &mut storage), ExitCode::Halted);
+    assert!(fork != processor);
+    assert_eq!(fork.register(RegisterId::T0).i(), 10);
+
+    assert_eq!(processor.register(RegisterId::T0).i(), 2);
+    assert_eq!(processor.program_counter(), 0);
+    assert_eq!(processor.state(), None);
+    assert_eq!(processor.total_cycles(), 6);
+}
+
+#[test]
+fn resume_after_fault() {
+    let instructions = instructions_from_words(&instructions![
+        (i LI T1 ZERO 0x100),
+        (i LW T0 T1 0),
+        (i ADDI T0 T0 1),
+        (i HALT ZERO ZERO 0)
+    ]);
+
+    let mut memory = CompositeMemory::new();
+    let mut processor = Processor::new();
+
+    assert_eq!(
+        processor.run(&instructions, &mut memory),
+        ExitCode::BadMemoryAccess
+    );
+    assert_eq!(processor.fault_pc(), Some(4));
+
+    // A stopped processor stays stopped.
+    assert_eq!(
+        processor.run(&instructions, &mut memory),
+        ExitCode::BadMemoryAccess
+    );
+    assert_eq!(processor.program_counter(), 4);
+
+    memory.mount(0x100, "data", [41u8, 0, 0, 0]).unwrap();
+    assert!(processor.resume_after_fault());
+    assert_eq!(processor.state(), None);
+
+    assert_eq!(processor.run(&instructions, &mut memory), ExitCode::Halted);
+    assert_eq!(processor.register(RegisterId::T0).u(), 42);
+
+    assert!(!processor.resume_after_fault());
+    assert_eq!(processor.state(), Some(ExitCode::Halted));
+    assert_eq!(processor.run(&instructions, &mut memory), ExitCode::Halted);
+}
+
+#[test]
+fn fall_through_end_of_program() {
+    // Without HALT, wrapping around to the first instruction would loop forever.
+    let instructions = instructions_from_words(&instructions![
+        (i ADDI T0 T0 1),
+        (i SLTI T1 T0 100)
+    ]);
+
+    let mut processor = Processor::new();
+    assert_eq!(
+        processor.run_bounded(&instructions, &mut [0u8; 0], 10),
+        Some(ExitCode::BadProgramCounter)
+    );
+    assert_eq!(processor.register(RegisterId::T0).i(), 1);
+    assert_eq!(processor.program_counter(), 8);
+    assert_eq!(processor.fault_pc(), None);
+
+    // A jump-and-link as the last instruction links to the address past the end as well.
+    let instructions = instructions_from_words(&instructions![
+        (j JMP jmp_addr_i32(2)),
+        (i HALT ZERO ZERO 0),
+        (j JL jmp_addr_i32(-1))
+    ]);
+
+    let mut processor = Processor::new();
+    assert_eq!(
+        processor.run(&instructions, &mut [0u8; 0]),
+        ExitCode::Halted
+    );
+    assert_eq!(processor.register(RegisterId::RA).u(), 12);
+}
@@ -1,5 +1,4 @@
 use super::*;
-use num_traits::FromPrimitive;
 
 macro_rules! instructions_execute {
     (
@@ -10,54 +9,16 @@ macro_rules! instructions_execute {
         $state:expr,
         $pc:expr
     ) => {{
-        // ----- PREPARE -----
-        // 1. Get instructions
-        let instructions = super::instructions_from_words(&$instr);
-
-        // 2. Initialize objects
         let storage = &mut $stor;
-        let mut processor = Processor::default();
-
-        // 3. Set initial register values (unspecified values stay as 0)
-        $(
-            *processor.register_mut(RegisterId::$id) = From::from($v);
-        )*
-
-        // 4. Get array of expected register values.
-        //    The final register values are expected to be the same as the initial values,
-        //    unless explicitly specified otherwise via the expected_registers parameter.
-
-        // 4.a Copy initial values
-        let mut expected_registers = [Register::default(); constants::REGISTER_COUNT];
-        expected_registers.copy_from_slice(processor.registers());
-
-        // 4.b Overwrite with specified values
-        $(
-            expected_registers[register_index(RegisterId::$eid)] = From::from($ev);
-        )*
-
-        // ----- ACT -----
-        // Tick up to max_ticks times
-        let max_ticks = $ticks;
-        for _ in 0..max_ticks {
-            if processor.tick(&instructions, storage).is_some() {
-                break;
-            }
-        }
-
-        // ----- ASSERT -----
-        // 1. Check final state of processor
-        assert_eq!($state, processor.state(), "Final state does not match!");
-
-        // 2. Check final program counter
-        assert_eq!($pc, processor.program_counter(), "Final program counter does not match!");
-
-        // 3. Check final register values
-        for i in 0..expected_registers.len() {
-            assert_eq!(expected_registers[i], processor.registers()[i], "Final value of register {0} does not match!", RegisterId::from_usize(i).unwrap());
-        }
-
-        // 4. Check storage
+        crate::testkit::run_ticks_and_assert(
+            &$instr,
+            storage,
+            &[$((RegisterId::$id, From::from($v))),*],
+            &[$((RegisterId::$eid, From::from($ev))),*],
+            $ticks,
+            $state,
+            $pc,
+        );
         assert_eq!(&$estor, storage);
     }};
 }
@@ -194,9 +155,11 @@ mod divi;
 mod fadd;
 mod fdiv;
 mod flip;
+mod fmod;
 mod fmul;
 mod fsub;
 mod ftoi;
+mod ftou;
 mod invalid;
 mod itof;
 mod jl;
@@ -224,6 +187,7 @@ mod sgti;
 mod sgtu;
 mod sgtui;
 mod sh;
+mod shi;
 mod sle;
 mod slei;
 mod sleu;
@@ -244,5 +208,6 @@ mod srli;
 mod sub;
 mod subi;
 mod sw;
+mod utof;
 mod xor;
 mod xori;
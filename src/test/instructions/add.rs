@@ -31,3 +31,11 @@ fn overflow() {
         [T1 = 0xFFFF_FFFFu32, T2 = 1234] => [T0 = 1233]
     };
 }
+
+#[test]
+fn zero_register_stays_zero() {
+    instruction_runs! {
+        instr_alu!(ADD, ZERO, T1, T2),
+        [T1 = 5678, T2 = 1234] => [ZERO = 0]
+    };
+}
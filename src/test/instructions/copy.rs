@@ -7,3 +7,35 @@ fn works() {
         [T0 = 0x1234_5678] => [T1 = 0x1234_5678]
     };
 }
+
+#[test]
+fn float() {
+    instruction_runs! {
+        instr_i!(COPY, T1, T0, 0),
+        [T0 = -1.5e-3f32] => [T1 = -1.5e-3f32]
+    };
+}
+
+#[test]
+fn nan_bit_pattern() {
+    instruction_runs! {
+        instr_i!(COPY, T1, T0, 0),
+        [T0 = 0x7FC0_1234u32] => [T1 = 0x7FC0_1234u32]
+    };
+}
+
+#[test]
+fn flop_result() {
+    instructions_execute! {
+        [
+            instr_flop!(FADD, T0, T1, T2),
+            instr_i!(COPY, T3, T0, 0),
+            nop!()
+        ],
+        [T1 = 262.562_f32, T2 = -82.35_f32] => [T0 = 180.212_f32, T3 = 180.212_f32],
+        empty_storage!() => empty_storage!(),
+        2,
+        None,
+        8
+    }
+}
@@ -12,3 +12,11 @@ fn works() {
         ]
     }
 }
+
+#[test]
+fn zero_register_stays_zero() {
+    instruction_runs! {
+        instr_flop!(FADD, ZERO, T1, T2),
+        [T1 = 1.5_f32, T2 = 2.0_f32] => [ZERO = 0]
+    };
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+#[test]
+fn works() {
+    instruction_runs! {
+        instr_flop!(FMOD, T0, T1, T2),
+        [
+            T1 = 5.5_f32,
+            T2 = 2.0_f32
+        ] => [
+            T0 = 1.5_f32
+        ]
+    }
+}
+
+#[test]
+fn negative_dividend() {
+    instruction_runs! {
+        instr_flop!(FMOD, T0, T1, T2),
+        [
+            T1 = -5.5_f32,
+            T2 = 2.0_f32
+        ] => [
+            T0 = -1.5_f32
+        ]
+    }
+}
+
+#[test]
+fn zero_divisor() {
+    let instructions = instructions_from_words(&[instr_flop!(FMOD, T0, T1, ZERO)]);
+    let mut processor = Processor::default();
+    processor.register_mut(RegisterId::T1).set_f(5.5);
+
+    assert_eq!(processor.tick(&instructions, &mut empty_storage!()), None);
+    assert!(processor.register(RegisterId::T0).f().is_nan());
+}
@@ -12,3 +12,37 @@ fn works() {
         ]
     }
 }
+
+fn fmul_subnormal(float_mode: FloatMode) -> f32 {
+    let instructions =
+        instructions_from_words(&[instr_flop!(FMUL, T0, T1, T2), instr_i!(HALT, ZERO, ZERO, 0)]);
+
+    let mut processor = Processor::default();
+    processor.set_float_mode(float_mode);
+    processor.register_mut(RegisterId::T1).set_f(1.0e-20_f32);
+    processor.register_mut(RegisterId::T2).set_f(-1.0e-20_f32);
+
+    assert_eq!(
+        processor.run(&instructions, &mut empty_storage!()),
+        ExitCode::Halted
+    );
+
+    processor.register(RegisterId::T0).f()
+}
+
+#[test]
+fn subnormal_result() {
+    let result = fmul_subnormal(FloatMode::default());
+    assert!(result.is_subnormal());
+    assert_eq!(result, 1.0e-20_f32 * -1.0e-20_f32);
+}
+
+#[test]
+fn flush_denormals() {
+    let result = fmul_subnormal(FloatMode {
+        flush_denormals: true,
+        ..Default::default()
+    });
+    assert_eq!(result, 0.0);
+    assert!(result.is_sign_negative());
+}
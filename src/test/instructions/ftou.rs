@@ -0,0 +1,67 @@
+use super::*;
+
+#[test]
+fn zero() {
+    instruction_runs! {
+        instr_i!(FTOU, T0, T1, 0),
+        [T1 = 0f32] => [T0 = 0u32]
+    }
+}
+
+#[test]
+fn real_positive() {
+    instruction_runs! {
+        instr_i!(FTOU, T0, T1, 0),
+        [T1 = 87455.88f32] => [T0 = 87455u32]
+    }
+}
+
+#[test]
+fn high_bit_set() {
+    instruction_runs! {
+        instr_i!(FTOU, T0, T1, 0),
+        [T1 = 2_147_483_648_f32] => [T0 = 0x8000_0000u32]
+    }
+
+    // The signed conversion saturates instead.
+    instruction_runs! {
+        instr_i!(FTOI, T0, T1, 0),
+        [T1 = 2_147_483_648_f32] => [T0 = i32::MAX]
+    }
+}
+
+#[test]
+fn negative() {
+    instruction_runs! {
+        instr_i!(FTOU, T0, T1, 0),
+        [T1 = -2347.31f32] => [T0 = 0u32]
+    }
+}
+
+#[test]
+fn too_large() {
+    instruction_runs! {
+        instr_i!(FTOU, T0, T1, 0),
+        [T1 = 1e10f32] => [T0 = u32::MAX]
+    }
+}
+
+#[test]
+fn nan() {
+    instruction_runs! {
+        instr_i!(FTOU, T0, T1, 0),
+        [T1 = f32::NAN] => [T0 = 0u32]
+    }
+}
+
+#[test]
+fn infinity() {
+    instruction_runs! {
+        instr_i!(FTOU, T0, T1, 0),
+        [T1 = f32::INFINITY] => [T0 = u32::MAX]
+    }
+    instruction_runs! {
+        instr_i!(FTOU, T0, T1, 0),
+        [T1 = f32::NEG_INFINITY] => [T0 = 0u32]
+    }
+}
@@ -15,3 +15,27 @@ fn overrides_all_bits() {
         [T0 = 0x1234_5678] => [T0 = 0x4321_0000]
     };
 }
+
+#[test]
+fn zero_register_stays_zero() {
+    instruction_runs! {
+        instr_i!(LHI, ZERO, ZERO, 0x1234),
+        [] => [ZERO = 0]
+    };
+}
+
+#[test]
+fn then_slo() {
+    instructions_execute! {
+        [
+            instr_i!(LHI, T0, ZERO, 0xABCDu16 as i16),
+            instr_i!(SLO, T0, ZERO, 0x1234),
+            nop!()
+        ],
+        [T0 = 0xFFFF_FFFFu32] => [T0 = 0xABCD_1234u32],
+        empty_storage!() => empty_storage!(),
+        2,
+        None,
+        8
+    };
+}
@@ -18,3 +18,12 @@ fn bad_access() {
         BadMemoryAccess
     };
 }
+
+#[test]
+fn zero_register_stays_zero() {
+    instruction_runs! {
+        instr_i!(LW, ZERO, ZERO, 0),
+        [] => [ZERO = 0],
+        [0xFF, 0xFF, 0xFE, 0xFF] => [0xFF, 0xFF, 0xFE, 0xFF]
+    };
+}
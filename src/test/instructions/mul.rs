@@ -55,3 +55,11 @@ fn no_overflow_overwrite_rm_negative() {
         [T1 = 10, T2 = -10, RM = 0x1234_5678] => [T0 = -100, RM = -1]
     };
 }
+
+#[test]
+fn zero_register_stays_zero() {
+    instruction_runs! {
+        instr_alu!(MUL, ZERO, T1, T2),
+        [T1 = 0x1234_5678, T2 = 0x1234_5678] => [ZERO = 0, RM = 0x14B_66DC]
+    };
+}
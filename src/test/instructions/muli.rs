@@ -55,3 +55,11 @@ fn no_overflow_overwrite_rm_negative() {
         [T1 = 10, RM = 0x1234_5678] => [T0 = -100, RM = -1]
     };
 }
+
+#[test]
+fn zero_register_stays_zero() {
+    instruction_runs! {
+        instr_i!(MULI, ZERO, T1, 3),
+        [T1 = 5] => [ZERO = 0, RM = 0]
+    };
+}
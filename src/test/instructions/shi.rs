@@ -12,7 +12,7 @@ fn no_sign_bit() {
 fn does_not_extend_sign() {
     instruction_runs! {
         instr_i!(SHI, T0, ZERO, -1),
-        [] => [T0 = 0xFFFF_0000]
+        [] => [T0 = 0xFFFF_0000u32]
     };
 }
 
@@ -27,7 +27,48 @@ fn does_not_overwrite_high_bits_no_sign_bit() {
 #[test]
 fn does_not_overwrite_high_bits_sign_bit() {
     instruction_runs! {
-        instr_i!(SHI, T0, ZERO, 0xF321),
-        [T0 = 0x1234_5678] => [T0 = 0xF321_5678]
+        instr_i!(SHI, T0, ZERO, 0xF321u16 as i16),
+        [T0 = 0x1234_5678] => [T0 = 0xF321_5678u32]
+    };
+}
+
+#[test]
+fn zero_register_stays_zero() {
+    instruction_runs! {
+        instr_i!(SHI, ZERO, ZERO, 0x1234),
+        [] => [ZERO = 0]
+    };
+}
+
+#[test]
+fn after_slo_on_zero_register() {
+    instructions_execute! {
+        [
+            instr_i!(SLO, ZERO, ZERO, 0x5678),
+            instr_i!(SHI, ZERO, ZERO, 0x1234),
+            instr_i!(COPY, T0, ZERO, 0),
+            nop!()
+        ],
+        [] => [ZERO = 0, T0 = 0],
+        empty_storage!() => empty_storage!(),
+        3,
+        None,
+        12
+    };
+}
+
+#[test]
+fn after_slo_on_filled_register() {
+    instructions_execute! {
+        [
+            instr_i!(SLO, T0, ZERO, 0x1234),
+            instr_i!(SHI, T0, ZERO, 0xABCDu16 as i16),
+            nop!()
+        ],
+        [T0 = 0xFFFF_FFFFu32] => [T0 = 0xABCD_1234u32],
+        empty_storage!() => empty_storage!(),
+        2,
+        None,
+        8
     };
 }
@@ -31,3 +31,11 @@ fn does_not_overwrite_high_bits_sign_bit() {
         [T0 = 0x1234_5678] => [T0 = 0x1234_F321]
     };
 }
+
+#[test]
+fn zero_register_stays_zero() {
+    instruction_runs! {
+        instr_i!(SLO, ZERO, ZERO, 0x1234),
+        [] => [ZERO = 0]
+    };
+}
@@ -0,0 +1,39 @@
+use super::*;
+
+#[test]
+fn zero() {
+    instruction_runs! {
+        instr_i!(UTOF, T0, T1, 0),
+        [T1 = 0] => [T0 = 0f32]
+    };
+}
+
+#[test]
+fn small() {
+    instruction_runs! {
+        instr_i!(UTOF, T0, T1, 0),
+        [T1 = 234] => [T0 = 234f32]
+    };
+}
+
+#[test]
+fn high_bit_set() {
+    instruction_runs! {
+        instr_i!(UTOF, T0, T1, 0),
+        [T1 = 0x8000_0000u32] => [T0 = 2_147_483_648_f32]
+    };
+
+    // The signed conversion interprets the same bits as a negative number.
+    instruction_runs! {
+        instr_i!(ITOF, T0, T1, 0),
+        [T1 = 0x8000_0000u32] => [T0 = -2_147_483_648_f32]
+    };
+}
+
+#[test]
+fn max_value() {
+    instruction_runs! {
+        instr_i!(UTOF, T0, T1, 0),
+        [T1 = u32::MAX] => [T0 = 4_294_967_296_f32]
+    };
+}
@@ -0,0 +1,129 @@
+//! Helpers for testing integrations with the processor, e.g. custom storages.
+//!
+//! Only available with the `testkit` feature. The crate's own opcode tests are built on it as
+//! well.
+
+use crate::*;
+use num::traits::FromPrimitive;
+
+/// Runs `instructions` on an empty storage and asserts the final state of the processor
+/// (see [`run_and_assert_with_storage`]).
+///
+/// # Examples
+/// ```
+/// use vcpu::testkit::run_and_assert;
+/// use vcpu::*;
+///
+/// run_and_assert(
+///     &[instr_alu!(ADD, T0, T1, T2), instr_i!(HALT, ZERO, ZERO, 0)],
+///     &[(RegisterId::T1, 3.into()), (RegisterId::T2, 4.into())],
+///     &[(RegisterId::T0, 7.into())],
+///     Some(ExitCode::Halted),
+///     4,
+/// );
+/// ```
+/// [`run_and_assert_with_storage`]: ./fn.run_and_assert_with_storage.html
+pub fn run_and_assert(
+    instructions: &[Word],
+    initial: &[(RegisterId, Register)],
+    expected: &[(RegisterId, Register)],
+    expected_state: Option<ExitCode>,
+    expected_pc: u32,
+) {
+    run_and_assert_with_storage(
+        instructions,
+        &mut [0u8; 0],
+        initial,
+        expected,
+        expected_state,
+        expected_pc,
+    );
+}
+
+/// Runs `instructions` on `storage` and asserts the final state of the processor.
+///
+/// The registers are set to the `initial` values (all others are zero) before at most one tick
+/// per instruction is executed (see [`run_ticks_and_assert`]).
+///
+/// # Panics
+/// Panics if the state, program counter or any register doesn't match.
+///
+/// [`run_ticks_and_assert`]: ./fn.run_ticks_and_assert.html
+pub fn run_and_assert_with_storage(
+    instructions: &[Word],
+    storage: &mut dyn StorageMut,
+    initial: &[(RegisterId, Register)],
+    expected: &[(RegisterId, Register)],
+    expected_state: Option<ExitCode>,
+    expected_pc: u32,
+) {
+    run_ticks_and_assert(
+        instructions,
+        storage,
+        initial,
+        expected,
+        instructions.len(),
+        expected_state,
+        expected_pc,
+    );
+}
+
+/// Runs `instructions` on `storage` for at most `max_ticks` ticks and asserts the final state of
+/// the processor.
+///
+/// The registers are set to the `initial` values (all others are zero) before ticking, stopping
+/// early if the processor stops. Afterwards, the registers must have the `expected` values, and
+/// all other registers must still have their initial values.
+///
+/// # Panics
+/// Panics if the state, program counter or any register doesn't match.
+pub fn run_ticks_and_assert(
+    instructions: &[Word],
+    storage: &mut dyn StorageMut,
+    initial: &[(RegisterId, Register)],
+    expected: &[(RegisterId, Register)],
+    max_ticks: usize,
+    expected_state: Option<ExitCode>,
+    expected_pc: u32,
+) {
+    let instruction_bytes = instructions_from_words(instructions);
+    let mut processor = Processor::default();
+
+    for (id, value) in initial {
+        *processor.register_mut(*id) = *value;
+    }
+
+    let mut expected_registers = *processor.registers();
+    for (id, value) in expected {
+        expected_registers[register_index(*id)] = *value;
+    }
+
+    for _ in 0..max_ticks {
+        if processor.tick(&instruction_bytes, storage).is_some() {
+            break;
+        }
+    }
+
+    assert_eq!(
+        expected_state,
+        processor.state(),
+        "Final state does not match!"
+    );
+    assert_eq!(
+        expected_pc,
+        processor.program_counter(),
+        "Final program counter does not match!"
+    );
+    for (i, (expected, actual)) in expected_registers
+        .iter()
+        .zip(processor.registers().iter())
+        .enumerate()
+    {
+        assert_eq!(
+            expected,
+            actual,
+            "Final value of register {} does not match!",
+            RegisterId::from_usize(i).unwrap()
+        );
+    }
+}
@@ -94,3 +94,21 @@ pub fn enum_get_variant_count_derive(input: TokenStream) -> TokenStream {
 
     expanded.into()
 }
+
+#[proc_macro_derive(EnumAll)]
+pub fn enum_all_derive(input: TokenStream) -> TokenStream {
+    let syn_item: syn::DeriveInput = syn::parse(input).unwrap();
+    let name = &syn_item.ident;
+    let variants = get_enum_variants(&syn_item, true);
+    let identifiers = variants.iter().map(|v| &v.ident);
+
+    let expanded = quote! {
+        impl EnumAll for #name {
+            fn all() -> &'static [#name] {
+                &[#(#name::#identifiers),*]
+            }
+        }
+    };
+
+    expanded.into()
+}
@@ -36,3 +36,8 @@ pub trait EnumGetVariantCount {
     // TODO: make this a const fn as soon as it becomes stable
     fn variant_count() -> usize;
 }
+
+pub trait EnumAll: Sized + 'static {
+    /// Returns all variants in the order of their declaration.
+    fn all() -> &'static [Self];
+}
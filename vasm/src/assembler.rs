@@ -0,0 +1,79 @@
+use crate::labels::LabelMap;
+use crate::*;
+use std::collections::HashMap;
+use vcpu::RegisterId;
+use vex::Executable;
+
+/// Assembles many programs that share a common prelude, which is only parsed once.
+///
+/// The prelude is a `.data` section (without an `.instructions` section). Its data and labels
+/// are available to every snippet assembled with [`assemble_snippet`], along with any register
+/// aliases set on the assembler.
+///
+/// # Examples
+/// ```
+/// use vasm::Assembler;
+///
+/// let assembler = Assembler::new(".data\nanswer: .word 42", 0).unwrap();
+///
+/// let (first, _) = assembler
+///     .assemble_snippet(".instructions\nLDA $t0, answer\nHALT")
+///     .unwrap();
+/// let (second, _) = assembler
+///     .assemble_snippet(".instructions\nLDA $t1, answer\nLW $t1, 0($t1)\nHALT")
+///     .unwrap();
+///
+/// assert_eq!(first.data(), second.data());
+/// ```
+/// [`assemble_snippet`]: #method.assemble_snippet
+pub struct Assembler<'p> {
+    data_offset: u32,
+    data: Vec<u8>,
+    data_labels: LabelMap<'p>,
+    aliases: HashMap<String, RegisterId>,
+}
+
+impl<'p> Assembler<'p> {
+    /// Parses the `.data` section in `prelude` for programs whose data is loaded at `data_offset`.
+    pub fn new(prelude: &'p str, data_offset: u32) -> Result<Assembler<'p>> {
+        let pair = parse_rule(Rule::prelude, prelude)?;
+        let (data, data_labels) = process_data_section(
+            vec![pair.into_inner().next().unwrap()],
+            data_offset,
+            Limits::default().max_data,
+        )?;
+
+        Ok(Assembler {
+            data_offset,
+            data,
+            data_labels,
+            aliases: HashMap::new(),
+        })
+    }
+
+    /// Adds a register alias, like the ones accepted by [`assemble_with_aliases`].
+    ///
+    /// [`assemble_with_aliases`]: fn.assemble_with_aliases.html
+    pub fn add_alias(&mut self, alias: String, register: RegisterId) {
+        self.aliases.insert(alias, register);
+    }
+
+    /// Assembles `snippet`, which consists of an `.instructions` section only,
+    /// together with the data of the prelude.
+    pub fn assemble_snippet(&self, snippet: &str) -> Result<(Executable, SourceMap)> {
+        let pair = parse_rule(Rule::snippet, snippet)?;
+
+        assemble_instruction_section(
+            vec![pair.into_inner().next().unwrap()],
+            self.data.clone(),
+            &self.data_labels,
+            &Options {
+                data_offset: self.data_offset,
+                aliases: Some(&self.aliases),
+                ..Default::default()
+            },
+            &mut Vec::new(),
+        )
+        .map(|(executable, source_map, _)| (executable, source_map))
+    }
+}
@@ -0,0 +1,155 @@
+use crate::instructions::jump_distance;
+use byteorder::ByteOrder;
+use num::NumCast;
+use std::collections::HashMap;
+use std::fmt;
+use util::Endian;
+use vcpu::{
+    make_i_instruction, make_j_instruction, Address, Immediate, Opcode, RegisterId, Word,
+    WORD_BYTES,
+};
+
+/// Maps labels to the index of the instruction they refer to.
+pub type SymbolTable = HashMap<String, u32>;
+
+/// The target of a branch or jump built with [`Instruction`].
+///
+/// [`Instruction`]: enum.Instruction.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target<'l, T> {
+    /// A distance in bytes, relative to the branch or jump itself.
+    Relative(T),
+    /// A label, which is looked up in the [`SymbolTable`].
+    ///
+    /// [`SymbolTable`]: type.SymbolTable.html
+    Label(&'l str),
+}
+
+/// An instruction for [`assemble_instructions_from`], built by a program instead of being
+/// parsed from text.
+///
+/// [`assemble_instructions_from`]: fn.assemble_instructions_from.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction<'l> {
+    /// An instruction that is already encoded (e.g. with [`make_alu_instruction`]).
+    ///
+    /// [`make_alu_instruction`]: ../vcpu/fn.make_alu_instruction.html
+    Complete(Word),
+    /// A branch (`BEZ` or `BNZ`) testing `rs1`.
+    Branch {
+        opcode: Opcode,
+        rs1: RegisterId,
+        target: Target<'l, Immediate>,
+    },
+    /// A jump (`JMP` or `JL`).
+    Jump {
+        opcode: Opcode,
+        target: Target<'l, Address>,
+    },
+}
+
+/// Error returned by [`assemble_instructions_from`].
+///
+/// [`assemble_instructions_from`]: fn.assemble_instructions_from.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssembleError {
+    /// The instruction at `index` refers to a label missing from the symbol table.
+    LabelNotFound { index: usize, label: String },
+    /// The instruction at `index` refers to a label that is too far away to be encoded.
+    JumpTooFar { index: usize, label: String },
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssembleError::LabelNotFound { index, label } => {
+                write!(f, "Label \"{}\" not found (instruction {})", label, index)
+            }
+            AssembleError::JumpTooFar { index, label } => write!(
+                f,
+                "Jump distance to label \"{}\" too far (instruction {})",
+                label, index
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+fn resolve_target<T: NumCast + Copy>(
+    labels: &SymbolTable,
+    target: &Target<T>,
+    index: usize,
+) -> Result<T, AssembleError> {
+    match *target {
+        Target::Relative(distance) => Ok(distance),
+        Target::Label(label) => {
+            let target_instr = *labels
+                .get(label)
+                .ok_or_else(|| AssembleError::LabelNotFound {
+                    index,
+                    label: label.to_owned(),
+                })?;
+
+            jump_distance(target_instr, index as u32).ok_or_else(|| AssembleError::JumpTooFar {
+                index,
+                label: label.to_owned(),
+            })
+        }
+    }
+}
+
+/// Encodes `instrs`, resolving label targets with `labels`, without going through VASM text.
+///
+/// # Examples
+/// ```
+/// use vasm::{assemble_instructions_from, Instruction, SymbolTable, Target};
+/// use vcpu::{make_i_instruction, Opcode, RegisterId};
+///
+/// let mut labels = SymbolTable::new();
+/// labels.insert("loop".to_owned(), 0);
+///
+/// let instrs = [
+///     Instruction::Complete(make_i_instruction(Opcode::ADDI, RegisterId::T0, RegisterId::T0, 1)),
+///     Instruction::Jump { opcode: Opcode::JMP, target: Target::Label("loop") },
+/// ];
+///
+/// let bytes = assemble_instructions_from(&instrs, &labels).unwrap();
+/// assert_eq!(bytes.len(), 8);
+/// ```
+///
+/// # Errors
+/// Returns an error if a target label is missing from `labels`, or too far away to be encoded.
+pub fn assemble_instructions_from(
+    instrs: &[Instruction],
+    labels: &SymbolTable,
+) -> Result<Vec<u8>, AssembleError> {
+    let mut result = vec![0; instrs.len() * WORD_BYTES as usize];
+
+    for (i, (instr, bytes)) in instrs
+        .iter()
+        .zip(result.chunks_mut(WORD_BYTES as usize))
+        .enumerate()
+    {
+        let word = match instr {
+            Instruction::Complete(word) => *word,
+            Instruction::Branch {
+                opcode,
+                rs1,
+                target,
+            } => make_i_instruction(
+                *opcode,
+                RegisterId::ZERO,
+                *rs1,
+                resolve_target(labels, target, i)?,
+            ),
+            Instruction::Jump { opcode, target } => {
+                make_j_instruction(*opcode, resolve_target(labels, target, i)?)
+            }
+        };
+
+        Endian::write_u32(bytes, word);
+    }
+
+    Ok(result)
+}
@@ -37,7 +37,7 @@ where
     Ok(())
 }
 
-fn process_data_element(pair: Pair<Rule>, data: &mut Vec<u8>) -> Result<()> {
+fn process_data_element(pair: Pair<Rule>, data: &mut Vec<u8>, max_size: u32) -> Result<()> {
     debug_assert_matches!(pair.as_rule(), Rule::data_element);
     let inner = pair.into_inner().next().unwrap();
     let span = inner.as_span();
@@ -56,9 +56,7 @@ fn process_data_element(pair: Pair<Rule>, data: &mut Vec<u8>) -> Result<()> {
         _ => unreachable!(),
     };
 
-    let max_size = u32::max_value() as usize - 1;
-
-    if data.len() > max_size {
+    if data.len() > max_size as usize {
         Err(new_parser_error(
             span,
             format!("Data exceeds maximum size of {} bytes", max_size),
@@ -68,20 +66,26 @@ fn process_data_element(pair: Pair<Rule>, data: &mut Vec<u8>) -> Result<()> {
     }
 }
 
-pub fn process_data(pair: Pair<Rule>) -> Result<(Vec<u8>, LabelMap)> {
-    debug_assert_matches!(pair.as_rule(), Rule::data);
-
+/// Processes the `.data` sections in `sections`, whose data is concatenated in order.
+pub fn process_data<'i, I>(sections: I, max_size: u32) -> Result<(Vec<u8>, LabelMap<'i>)>
+where
+    I: IntoIterator<Item = Pair<'i, Rule>>,
+{
     let mut data = Vec::new();
     let mut labels = HashMap::new();
 
-    for labeled_data_element in pair.into_inner() {
-        process_labeled_element(
-            labeled_data_element,
-            &mut labels,
-            Rule::data_element,
-            data.len() as u32,
-            |p| process_data_element(p, &mut data),
-        )?;
+    for pair in sections {
+        debug_assert_matches!(pair.as_rule(), Rule::data);
+
+        for labeled_data_element in pair.into_inner() {
+            process_labeled_element(
+                labeled_data_element,
+                &mut labels,
+                Rule::data_element,
+                data.len() as u32,
+                |p| process_data_element(p, &mut data, max_size),
+            )?;
+        }
     }
 
     Ok((data, labels))
@@ -98,7 +102,7 @@ mod test {
         let mut output = Vec::new();
 
         let pair = parse_rule(Rule::data_element, input).unwrap();
-        super::process_data_element(pair, &mut output).unwrap();
+        super::process_data_element(pair, &mut output, u32::MAX).unwrap();
 
         assert_eq!([0xFF, 0xFF, 0xFF, 0xFF], &output[..]);
     }
@@ -109,7 +113,7 @@ mod test {
         let mut output = Vec::new();
 
         let pair = parse_rule(Rule::data_element, input).unwrap();
-        super::process_data_element(pair, &mut output).unwrap();
+        super::process_data_element(pair, &mut output, u32::MAX).unwrap();
 
         assert_eq!([0xFF, 0xFF], &output[..]);
     }
@@ -120,7 +124,7 @@ mod test {
         let mut output = Vec::new();
 
         let pair = parse_rule(Rule::data_element, input).unwrap();
-        super::process_data_element(pair, &mut output).unwrap();
+        super::process_data_element(pair, &mut output, u32::MAX).unwrap();
 
         assert_eq!([0xFF], &output[..]);
     }
@@ -131,7 +135,7 @@ mod test {
         let mut output = Vec::new();
 
         let pair = parse_rule(Rule::data_element, input).unwrap();
-        super::process_data_element(pair, &mut output).unwrap();
+        super::process_data_element(pair, &mut output, u32::MAX).unwrap();
 
         assert_eq!([0x2E, 0xFB, 0xFF, 0xFF], &output[..]);
     }
@@ -142,7 +146,7 @@ mod test {
         let mut output = Vec::new();
 
         let pair = parse_rule(Rule::data_element, input).unwrap();
-        super::process_data_element(pair, &mut output).unwrap();
+        super::process_data_element(pair, &mut output, u32::MAX).unwrap();
 
         assert_eq!([0x2E, 0xFB], &output[..]);
     }
@@ -153,7 +157,7 @@ mod test {
         let mut output = Vec::new();
 
         let pair = parse_rule(Rule::data_element, input).unwrap();
-        super::process_data_element(pair, &mut output).unwrap();
+        super::process_data_element(pair, &mut output, u32::MAX).unwrap();
 
         assert_eq!([0x85], &output[..]);
     }
@@ -0,0 +1,295 @@
+use crate::*;
+
+const INDENT: &str = "    ";
+
+/// A piece of the program which is emitted on its own line(s).
+struct Item {
+    start: usize,
+    end: usize,
+    label: Option<String>,
+    text: String,
+    indent: bool,
+}
+
+impl Item {
+    fn new(pair: &Pair<Rule>, label: Option<String>, text: String, indent: bool) -> Item {
+        Item {
+            start: pair.as_span().start(),
+            end: effective_end(pair),
+            label,
+            text,
+            indent,
+        }
+    }
+}
+
+/// Re-emits `input` with canonical formatting, keeping all comments.
+///
+/// Section headers and labels start at the beginning of a line, and every data element and
+/// instruction is indented on its own line. Mnemonics and register names are written in upper
+/// case and operands are separated by `", "`. Number literals are kept as written.
+///
+/// Comments stay either on their own line or at the end of the line they were attached to.
+/// Runs of blank lines are collapsed into a single blank line.
+///
+/// # Errors
+/// Returns an error if `input` cannot be parsed.
+///
+/// # Examples
+/// ```
+/// let input = ".data\n.instructions\nloop: addi $t0,$t0 , 1 # count\n  jmp loop";
+///
+/// assert_eq!(
+///     vasm::format_source(input).unwrap(),
+///     ".data\n.instructions\nloop:\n    ADDI $T0, $T0, 1 # count\n    JMP loop\n"
+/// );
+/// ```
+pub fn format_source(input: &str) -> Result<String> {
+    let mut items = Vec::new();
+
+    for section in parse(input)?.into_inner() {
+        let header = match section.as_rule() {
+            Rule::data => ".data",
+            Rule::instructions => ".instructions",
+            _ => continue,
+        };
+
+        let start = section.as_span().start();
+        items.push(Item {
+            start,
+            end: start + header.len(),
+            label: None,
+            text: header.to_owned(),
+            indent: false,
+        });
+
+        for element in section.into_inner() {
+            format_element(element, &mut items);
+        }
+    }
+
+    let mut output = Vec::new();
+    let mut cursor = 0;
+
+    for item in &items {
+        // Everything before the first section header is not indented.
+        emit_gap(&input[cursor..item.start], cursor > 0, &mut output);
+
+        // Comments within a multi-line element are moved in front of it.
+        for comment in comments(&input[item.start..item.end]) {
+            output.push(indented(comment, item.indent));
+        }
+        if let Some(label) = &item.label {
+            output.push(format!("{}:", label));
+        }
+        output.push(indented(&item.text, item.indent));
+
+        cursor = item.end;
+    }
+
+    emit_gap(&input[cursor..], true, &mut output);
+
+    while output.last().is_some_and(String::is_empty) {
+        output.pop();
+    }
+
+    let mut result = output.join("\n");
+    result.push('\n');
+    Ok(result)
+}
+
+fn format_element(element: Pair<Rule>, items: &mut Vec<Item>) {
+    match element.as_rule() {
+        Rule::entry => {
+            let label = element.clone().into_inner().as_str();
+            items.push(Item::new(&element, None, format!(".entry {}", label), true));
+        }
+        Rule::rept_block => {
+            let end = element.as_span().end();
+            let mut inner = element.into_inner();
+            let count = inner.next().unwrap();
+            let value = count.clone().into_inner().as_str();
+            items.push(Item::new(&count, None, format!(".rept {}", value), true));
+
+            for body_element in inner {
+                format_element(body_element, items);
+            }
+
+            items.push(Item {
+                start: end - ".endr".len(),
+                end,
+                label: None,
+                text: ".endr".to_owned(),
+                indent: true,
+            });
+        }
+        _ => items.push(format_labeled_element(element)),
+    }
+}
+
+fn format_labeled_element(pair: Pair<Rule>) -> Item {
+    let mut label = None;
+    let mut text = String::new();
+
+    for inner in pair.clone().into_inner() {
+        match inner.as_rule() {
+            Rule::label => label = Some(inner.into_inner().as_str().to_owned()),
+            Rule::data_element => text = format_data_element(inner),
+            Rule::instruction => text = format_instruction(inner),
+            _ => unreachable!(),
+        }
+    }
+
+    Item::new(&pair, label, text, true)
+}
+
+/// Returns the end of the last token in `pair`. Its span may also contain trailing whitespace
+/// and comments, which were skipped by the parser.
+fn effective_end(pair: &Pair<Rule>) -> usize {
+    let end = pair.as_span().end();
+    let last_token_end = pair
+        .clone()
+        .into_inner()
+        .flatten()
+        .map(|p| p.as_span().end())
+        .max()
+        .unwrap_or(end);
+
+    let rest = &pair.as_str()[last_token_end - pair.as_span().start()..];
+    last_token_end
+        + rest
+            .find(|c: char| c.is_whitespace() || c == '#')
+            .unwrap_or(rest.len())
+}
+
+fn format_data_element(pair: Pair<Rule>) -> String {
+    let element = pair.into_inner().next().unwrap();
+    let directive = element
+        .as_str()
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .to_owned();
+    let values = element
+        .into_inner()
+        .flat_map(|p| match p.as_rule() {
+            Rule::int_list => p.into_inner().map(|i| i.as_str()).collect(),
+            _ => vec![p.as_str()],
+        })
+        .collect::<Vec<_>>();
+
+    format!("{} {}", directive, values.join(", "))
+}
+
+fn format_instruction(pair: Pair<Rule>) -> String {
+    let instruction = pair.into_inner().next().unwrap();
+    let rule = instruction.as_rule();
+    let keyword: String = instruction
+        .as_str()
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect();
+
+    let operands: Vec<String> = instruction
+        .into_inner()
+        .filter(|p| !is_mnemonic(p.as_rule()))
+        .map(format_operand)
+        .collect();
+
+    let mnemonic = keyword.to_ascii_uppercase();
+
+    if operands.is_empty() {
+        mnemonic
+    } else if rule == Rule::instruction_ls {
+        format!(
+            "{} {}, {}({})",
+            mnemonic, operands[0], operands[1], operands[2]
+        )
+    } else {
+        format!("{} {}", mnemonic, operands.join(", "))
+    }
+}
+
+fn is_mnemonic(rule: Rule) -> bool {
+    matches!(
+        rule,
+        Rule::mnemonic_alu
+            | Rule::mnemonic_flop
+            | Rule::mnemonic_i
+            | Rule::mnemonic_iu
+            | Rule::mnemonic_ds
+            | Rule::mnemonic_li
+            | Rule::mnemonic_si
+            | Rule::mnemonic_e
+            | Rule::mnemonic_br
+            | Rule::mnemonic_jr
+            | Rule::mnemonic_ls
+            | Rule::mnemonic_j
+    )
+}
+
+fn format_operand(pair: Pair<Rule>) -> String {
+    if pair.as_rule() == Rule::register_list {
+        let registers: Vec<String> = pair.into_inner().map(format_operand).collect();
+        registers.join(", ")
+    } else if pair.as_rule() == Rule::register {
+        let inner = pair.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::register_id => format!("${}", inner.as_str().to_ascii_uppercase()),
+            _ => format!("${}", inner.as_str()),
+        }
+    } else {
+        pair.as_str().to_owned()
+    }
+}
+
+/// Returns the comments in `text`. Since `#` cannot be part of any token, every `#` starts a
+/// comment which extends to the end of the line.
+fn comments(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n')
+        .filter_map(|line| line.find('#').map(|i| line[i..].trim_end()))
+}
+
+/// Emits the comments and blank lines in the text between two items.
+fn emit_gap(gap: &str, indent: bool, output: &mut Vec<String>) {
+    let mut lines = gap.split('\n');
+
+    // The first line continues the line of the previous item.
+    if let Some(comment) = lines
+        .next()
+        .and_then(|line| line.find('#').map(|i| &line[i..]))
+    {
+        if output.is_empty() {
+            output.push(comment.trim_end().to_owned());
+        } else {
+            append_comment(output, comment.trim_end());
+        }
+    }
+
+    let mut lines: Vec<&str> = lines.collect();
+    // The last line is the indentation of the next item (if any).
+    lines.pop();
+
+    for line in lines {
+        let line = line.trim();
+        if !line.is_empty() {
+            output.push(indented(line, indent));
+        } else if output.last().is_some_and(|l| !l.is_empty()) {
+            output.push(String::new());
+        }
+    }
+}
+
+fn append_comment(output: &mut [String], comment: &str) {
+    let last = output.last_mut().unwrap();
+    last.push(' ');
+    last.push_str(comment);
+}
+
+fn indented(text: &str, indent: bool) -> String {
+    if indent {
+        format!("{}{}", INDENT, text)
+    } else {
+        text.to_owned()
+    }
+}
@@ -47,8 +47,40 @@ fn process_enum_inner<'i, T: FromStr<Err = ParseEnumError>>(pair: &Pair<'i, Rule
         .map_err(|err| new_parser_error(pair.as_span(), format!("{}", err)))
 }
 
-fn process_enum<T: FromStr<Err = ParseEnumError>>(pair: Pair<Rule>) -> Result<T> {
-    process_enum_inner(&pair.into_inner().next().unwrap())
+fn process_register(pair: Pair<Rule>, aliases: &HashMap<String, RegisterId>) -> Result<RegisterId> {
+    let inner = pair.into_inner().next().unwrap();
+    match inner.as_rule() {
+        Rule::register_id => process_enum_inner(&inner),
+        Rule::register_alias => {
+            let name = inner.as_str();
+            aliases
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+                .map(|(_, id)| *id)
+                .ok_or_else(|| {
+                    new_parser_error(inner.as_span(), format!("Unknown register \"{}\"", name))
+                })
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Like `process_register`, but rejects `$ZERO`, because the register is written by the instruction.
+fn process_destination_register(
+    pair: Pair<Rule>,
+    aliases: &HashMap<String, RegisterId>,
+) -> Result<RegisterId> {
+    let span = pair.as_span();
+    let register = process_register(pair, aliases)?;
+
+    if register == RegisterId::ZERO {
+        Err(new_parser_error(
+            span,
+            "Register $ZERO cannot be written".to_owned(),
+        ))
+    } else {
+        Ok(register)
+    }
 }
 
 fn process_jump_target<T>(pair: Pair<Rule>) -> Result<JumpTarget<T>>
@@ -71,6 +103,8 @@ fn process_instruction<'i>(
     instr: &mut InstrVec<'i>,
     data_labels: &LabelMap<'i>,
     data_offset: u32,
+    aliases: &HashMap<String, RegisterId>,
+    max_size: u32,
 ) -> Result<usize> {
     let span = pair.as_span();
     let inner = pair.into_inner().next().unwrap();
@@ -82,26 +116,26 @@ fn process_instruction<'i>(
     match rule {
         Rule::instruction_alu => {
             let alu_funct = process_enum_inner(&pairs.next().unwrap())?;
-            let rd = process_enum(pairs.next().unwrap())?;
-            let rs1 = process_enum(pairs.next().unwrap())?;
-            let rs2 = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
+            let rs1 = process_register(pairs.next().unwrap(), aliases)?;
+            let rs2 = process_register(pairs.next().unwrap(), aliases)?;
             instr.push(ParsedInstruction::Complete(make_alu_instruction(
                 alu_funct, rd, rs1, rs2,
             )));
         }
         Rule::instruction_flop => {
             let flop_funct = process_enum_inner(&pairs.next().unwrap())?;
-            let rd = process_enum(pairs.next().unwrap())?;
-            let rs1 = process_enum(pairs.next().unwrap())?;
-            let rs2 = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
+            let rs1 = process_register(pairs.next().unwrap(), aliases)?;
+            let rs2 = process_register(pairs.next().unwrap(), aliases)?;
             instr.push(ParsedInstruction::Complete(make_flop_instruction(
                 flop_funct, rd, rs1, rs2,
             )));
         }
         Rule::instruction_i => {
             let opcode = process_enum_inner(&pairs.next().unwrap())?;
-            let rd = process_enum(pairs.next().unwrap())?;
-            let rs1 = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
+            let rs1 = process_register(pairs.next().unwrap(), aliases)?;
             let immediate = process_int(pairs.next().unwrap())?;
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 opcode, rd, rs1, immediate,
@@ -109,8 +143,8 @@ fn process_instruction<'i>(
         }
         Rule::instruction_iu => {
             let opcode = process_enum_inner(&pairs.next().unwrap())?;
-            let rd = process_enum(pairs.next().unwrap())?;
-            let rs1 = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
+            let rs1 = process_register(pairs.next().unwrap(), aliases)?;
             let immediate = process_uint::<u16>(pairs.next().unwrap())?;
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 opcode,
@@ -121,15 +155,15 @@ fn process_instruction<'i>(
         }
         Rule::instruction_ds => {
             let opcode = process_enum_inner(&pairs.next().unwrap())?;
-            let rd = process_enum(pairs.next().unwrap())?;
-            let rs1 = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
+            let rs1 = process_register(pairs.next().unwrap(), aliases)?;
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 opcode, rd, rs1, 0i16,
             )));
         }
         Rule::instruction_li => {
             let opcode = process_enum_inner(&pairs.next().unwrap())?;
-            let rd = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
             let immediate = process_int(pairs.next().unwrap())?;
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 opcode,
@@ -140,7 +174,7 @@ fn process_instruction<'i>(
         }
         Rule::instruction_si => {
             let opcode = process_enum_inner(&pairs.next().unwrap())?;
-            let rd = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
             let immediate = process_uint::<u16>(pairs.next().unwrap())?;
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 opcode,
@@ -160,7 +194,7 @@ fn process_instruction<'i>(
         }
         Rule::instruction_br => {
             let opcode = process_enum_inner(&pairs.next().unwrap())?;
-            let rs1 = process_enum(pairs.next().unwrap())?;
+            let rs1 = process_register(pairs.next().unwrap(), aliases)?;
             let target = process_jump_target(pairs.next().unwrap())?;
             instr.push(ParsedInstruction::Branch {
                 opcode,
@@ -170,7 +204,7 @@ fn process_instruction<'i>(
         }
         Rule::instruction_jr => {
             let opcode = process_enum_inner(&pairs.next().unwrap())?;
-            let rs1 = process_enum(pairs.next().unwrap())?;
+            let rs1 = process_register(pairs.next().unwrap(), aliases)?;
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 opcode,
                 RegisterId::ZERO,
@@ -180,9 +214,14 @@ fn process_instruction<'i>(
         }
         Rule::instruction_ls => {
             let opcode = process_enum_inner(&pairs.next().unwrap())?;
-            let rd = process_enum(pairs.next().unwrap())?;
+            let rd = match opcode {
+                Opcode::SB | Opcode::SH | Opcode::SW => {
+                    process_register(pairs.next().unwrap(), aliases)?
+                }
+                _ => process_destination_register(pairs.next().unwrap(), aliases)?,
+            };
             let immediate = process_int(pairs.next().unwrap())?;
-            let rs1 = process_enum(pairs.next().unwrap())?;
+            let rs1 = process_register(pairs.next().unwrap(), aliases)?;
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 opcode, rd, rs1, immediate,
             )));
@@ -193,53 +232,81 @@ fn process_instruction<'i>(
             instr.push(ParsedInstruction::Jump { opcode, target });
         }
         Rule::instruction_push => {
-            let register = process_enum(pairs.next().unwrap())?;
-            instr.push(ParsedInstruction::Complete(make_i_instruction(
-                Opcode::SW,
-                register,
-                RegisterId::SP,
-                -4i16,
-            )));
+            // Like consecutive single pushes: the first register ends up at the highest address.
+            let registers = pairs
+                .next()
+                .unwrap()
+                .into_inner()
+                .map(|p| process_register(p, aliases))
+                .collect::<Result<Vec<_>>>()?;
+            let size = stack_size(span, registers.len())?;
+            for (i, register) in registers.into_iter().enumerate() {
+                instr.push(ParsedInstruction::Complete(make_i_instruction(
+                    Opcode::SW,
+                    register,
+                    RegisterId::SP,
+                    -4 * (i as i16 + 1),
+                )));
+            }
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 Opcode::SUBI,
                 RegisterId::SP,
                 RegisterId::SP,
-                4i16,
+                size,
             )));
         }
         Rule::instruction_pop => {
-            let register = process_enum(pairs.next().unwrap())?;
-            instr.push(ParsedInstruction::Complete(make_i_instruction(
-                Opcode::LW,
-                register,
-                RegisterId::SP,
-                0i16,
-            )));
+            // Restores the registers saved by a PUSH with the same list.
+            let registers = pairs
+                .next()
+                .unwrap()
+                .into_inner()
+                .map(|p| process_destination_register(p, aliases))
+                .collect::<Result<Vec<_>>>()?;
+            let size = stack_size(span, registers.len())?;
+            for (i, register) in registers.into_iter().enumerate() {
+                instr.push(ParsedInstruction::Complete(make_i_instruction(
+                    Opcode::LW,
+                    register,
+                    RegisterId::SP,
+                    size - 4 * (i as i16 + 1),
+                )));
+            }
             instr.push(ParsedInstruction::Complete(make_i_instruction(
                 Opcode::ADDI,
                 RegisterId::SP,
                 RegisterId::SP,
-                4i16,
+                size,
             )));
         }
-        Rule::instruction_lwi => {
-            let register = process_enum(pairs.next().unwrap())?;
+        Rule::instruction_lwi | Rule::instruction_li32 => {
+            let register = process_destination_register(pairs.next().unwrap(), aliases)?;
             let value: i32 = process_int(pairs.next().unwrap())?;
-            instr.push(ParsedInstruction::Complete(make_i_instruction(
-                Opcode::SLO,
-                register,
-                RegisterId::ZERO,
-                value as i16,
-            )));
-            instr.push(ParsedInstruction::Complete(make_i_instruction(
-                Opcode::SHI,
-                register,
-                RegisterId::ZERO,
-                (value >> 16) as i16,
-            )));
+            if rule == Rule::instruction_li32 && value == value as i16 as i32 {
+                // LI sign-extends its immediate, so a single instruction suffices.
+                instr.push(ParsedInstruction::Complete(make_i_instruction(
+                    Opcode::LI,
+                    register,
+                    RegisterId::ZERO,
+                    value as i16,
+                )));
+            } else {
+                instr.push(ParsedInstruction::Complete(make_i_instruction(
+                    Opcode::SLO,
+                    register,
+                    RegisterId::ZERO,
+                    value as i16,
+                )));
+                instr.push(ParsedInstruction::Complete(make_i_instruction(
+                    Opcode::SHI,
+                    register,
+                    RegisterId::ZERO,
+                    (value >> 16) as i16,
+                )));
+            }
         }
         Rule::instruction_lda => {
-            let rd = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
             let label_span = pairs.next().unwrap().as_span();
             let label = label_span.as_str();
             let address = data_labels.get(label).ok_or_else(|| {
@@ -261,7 +328,7 @@ fn process_instruction<'i>(
             )));
         }
         Rule::instruction_lia => {
-            let rd = process_enum(pairs.next().unwrap())?;
+            let rd = process_destination_register(pairs.next().unwrap(), aliases)?;
             let label = pairs.next().unwrap();
 
             instr.push(ParsedInstruction::LoadInstructionAddress {
@@ -275,34 +342,83 @@ fn process_instruction<'i>(
                 upper: true,
             });
         }
+        Rule::instruction_call => {
+            let target = process_jump_target(pairs.next().unwrap())?;
+            instr.push(ParsedInstruction::Jump {
+                opcode: Opcode::JL,
+                target,
+            });
+        }
+        Rule::instruction_ret => {
+            instr.push(ParsedInstruction::Complete(make_i_instruction(
+                Opcode::JR,
+                RegisterId::ZERO,
+                RegisterId::RA,
+                0,
+            )));
+        }
         _ => unreachable!(),
     }
 
-    let max_size = (u32::max_value() / WORD_BYTES) as usize - 1;
     let new_len = instr.len();
 
-    if new_len > max_size {
-        Err(new_parser_error(
-            span,
-            format!("Instructions exceed maximum size of {} bytes", max_size),
-        ))
+    // The instructions before this one fit, so this is the first one crossing the limit,
+    // even if it expanded into several instructions.
+    if new_len > (max_size / WORD_BYTES) as usize {
+        Err(size_exceeded_error(span, max_size, new_len))
     } else {
         Ok(new_len - old_len)
     }
 }
 
-pub fn process_instructions<'i>(
-    pair: Pair<'i, Rule>,
+/// Returns the number of bytes on the stack occupied by `count` registers.
+fn stack_size(span: Span, count: usize) -> Result<Immediate> {
+    count
+        .checked_mul(WORD_BYTES as usize)
+        .filter(|&size| size <= Immediate::MAX as usize)
+        .map(|size| size as Immediate)
+        .ok_or_else(|| new_parser_error(span, "Too many registers".to_owned()))
+}
+
+fn size_exceeded_error(span: Span, max_size: u32, len: usize) -> Error {
+    new_parser_error(
+        span,
+        format!(
+            "Instructions exceed maximum size of {} bytes ({} instructions, {} bytes)",
+            max_size,
+            len,
+            len * WORD_BYTES as usize
+        ),
+    )
+}
+
+/// Processes the `.instructions` sections in `sections`, whose instructions are concatenated in
+/// order. Labels are shared by all sections.
+pub fn process_instructions<'i, I>(
+    sections: I,
     data_labels: &LabelMap<'i>,
     data_offset: u32,
-) -> Result<(InstrVec<'i>, LabelMap<'i>, SourceMap)> {
-    debug_assert_matches!(pair.as_rule(), Rule::instructions);
-
+    aliases: &HashMap<String, RegisterId>,
+    max_size: u32,
+) -> Result<(InstrVec<'i>, LabelMap<'i>, SourceMap)>
+where
+    I: IntoIterator<Item = Pair<'i, Rule>>,
+{
     let mut instructions = Vec::new();
     let mut labels = HashMap::new();
     let mut source_map = Vec::new();
 
-    for labeled_instruction in pair.into_inner() {
+    let mut expanded = Vec::new();
+    expand_repeats(
+        sections.into_iter().flat_map(|pair| {
+            debug_assert_matches!(pair.as_rule(), Rule::instructions);
+            pair.into_inner()
+        }),
+        max_size,
+        &mut expanded,
+    )?;
+
+    for labeled_instruction in expanded {
         let span = labeled_instruction.as_span();
         let start_line = span.start_pos().line_col().0 as u32;
         let end_line = span.end_pos().line_col().0 as u32;
@@ -318,7 +434,14 @@ pub fn process_instructions<'i>(
             Rule::instruction,
             instructions.len() as u32,
             |p| {
-                let count = process_instruction(p, &mut instructions, &data_labels, data_offset)?;
+                let count = process_instruction(
+                    p,
+                    &mut instructions,
+                    &data_labels,
+                    data_offset,
+                    aliases,
+                    max_size,
+                )?;
                 for _ in 0..count {
                     source_map.push(source_map_item);
                 }
@@ -331,6 +454,280 @@ pub fn process_instructions<'i>(
     Ok((instructions, labels, source_map))
 }
 
+/// Appends the labeled instructions in `pairs` to `expanded`, replacing each `.rept` block with
+/// the given number of copies of its body. Entry directives are skipped.
+///
+/// Every copy keeps the spans of the original lines, so the source map points all of them back
+/// to the body of the block.
+fn expand_repeats<'i, I>(pairs: I, max_size: u32, expanded: &mut Vec<Pair<'i, Rule>>) -> Result<()>
+where
+    I: IntoIterator<Item = Pair<'i, Rule>>,
+{
+    for pair in pairs {
+        match pair.as_rule() {
+            Rule::entry => {}
+            Rule::rept_block => {
+                let mut inner = pair.into_inner();
+                let count =
+                    process_uint::<u32>(inner.next().unwrap().into_inner().next().unwrap())?;
+                let body: Vec<_> = inner.collect();
+
+                for element in &body {
+                    let first = element.clone().into_inner().next().unwrap();
+                    if first.as_rule() == Rule::label {
+                        return Err(new_parser_error(
+                            first.as_span(),
+                            "Labels are not allowed inside a .rept block".to_owned(),
+                        ));
+                    }
+                }
+
+                for _ in 0..count {
+                    let len = expanded.len();
+                    expand_repeats(body.iter().cloned(), max_size, expanded)?;
+                    if expanded.len() == len || expanded.len() > (max_size / WORD_BYTES) as usize {
+                        break;
+                    }
+                }
+            }
+            _ => expanded.push(pair),
+        }
+
+        // Every instruction assembles to at least one word, so once the expansion exceeds the
+        // size limit, the rest is not needed to report the error. This keeps large repeat counts
+        // from exhausting memory.
+        if expanded.len() > (max_size / WORD_BYTES) as usize {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a warning if execution can fall through the end of the program, i.e. if the last
+/// instruction is not an unconditional control transfer (`HALT`, `JMP` or `JR`).
+pub fn check_fall_through(instr: &[ParsedInstruction], source_map: &SourceMap) -> Option<Warning> {
+    let opcode = match instr.last()? {
+        ParsedInstruction::Complete(word) => {
+            Opcode::from_u32((word & OPCODE_MASK) >> OPCODE_OFFSET)
+        }
+        ParsedInstruction::Jump { opcode, .. } => Some(*opcode),
+        _ => None,
+    };
+
+    match opcode {
+        Some(Opcode::HALT) | Some(Opcode::JMP) | Some(Opcode::JR) => None,
+        _ => Some(Warning {
+            line: source_map.last().map_or(0, |item| item.start_line),
+            message: "Execution can continue past the last instruction, which stops the processor with BadProgramCounter (missing HALT?)".to_owned(),
+        }),
+    }
+}
+
+/// Returns a warning for every run of unreachable instructions, i.e. instructions following an
+/// unconditional control transfer (`HALT`, `JMP` or `JR`) which are neither labeled nor the
+/// target of a branch or jump with a numeric offset.
+pub fn check_dead_code(
+    instr: &[ParsedInstruction],
+    labels: &LabelMap,
+    source_map: &SourceMap,
+) -> Vec<Warning> {
+    let mut reachable = vec![false; instr.len()];
+    for &index in labels.values() {
+        if let Some(r) = reachable.get_mut(index as usize) {
+            *r = true;
+        }
+    }
+
+    for (index, pi) in instr.iter().enumerate() {
+        let offset = match pi {
+            ParsedInstruction::Branch {
+                target: JumpTarget::Address(offset),
+                ..
+            } => *offset as i64,
+            ParsedInstruction::Jump {
+                target: JumpTarget::Address(offset),
+                ..
+            } => *offset as i64,
+            _ => continue,
+        };
+        let target = index as i64 + offset / WORD_BYTES as i64;
+        if target >= 0 {
+            if let Some(r) = reachable.get_mut(target as usize) {
+                *r = true;
+            }
+        }
+    }
+
+    let mut warnings = Vec::new();
+    // Whether the previous instruction can't fall through to the current one.
+    let mut after_transfer = false;
+    let mut previous_dead = false;
+
+    for (index, pi) in instr.iter().enumerate() {
+        let dead = after_transfer && !reachable[index];
+
+        // Only the first instruction of a run of unreachable ones is reported.
+        if dead && !previous_dead {
+            warnings.push(Warning {
+                line: source_map[index].start_line,
+                message: "Unreachable instruction (no label after the preceding HALT, JMP or JR)"
+                    .to_owned(),
+            });
+        }
+
+        let opcode = match pi {
+            ParsedInstruction::Complete(word) => {
+                Opcode::from_u32((word & OPCODE_MASK) >> OPCODE_OFFSET)
+            }
+            ParsedInstruction::Jump { opcode, .. } => Some(*opcode),
+            _ => None,
+        };
+
+        after_transfer = dead
+            || matches!(
+                opcode,
+                Some(Opcode::HALT) | Some(Opcode::JMP) | Some(Opcode::JR)
+            );
+        previous_dead = dead;
+    }
+
+    warnings
+}
+
+/// Returns a warning for every `ADDI`/`SUBI` which adjusts `$SP` or `$FP` by an amount that is
+/// not a multiple of the word size, since that misaligns the stack for `PUSH`, `POP` and `LW`.
+pub fn check_stack_alignment(instr: &[ParsedInstruction], source_map: &SourceMap) -> Vec<Warning> {
+    let is_stack_register =
+        |id: u32| id == enum_to_u32(RegisterId::SP) || id == enum_to_u32(RegisterId::FP);
+
+    instr
+        .iter()
+        .zip(source_map)
+        .filter_map(|(pi, item)| match pi {
+            ParsedInstruction::Complete(word) => Some((*word, item)),
+            _ => None,
+        })
+        .filter(|(word, _)| {
+            let opcode = Opcode::from_u32((word & OPCODE_MASK) >> OPCODE_OFFSET);
+            let rd = (word & RD_MASK) >> RD_OFFSET;
+            let (immediate, _) = extract_immediate(*word);
+
+            matches!(opcode, Some(Opcode::ADDI) | Some(Opcode::SUBI))
+                && is_stack_register(rd)
+                && immediate % WORD_BYTES as i16 != 0
+        })
+        .map(|(word, item)| {
+            let rd: RegisterId = RegisterId::from_u32((word & RD_MASK) >> RD_OFFSET).unwrap();
+            Warning {
+                line: item.start_line,
+                message: format!(
+                    "Adjusting ${:?} by {} misaligns it (not a multiple of {})",
+                    rd,
+                    extract_immediate(word).0,
+                    WORD_BYTES
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Replaces every branch to a label that is too far away to be encoded in the immediate with a
+/// branch on the inverted condition over a `JMP` to the label, which has a much larger range.
+///
+/// The labels and the source map are updated for the inserted instructions. Branches and jumps
+/// with numeric targets are not adjusted.
+///
+/// # Errors
+/// Returns an error at the label of the branch whose expansion makes the instructions exceed
+/// `max_size` bytes.
+pub fn expand_long_branches(
+    instr: &mut InstrVec,
+    labels: &mut LabelMap,
+    source_map: &mut SourceMap,
+    max_size: u32,
+) -> Result<()> {
+    let is_far = |labels: &LabelMap, pi: &ParsedInstruction, index: usize| match pi {
+        ParsedInstruction::Branch {
+            target: JumpTarget::Label(label),
+            ..
+        } => labels
+            .get(label.as_str())
+            .is_some_and(|&target| jump_distance::<Immediate>(target, index as u32).is_none()),
+        _ => false,
+    };
+
+    // Inserting instructions can make branches that were checked before too far away as well,
+    // so repeat until nothing changes.
+    let mut expanded = true;
+    while expanded {
+        expanded = false;
+
+        let mut index = 0;
+        while index < instr.len() {
+            if !is_far(labels, &instr[index], index) {
+                index += 1;
+                continue;
+            }
+
+            if let ParsedInstruction::Branch {
+                opcode,
+                rs1,
+                target: JumpTarget::Label(label),
+            } = instr.remove(index)
+            {
+                let inverted = match opcode {
+                    Opcode::BEZ => Opcode::BNZ,
+                    _ => Opcode::BEZ,
+                };
+
+                instr.insert(
+                    index,
+                    ParsedInstruction::Branch {
+                        opcode: inverted,
+                        rs1,
+                        target: JumpTarget::Address(2 * WORD_BYTES as Immediate),
+                    },
+                );
+                instr.insert(
+                    index + 1,
+                    ParsedInstruction::Jump {
+                        opcode: Opcode::JMP,
+                        target: JumpTarget::Label(label),
+                    },
+                );
+
+                if instr.len() > (max_size / WORD_BYTES) as usize {
+                    return Err(size_exceeded_error(label, max_size, instr.len()));
+                }
+            }
+
+            for target in labels.values_mut() {
+                if *target > index as u32 {
+                    *target += 1;
+                }
+            }
+            source_map.insert(index + 1, source_map[index]);
+
+            expanded = true;
+            index += 2;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn pad_instructions(instr: &mut InstrVec, source_map: &mut SourceMap, len: usize) {
+    let nop = make_i_instruction(Opcode::NOP, RegisterId::ZERO, RegisterId::ZERO, 0);
+    let placeholder = SourceMapItem {
+        start_line: 0,
+        line_count: 0,
+    };
+
+    instr.resize_with(len, || ParsedInstruction::Complete(nop));
+    source_map.resize(len, placeholder);
+}
+
 fn resolve_jump_target<T: NumCast + Num + Copy>(
     labels: &LabelMap,
     target: &JumpTarget<T>,
@@ -339,23 +736,28 @@ fn resolve_jump_target<T: NumCast + Num + Copy>(
     match target {
         JumpTarget::Address(address) => Ok(*address),
         JumpTarget::Label(label) => {
-            let absolute =
-                Into::<i64>::into(*labels.get(label.as_str()).ok_or_else(|| {
-                    new_parser_error(label.clone(), "Label not found".to_owned())
-                })?);
-
-            let relative = absolute - Into::<i64>::into(current_instr);
-            let byte_dist = relative * Into::<i64>::into(WORD_BYTES);
-            num::NumCast::from(byte_dist)
+            let target_instr = *labels
+                .get(label.as_str())
+                .ok_or_else(|| new_parser_error(label.clone(), "Label not found".to_owned()))?;
+
+            jump_distance(target_instr, current_instr)
                 .ok_or_else(|| new_parser_error(label.clone(), "Jump distance too far".to_owned()))
         }
     }
 }
 
+/// Returns the distance in bytes from the instruction at index `current_instr` to the one at
+/// index `target_instr`, or `None` if it doesn't fit into `T`.
+pub fn jump_distance<T: NumCast>(target_instr: u32, current_instr: u32) -> Option<T> {
+    let relative = Into::<i64>::into(target_instr) - Into::<i64>::into(current_instr);
+    num::NumCast::from(relative * Into::<i64>::into(WORD_BYTES))
+}
+
 fn finalize_instruction(
     labels: &LabelMap,
     instr: &ParsedInstruction,
     current_instr: u32,
+    instr_offset: u32,
 ) -> Result<Word> {
     Ok(match *instr {
         ParsedInstruction::Complete(word) => word,
@@ -381,11 +783,18 @@ fn finalize_instruction(
             ref rd,
             ref upper,
         } => {
-            let address = *labels
+            let index = *labels
                 .get(label.as_str())
-                .ok_or_else(|| new_parser_error(label.clone(), "Label not found".to_owned()))?
-                as u32
-                * WORD_BYTES;
+                .ok_or_else(|| new_parser_error(label.clone(), "Label not found".to_owned()))?;
+            let address = index
+                .checked_mul(WORD_BYTES)
+                .and_then(|address| address.checked_add(instr_offset))
+                .ok_or_else(|| {
+                    new_parser_error(
+                        *label,
+                        "Instruction address exceeds the address space".to_owned(),
+                    )
+                })?;
             if *upper {
                 make_i_instruction(Opcode::SHI, *rd, RegisterId::ZERO, (address >> 16) as i16)
             } else {
@@ -395,12 +804,51 @@ fn finalize_instruction(
     })
 }
 
-pub fn assemble_instructions(instr: &[ParsedInstruction], labels: &LabelMap) -> Result<Vec<u8>> {
+/// Resolves the labels of `instr`, like [`assemble_instructions`], but keeps branches and jumps
+/// apart from the other instructions, which are encoded.
+///
+/// [`assemble_instructions`]: fn.assemble_instructions.html
+pub fn resolve_instructions(
+    instr: &[ParsedInstruction],
+    labels: &LabelMap,
+    instr_offset: u32,
+) -> Result<Vec<Instruction<'static>>> {
+    instr
+        .iter()
+        .enumerate()
+        .map(|(i, pi)| {
+            Ok(match *pi {
+                ParsedInstruction::Branch {
+                    opcode,
+                    rs1,
+                    ref target,
+                } => Instruction::Branch {
+                    opcode,
+                    rs1,
+                    target: Target::Relative(resolve_jump_target(labels, target, i as u32)?),
+                },
+                ParsedInstruction::Jump { opcode, ref target } => Instruction::Jump {
+                    opcode,
+                    target: Target::Relative(resolve_jump_target(labels, target, i as u32)?),
+                },
+                _ => {
+                    Instruction::Complete(finalize_instruction(labels, pi, i as u32, instr_offset)?)
+                }
+            })
+        })
+        .collect()
+}
+
+pub fn assemble_instructions(
+    instr: &[ParsedInstruction],
+    labels: &LabelMap,
+    instr_offset: u32,
+) -> Result<Vec<u8>> {
     let result_size = instr.len() * WORD_BYTES as usize;
     let mut result = vec![0; result_size];
 
     for (i, pi) in instr.iter().enumerate() {
-        let instr = finalize_instruction(labels, pi, i as u32)?;
+        let instr = finalize_instruction(labels, pi, i as u32, instr_offset)?;
         let start = i * WORD_BYTES as usize;
         let end = start + WORD_BYTES as usize;
         Endian::write_u32(&mut result[start..end], instr);
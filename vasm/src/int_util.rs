@@ -96,6 +96,10 @@ where
         Rule::oct_uint => process_num_lit(inner.into_inner().next().unwrap(), 8),
         Rule::hex_uint => process_num_lit(inner.into_inner().next().unwrap(), 16),
         Rule::dec_uint => process_num_lit(inner, 10),
+        Rule::negative_dec_int => Err(new_parser_error(
+            inner.as_span(),
+            "Expected unsigned value, found negative value".to_owned(),
+        )),
         _ => unreachable!(),
     }
 }
@@ -13,7 +13,8 @@
 //! # VASM Assembler Language
 //!
 //! A VASM program consists of the two sections `.data` and `.instructions`, which are both always mandatory
-//! and must appear in that order.
+//! and must appear in that order. After that, any number of further `.data` and `.instructions` sections
+//! may follow. Sections of the same kind are concatenated in order, and their labels are visible in all sections.
 //!
 //! The source can contain comments, which start with a hash-symbol `#` and continue to the end of the line.
 //!
@@ -36,12 +37,26 @@
 //! Note that integer literals can be signed decimal, hexadecimal (`0x`), octal (`0o`) or binary (`0b`).
 //! Lists of integers are separated with commas.
 //!
+//! Negative values are only allowed where the value is signed, e.g. in `.byte` lists or for the immediate
+//! values of `ADDI` and `LI`. Sizes (`.block`) and the immediate values of unsigned instructions (`SLO`, `SHI`
+//! and the unsigned comparisons like `SLTUI`) must not be negative. Hexadecimal, octal and binary literals
+//! are never negative, but can be used for signed values, in which case they are interpreted as
+//! the two's complement bit pattern, e.g. `.byte 0xFF` is the same as `.byte -1`.
+//!
 //! ## `.instructions` Section
 //!
 //! This section contains the instructions that make up the program.
 //! Instructions are constructed using mnemonics.
 //! Each mnemonic procudes one or more instructions.
 //!
+//! Execution begins at the first instruction, unless the section starts with an `.entry <label>`
+//! directive, which sets the [entry point](../vex/struct.Executable.html#method.entry_point)
+//! of the executable to the labeled instruction.
+//!
+//! A block of instructions can be repeated by enclosing it in `.rept <count>` and `.endr`, e.g. to unroll
+//! a loop at assembly time. Blocks can be nested, but the instructions inside them cannot be labeled.
+//! In the source map, every copy refers to the original lines of the block.
+//!
 //! ### Register Identifiers
 //!
 //! Many mnemonics require registers to be specified so their id can be encoded in the resulting instruction(s).
@@ -49,7 +64,7 @@
 //!
 //! Register    | Purpose
 //! ------------|---------
-//! `$ZERO`     | Always contains zero. Attempting to write to this register has no effect.
+//! `$ZERO`     | Always contains zero. Instructions that would write to this register are rejected by the assembler.
 //! `$V0`-`$V1` | "Value". General purpose, but used for returning values from functions.
 //! `$A0`-`$A4` | "Argument". General purpose, but used for passing arguments to functions.
 //! `$T0`-`$T9` | "Temporary". General purpose, but used for holding temporary values (must be saved by caller).
@@ -134,26 +149,36 @@
 //! `JLR`    | Jump to register and link                    | `JLR rs`
 //! `ITOF`   | Integer to float                             | `ITOF rd, rs`
 //! `FTOI`   | Float to integer                             | `FTOI rd, rs`
+//! `UTOF`   | Unsigned integer to float                    | `UTOF rd, rs`
+//! `FTOU`   | Float to unsigned integer                    | `FTOU rd, rs`
 //! `FADD`   | Float addition                               | `FADD rd, rs1, rs2`
 //! `FSUB`   | Float subtraction                            | `FSUB rd, rs1, rs2`
 //! `FMUL`   | Float multiplication                         | `FMUL rd, rs1, rs2`
 //! `FDIV`   | Float division                               | `FDIV rd, rs1, rs2`
+//! `FMOD`   | Float remainder                              | `FMOD rd, rs1, rs2`
 //!
 //! ### Shorthand Mnemonics
 //!
-//! Mnemonics that produce more than one instruction are purely an assembler feature and don't
-//! directly correspond to processor-opcodes. They can be thought of as shorthands that make
-//! common tasks more convenient, for example pushing to and popping from the stack.
+//! Mnemonics that produce more than one instruction, or that stand for a particular form of
+//! another instruction, are purely an assembler feature and don't directly correspond to processor-opcodes.
+//! They can be thought of as shorthands that make common tasks more convenient, for example pushing
+//! to and popping from the stack, or calling and returning from subroutines.
 //!
 //! Quick reference for all available shorthand mnemonics:
 //!
 //! Mnemonic | Short Description                            | Syntax
 //! ---------|----------------------------------------------|----------------------
-//! `PUSH`   | Push register value onto stack               | `PUSH rs`
-//! `POP`    | Pop register value from stack                | `POP rd`
+//! `PUSH`   | Push register values onto stack              | `PUSH rs [, rs]*`
+//! `POP`    | Pop register values from stack               | `POP rd [, rd]*`
 //! `LWI`    | Load word immediate                          | `LWI rd, value`
+//! `LI32`   | Load word immediate (one `LI` if it fits)    | `LI32 rd, value`
 //! `LDA`    | Load data address                            | `LDA rd, label`
 //! `LIA`    | Load instruction address                     | `LIA rd, label`
+//! `CALL`   | Call subroutine (same as `JL`)               | `CALL label`
+//! `RET`    | Return from subroutine (same as `JR $RA`)    | `RET`
+//!
+//! `PUSH` and `POP` accept a list of registers, for which `$SP` is only adjusted once. `POP` restores
+//! the registers saved by a `PUSH` with the same list, i.e. `PUSH $t0, $t1` is undone by `POP $t0, $t1`.
 //!
 //! [pest]: https://docs.rs/pest/
 
@@ -161,55 +186,626 @@
 // TODO: describe data labels and instruction labels
 // TODO: provide detailed documentation for each mnemonic (separate pages?)
 
+mod assembler;
+mod codegen;
 mod data;
+mod format;
 mod instructions;
 mod int_util;
 mod labels;
+mod mnemonics;
 mod parser;
+mod segment_info;
 mod source_map;
+mod stats;
+mod warning;
 
 #[cfg(test)]
 mod test;
 
-use parser::{Rule, VASMParser};
+pub use assembler::Assembler;
+pub use codegen::{assemble_instructions_from, AssembleError, Instruction, SymbolTable, Target};
+pub use format::format_source;
+use labels::LabelMap;
+pub use mnemonics::{mnemonics, MnemonicKind};
+pub use parser::Rule;
+use parser::VASMParser;
 use pest::iterators::Pair;
 use pest::{Parser, Span};
+pub use segment_info::SegmentInfo;
 pub use source_map::{SourceMap, SourceMapItem};
+pub use stats::ProgramStats;
+use std::collections::HashMap;
+use vcpu::{RegisterId, WORD_BYTES};
 use vex::Executable;
+pub use warning::Warning;
 
 pub type Error = pest::error::Error<Rule>;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Error returned by [`assemble_reader`], which distinguishes failing to read the input
+/// from failing to assemble it.
+///
+/// [`assemble_reader`]: fn.assemble_reader.html
+#[derive(Debug)]
+pub enum ReadError {
+    Io(std::io::Error),
+    Vasm(Error),
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "Reading input failed: {}", err),
+            ReadError::Vasm(err) => write!(f, "Parsing input failed:\n{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+impl From<std::io::Error> for ReadError {
+    fn from(err: std::io::Error) -> ReadError {
+        ReadError::Io(err)
+    }
+}
+
+impl From<Error> for ReadError {
+    fn from(err: Error) -> ReadError {
+        ReadError::Vasm(err)
+    }
+}
+
+/// Opt-in lints for [`assemble_with_diagnostics`], in addition to the warnings which are
+/// always reported.
+///
+/// [`assemble_with_diagnostics`]: fn.assemble_with_diagnostics.html
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// Warn about `ADDI`/`SUBI` adjusting `$SP` or `$FP` by an amount that is not a multiple of
+    /// the word size.
+    pub stack_alignment: bool,
+    /// Warn about instructions which can never be executed, since they follow an unconditional
+    /// `HALT`, `JMP` or `JR` without a label in between.
+    pub dead_code: bool,
+}
+
+/// Maximum sizes (in bytes) of the data and instructions of an assembled program, e.g. the
+/// sizes of the RAM and ROM of a device. Exceeding a limit is an assembly error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    pub max_data: u32,
+    pub max_instr: u32,
+}
+
+impl Default for Limits {
+    /// Returns the limits imposed by the 32 bit address space.
+    fn default() -> Limits {
+        Limits {
+            max_data: u32::max_value() - 1,
+            max_instr: (u32::max_value() / WORD_BYTES - 1) * WORD_BYTES,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Options<'a> {
+    data_offset: u32,
+    instr_offset: u32,
+    aliases: Option<&'a HashMap<String, RegisterId>>,
+    pad_to: Option<u32>,
+    diagnostics: Diagnostics,
+    limits: Limits,
+}
+
+/// Assembles `input` for a program whose data is loaded at `data_offset` and whose
+/// instructions are loaded at `instr_offset` (both in bytes).
+///
+/// `instr_offset` is added to the addresses loaded by `LIA`. Relative jumps and branches
+/// are not affected by it.
+pub fn assemble_full(
+    input: &str,
+    data_offset: u32,
+    instr_offset: u32,
+) -> Result<(Executable, SourceMap)> {
+    assemble_with_info(input, data_offset, instr_offset)
+        .map(|(executable, source_map, _)| (executable, source_map))
+}
+
+/// Like [`assemble_full`], but additionally returns a [`SegmentInfo`] describing the sizes of the
+/// data and instruction segments and the offsets of all labels.
+///
+/// [`assemble_full`]: fn.assemble_full.html
+/// [`SegmentInfo`]: struct.SegmentInfo.html
+pub fn assemble_with_info(
+    input: &str,
+    data_offset: u32,
+    instr_offset: u32,
+) -> Result<(Executable, SourceMap, SegmentInfo)> {
+    assemble_parsed(
+        parse(input)?,
+        &Options {
+            data_offset,
+            instr_offset,
+            ..Default::default()
+        },
+        &mut Vec::new(),
+    )
+}
+
+/// Like [`assemble_addressed`], but if `pad_to` is given, the instructions are padded with `NOP`
+/// up to a total of `pad_to` instruction words, e.g. to fill a fixed-size instruction ROM.
+///
+/// The padding instructions have source map entries with a `start_line` and `line_count` of `0`.
+///
+/// # Errors
+/// Additionally returns an error if the program consists of more than `pad_to` instruction words.
+///
+/// [`assemble_addressed`]: fn.assemble_addressed.html
+pub fn assemble_padded(
+    input: &str,
+    data_offset: u32,
+    pad_to: Option<u32>,
+) -> Result<(Executable, SourceMap)> {
+    assemble_parsed(
+        parse(input)?,
+        &Options {
+            data_offset,
+            pad_to,
+            ..Default::default()
+        },
+        &mut Vec::new(),
+    )
+    .map(|(executable, source_map, _)| (executable, source_map))
+}
+
+/// Like [`assemble`], but additionally accepts the register names in `aliases`
+/// (without the leading `$`), which are resolved to the mapped register.
+///
+/// Like the built-in register names, aliases are case-insensitive.
+/// They cannot shadow the built-in register names.
+///
+/// # Examples
+/// ```
+/// use std::collections::HashMap;
+/// use vcpu::RegisterId;
+///
+/// let mut aliases = HashMap::new();
+/// aliases.insert("arg0".to_owned(), RegisterId::A0);
+///
+/// let (aliased, _) = vasm::assemble_with_aliases(".data\n.instructions\nLI $arg0, 1", &aliases).unwrap();
+/// let (canonical, _) = vasm::assemble(".data\n.instructions\nLI $a0, 1").unwrap();
+/// assert_eq!(aliased, canonical);
+/// ```
+///
+/// [`assemble`]: fn.assemble.html
+pub fn assemble_with_aliases(
+    input: &str,
+    aliases: &HashMap<String, RegisterId>,
+) -> Result<(Executable, SourceMap)> {
+    assemble_parsed(
+        parse(input)?,
+        &Options {
+            aliases: Some(aliases),
+            ..Default::default()
+        },
+        &mut Vec::new(),
+    )
+    .map(|(executable, source_map, _)| (executable, source_map))
+}
+
+/// Like [`assemble_addressed`], but additionally returns warnings about suspicious constructs
+/// in the program, for example a last instruction that lets execution run off the end of
+/// the program (in which case the processor stops with `BadProgramCounter`).
+///
+/// [`assemble_addressed`]: fn.assemble_addressed.html
+pub fn assemble_with_warnings(
+    input: &str,
+    data_offset: u32,
+) -> Result<(Executable, SourceMap, Vec<Warning>)> {
+    assemble_with_diagnostics(input, data_offset, Diagnostics::default())
+}
+
+/// Like [`assemble_with_warnings`], but additionally reports the lints enabled in `diagnostics`.
+///
+/// # Examples
+/// ```
+/// use vasm::Diagnostics;
+///
+/// let input = ".data\n.instructions\nADDI $sp, $sp, 3\nHALT";
+/// let diagnostics = Diagnostics {
+///     stack_alignment: true,
+///     ..Default::default()
+/// };
+///
+/// let (_, _, warnings) = vasm::assemble_with_diagnostics(input, 0, diagnostics).unwrap();
+/// assert_eq!(warnings[0].line, 3);
+/// ```
+///
+/// [`assemble_with_warnings`]: fn.assemble_with_warnings.html
+pub fn assemble_with_diagnostics(
+    input: &str,
+    data_offset: u32,
+    diagnostics: Diagnostics,
+) -> Result<(Executable, SourceMap, Vec<Warning>)> {
+    let mut warnings = Vec::new();
+    let (executable, source_map, _) = assemble_parsed(
+        parse(input)?,
+        &Options {
+            data_offset,
+            diagnostics,
+            ..Default::default()
+        },
+        &mut warnings,
+    )?;
+    Ok((executable, source_map, warnings))
+}
+
+/// Like [`assemble_addressed`], but fails if the data or instructions of the program exceed
+/// `limits`. The error points at the data element or instruction which exceeds the limit.
+///
+/// # Examples
+/// ```
+/// use vasm::Limits;
+///
+/// let input = ".data\n.block 16\n.instructions\nHALT";
+/// let limits = Limits { max_data: 8, max_instr: 64 };
+///
+/// assert!(vasm::assemble_with_limits(input, 0, limits).is_err());
+/// ```
+///
+/// [`assemble_addressed`]: fn.assemble_addressed.html
+pub fn assemble_with_limits(
+    input: &str,
+    data_offset: u32,
+    limits: Limits,
+) -> Result<(Executable, SourceMap)> {
+    assemble_parsed(
+        parse(input)?,
+        &Options {
+            data_offset,
+            limits,
+            ..Default::default()
+        },
+        &mut Vec::new(),
+    )
+    .map(|(executable, source_map, _)| (executable, source_map))
+}
+
 pub fn assemble_addressed(input: &str, data_offset: u32) -> Result<(Executable, SourceMap)> {
-    assemble_parsed(parse(input)?, data_offset)
+    assemble_full(input, data_offset, 0u32)
 }
 
 pub fn assemble(input: &str) -> Result<(Executable, SourceMap)> {
     assemble_addressed(input, 0u32)
 }
 
+/// Like [`assemble_addressed`], but returns the typed instructions instead of an executable,
+/// along with the data, the entry point and the source map, e.g. to show the instructions of a
+/// program in a teaching tool.
+///
+/// All labels are resolved, so the targets of branches and jumps are always
+/// [`Target::Relative`]. Encoding the instructions with [`assemble_instructions_from`] yields
+/// the instructions of the executable.
+///
+/// # Examples
+/// ```
+/// use vasm::{Instruction, Target};
+/// use vcpu::{Opcode, RegisterId};
+///
+/// let input = ".data\n.instructions\nloop: BNZ $t0, loop\nHALT";
+/// let (instructions, data, entry_point, _) = vasm::assemble_to_instructions(input, 0).unwrap();
+///
+/// assert_eq!(
+///     instructions[0],
+///     Instruction::Branch { opcode: Opcode::BNZ, rs1: RegisterId::T0, target: Target::Relative(0) }
+/// );
+/// assert!(data.is_empty());
+/// assert_eq!(entry_point, 0);
+/// ```
+/// [`assemble_addressed`]: fn.assemble_addressed.html
+/// [`Target::Relative`]: enum.Target.html#variant.Relative
+/// [`assemble_instructions_from`]: fn.assemble_instructions_from.html
+pub fn assemble_to_instructions(
+    input: &str,
+    data_offset: u32,
+) -> Result<(Vec<Instruction<'static>>, Vec<u8>, u32, SourceMap)> {
+    let options = Options {
+        data_offset,
+        ..Default::default()
+    };
+    let (data, _, section) = process_program(parse(input)?, &options, &mut Vec::new())?;
+    let instructions =
+        instructions::resolve_instructions(&section.instr, &section.labels, options.instr_offset)?;
+    let entry_point = resolve_entry_point(section.entry_label, &section.labels)?;
+
+    Ok((instructions, data, entry_point, section.source_map))
+}
+
+/// Like [`assemble_addressed`], but reads the source from `reader`, which must yield UTF-8.
+///
+/// # Examples
+/// ```
+/// use std::io::Cursor;
+///
+/// let source = ".data\n.instructions\nHALT";
+/// let (executable, _) = vasm::assemble_reader(Cursor::new(source), 0).unwrap();
+/// assert_eq!(executable, vasm::assemble(source).unwrap().0);
+/// ```
+///
+/// [`assemble_addressed`]: fn.assemble_addressed.html
+pub fn assemble_reader<R: std::io::Read>(
+    mut reader: R,
+    data_offset: u32,
+) -> std::result::Result<(Executable, SourceMap), ReadError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    Ok(assemble_addressed(&input, data_offset)?)
+}
+
+/// Checks `input` and returns the sizes of the program, without assembling it into an executable,
+/// e.g. to validate a program in an editor on every change.
+///
+/// The same errors are reported as by [`assemble_with_warnings`], except for unknown labels and
+/// jumps or branches which are too far away, which are only detected when assembling.
+///
+/// # Examples
+/// ```
+/// let stats = vasm::analyze(".data\nvalue: .word 1, 2\n.instructions\nLWI $t0, 0x12345678").unwrap();
+///
+/// assert_eq!(stats.instruction_count, 2);
+/// assert_eq!(stats.data_size, 8);
+/// assert_eq!(stats.label_count, 1);
+/// assert_eq!(stats.warnings.len(), 1);
+/// ```
+/// [`assemble_with_warnings`]: fn.assemble_with_warnings.html
+pub fn analyze(input: &str) -> Result<ProgramStats> {
+    let mut warnings = Vec::new();
+    let (data, data_labels, section) =
+        process_program(parse(input)?, &Options::default(), &mut warnings)?;
+
+    Ok(ProgramStats {
+        instruction_count: section.instr.len() as u32,
+        data_size: data.len() as u32,
+        label_count: (data_labels.len() + section.labels.len()) as u32,
+        warnings,
+    })
+}
+
+/// Parses a single, optionally labeled instruction, e.g. to validate one line at a time in an editor.
+///
+/// Only the syntax is checked; labels and register aliases are not resolved.
+/// On success, the [`Rule::labeled_instruction`] pair is returned.
+///
+/// # Examples
+/// ```
+/// use vasm::Rule;
+///
+/// let pair = vasm::parse_instruction_line("loop: ADD $t0, $t0, $t1 # comment").unwrap();
+/// assert_eq!(pair.as_rule(), Rule::labeled_instruction);
+/// assert!(vasm::parse_instruction_line("ADD $t0, $t0").is_err());
+/// ```
+/// [`Rule::labeled_instruction`]: enum.Rule.html#variant.labeled_instruction
+pub fn parse_instruction_line(src: &str) -> Result<Pair<'_, Rule>> {
+    let line = parse_rule(Rule::instruction_line, src)?;
+    Ok(line.into_inner().next().unwrap())
+}
+
 fn new_parser_error(span: Span, message: String) -> Error {
     Error::new_from_span(pest::error::ErrorVariant::CustomError { message }, span)
 }
 
 fn parse(input: &str) -> Result<Pair<Rule>> {
-    Ok(VASMParser::parse(Rule::program, input)?.next().unwrap())
+    parse_rule(Rule::program, input)
+}
+
+fn parse_rule(rule: Rule, input: &str) -> Result<Pair<'_, Rule>> {
+    Ok(VASMParser::parse(rule, input)?.next().unwrap())
 }
 
-fn assemble_parsed(pair: Pair<Rule>, data_offset: u32) -> Result<(Executable, SourceMap)> {
-    let mut pairs = pair.into_inner();
+fn assemble_parsed(
+    pair: Pair<Rule>,
+    options: &Options,
+    warnings: &mut Vec<Warning>,
+) -> Result<(Executable, SourceMap, SegmentInfo)> {
+    let (data, data_labels, section) = process_program(pair, options, warnings)?;
+    finish_instruction_section(section, data, &data_labels, options, warnings)
+}
 
-    let (data, data_labels) = data::process_data(pairs.next().unwrap())?;
-    let (instr, instr_labels, source_map) =
-        instructions::process_instructions(pairs.next().unwrap(), &data_labels, data_offset)?;
+/// Processes the data and instructions of a parsed program, up to (but excluding) encoding the
+/// instructions. This is shared by [`analyze`] and everything that assembles a whole program.
+///
+/// [`analyze`]: fn.analyze.html
+fn process_program<'i>(
+    pair: Pair<'i, Rule>,
+    options: &Options,
+    warnings: &mut Vec<Warning>,
+) -> Result<(Vec<u8>, LabelMap<'i>, InstructionSection<'i>)> {
+    let (data_pairs, instr_pairs) = split_sections(pair);
+    let (data, data_labels) =
+        process_data_section(data_pairs, options.data_offset, options.limits.max_data)?;
+    let section = process_instruction_section(instr_pairs, &data_labels, options, warnings)?;
 
-    Ok((
-        Executable::from(
-            data_offset,
-            instructions::assemble_instructions(&instr, &instr_labels)?,
-            data,
-        ),
+    Ok((data, data_labels, section))
+}
+
+/// Splits a parsed program into its `.data` and `.instructions` sections.
+fn split_sections(pair: Pair<Rule>) -> (Vec<Pair<Rule>>, Vec<Pair<Rule>>) {
+    pair.into_inner()
+        .filter(|p| p.as_rule() != Rule::EOI)
+        .partition(|p| p.as_rule() == Rule::data)
+}
+
+fn process_data_section<'i>(
+    pairs: Vec<Pair<'i, Rule>>,
+    data_offset: u32,
+    max_size: u32,
+) -> Result<(Vec<u8>, LabelMap<'i>)> {
+    let data_span = pairs.last().unwrap().as_span();
+    let (data, data_labels) = data::process_data(pairs, max_size)?;
+
+    if data_offset.checked_add(data.len() as u32).is_none() {
+        return Err(new_parser_error(
+            data_span,
+            format!(
+                "Data of {} bytes at offset {} exceeds the address space",
+                data.len(),
+                data_offset
+            ),
+        ));
+    }
+
+    Ok((data, data_labels))
+}
+
+/// The instructions of a program, after labels have been collected and far branches have been
+/// expanded.
+struct InstructionSection<'i> {
+    instr: Vec<instructions::ParsedInstruction<'i>>,
+    labels: LabelMap<'i>,
+    source_map: SourceMap,
+    entry_label: Option<Span<'i>>,
+    span: Span<'i>,
+}
+
+fn assemble_instruction_section<'i>(
+    instr_pairs: Vec<Pair<'i, Rule>>,
+    data: Vec<u8>,
+    data_labels: &LabelMap<'i>,
+    options: &Options,
+    warnings: &mut Vec<Warning>,
+) -> Result<(Executable, SourceMap, SegmentInfo)> {
+    let section = process_instruction_section(instr_pairs, data_labels, options, warnings)?;
+    finish_instruction_section(section, data, data_labels, options, warnings)
+}
+
+/// Validates the entry point and processes the instructions in `instr_pairs`, warning if the
+/// last instruction falls through.
+fn process_instruction_section<'i>(
+    instr_pairs: Vec<Pair<'i, Rule>>,
+    data_labels: &LabelMap<'i>,
+    options: &Options,
+    warnings: &mut Vec<Warning>,
+) -> Result<InstructionSection<'i>> {
+    let no_aliases = HashMap::new();
+    let aliases = options.aliases.unwrap_or(&no_aliases);
+
+    let span = instr_pairs.last().unwrap().as_span();
+    let mut entry_label = None;
+    for entry in instr_pairs
+        .iter()
+        .flat_map(|p| p.clone().into_inner())
+        .filter(|p| p.as_rule() == Rule::entry)
+    {
+        let label = entry.into_inner().next().unwrap().as_span();
+        if entry_label.is_some() {
+            return Err(new_parser_error(
+                label,
+                "Entry point is already set".to_owned(),
+            ));
+        }
+        entry_label = Some(label);
+    }
+    let (mut instr, mut instr_labels, mut source_map) = instructions::process_instructions(
+        instr_pairs,
+        data_labels,
+        options.data_offset,
+        aliases,
+        options.limits.max_instr,
+    )?;
+
+    instructions::expand_long_branches(
+        &mut instr,
+        &mut instr_labels,
+        &mut source_map,
+        options.limits.max_instr,
+    )?;
+
+    warnings.extend(instructions::check_fall_through(&instr, &source_map));
+
+    Ok(InstructionSection {
+        instr,
+        labels: instr_labels,
         source_map,
-    ))
+        entry_label,
+        span,
+    })
+}
+
+/// Runs the optional diagnostics on `section`, then pads and encodes its instructions.
+fn finish_instruction_section<'i>(
+    section: InstructionSection<'i>,
+    data: Vec<u8>,
+    data_labels: &LabelMap<'i>,
+    options: &Options,
+    warnings: &mut Vec<Warning>,
+) -> Result<(Executable, SourceMap, SegmentInfo)> {
+    let data_offset = options.data_offset;
+    let instr_offset = options.instr_offset;
+    let InstructionSection {
+        mut instr,
+        labels: instr_labels,
+        mut source_map,
+        entry_label,
+        span: instr_span,
+    } = section;
+
+    if options.diagnostics.stack_alignment {
+        warnings.extend(instructions::check_stack_alignment(&instr, &source_map));
+    }
+    if options.diagnostics.dead_code {
+        warnings.extend(instructions::check_dead_code(
+            &instr,
+            &instr_labels,
+            &source_map,
+        ));
+    }
+
+    if let Some(pad_to) = options.pad_to {
+        if instr.len() > pad_to as usize {
+            return Err(new_parser_error(
+                instr_span,
+                format!(
+                    "Program of {} instructions exceeds the padded size of {} instructions",
+                    instr.len(),
+                    pad_to
+                ),
+            ));
+        }
+
+        instructions::pad_instructions(&mut instr, &mut source_map, pad_to as usize);
+    }
+    let instructions = instructions::assemble_instructions(&instr, &instr_labels, instr_offset)?;
+
+    let entry_point = resolve_entry_point(entry_label, &instr_labels)?;
+
+    let segment_info = SegmentInfo::new(
+        data_offset,
+        data.len() as u32,
+        data_labels,
+        instr_offset,
+        instructions.len() as u32,
+        &instr_labels,
+    );
+
+    let mut executable = Executable::from(data_offset, instructions, data);
+    executable.set_entry_point(entry_point);
+
+    Ok((executable, source_map, segment_info))
+}
+
+/// Returns the address of the instruction at `entry_label`, or 0 if there is no entry label.
+fn resolve_entry_point(entry_label: Option<Span>, instr_labels: &LabelMap) -> Result<u32> {
+    match entry_label {
+        Some(label) => {
+            let index = *instr_labels
+                .get(label.as_str())
+                .ok_or_else(|| new_parser_error(label, "Label not found".to_owned()))?;
+            index.checked_mul(WORD_BYTES).ok_or_else(|| {
+                new_parser_error(label, "Entry point exceeds the address space".to_owned())
+            })
+        }
+        None => Ok(0),
+    }
 }
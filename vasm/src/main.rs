@@ -68,18 +68,24 @@ fn main() {
                 .value_name("SOURCE_MAP")
                 .help("Sets the file to write the source map to"),
         )
+        .arg(
+            Arg::with_name("raw")
+                .long("raw")
+                .help("Writes only the raw instruction bytes, without a header or data"),
+        )
         .get_matches();
 
     let input = matches.value_of("INPUT").unwrap();
     let output = matches.value_of("OUTPUT");
     let map = matches.value_of("SOURCE_MAP");
+    let raw = matches.is_present("raw");
 
-    if let Err(err) = vasm(input, output, map) {
+    if let Err(err) = vasm(input, output, map, raw) {
         eprintln!("{}", err);
     }
 }
 
-fn vasm(input: &str, output: Option<&str>, map: Option<&str>) -> Result<(), Error> {
+fn vasm(input: &str, output: Option<&str>, map: Option<&str>, raw: bool) -> Result<(), Error> {
     let input_path = Path::new(input);
 
     // Read input file
@@ -93,20 +99,33 @@ fn vasm(input: &str, output: Option<&str>, map: Option<&str>) -> Result<(), Erro
         .map_err(|err| Error::Io(err, IOErrorContext::ReadInput, input_path.to_owned()))?;
 
     // Perform parse
-    let (executable, source_map) = vasm::assemble(&input).map_err(|err| {
-        Error::Vasm(match input_path.to_str() {
-            Some(path_str) => err.with_path(path_str),
-            None => err,
-        })
-    })?;
+    let (executable, source_map, warnings) =
+        vasm::assemble_with_warnings(&input, 0).map_err(|err| {
+            Error::Vasm(match input_path.to_str() {
+                Some(path_str) => err.with_path(path_str),
+                None => err,
+            })
+        })?;
+
+    for warning in warnings {
+        eprintln!("{}", warning);
+    }
 
     let output_path: PathBuf = output
         .map(PathBuf::from)
-        .unwrap_or_else(|| input_path.with_extension("vex"));
+        .unwrap_or_else(|| input_path.with_extension(if raw { "bin" } else { "vex" }));
 
     // Write output file
-    vex::write_file(&output_path, &executable)
-        .map_err(|err| Error::Io(err, IOErrorContext::WriteOutput, output_path))?;
+    let result = if raw {
+        File::create(&output_path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            vex::write_instructions(&mut writer, &executable)?;
+            writer.flush()
+        })
+    } else {
+        vex::write_file(&output_path, &executable)
+    };
+    result.map_err(|err| Error::Io(err, IOErrorContext::WriteOutput, output_path))?;
 
     // Write source map file (if path is set)
     if let Some(map_path_str) = map {
@@ -0,0 +1,123 @@
+/// The operands accepted by a mnemonic, which correspond to the instruction rules of the grammar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MnemonicKind {
+    /// `ALU` instruction: `ADD $rd, $rs1, $rs2`.
+    Alu,
+    /// `FLOP` instruction: `FADD $rd, $rs1, $rs2`.
+    Flop,
+    /// Instruction with a signed immediate: `ADDI $rd, $rs1, imm`.
+    Immediate,
+    /// Instruction with an unsigned immediate: `SLTUI $rd, $rs1, imm`.
+    UnsignedImmediate,
+    /// Instruction with a destination and a source register: `COPY $rd, $rs1`.
+    DestinationSource,
+    /// Load of a signed immediate: `LI $rd, imm`.
+    LoadImmediate,
+    /// Load of an unsigned immediate into half of a register: `SLO $rd, imm`.
+    SetImmediate,
+    /// Instruction without operands: `HALT`.
+    NoOperands,
+    /// Conditional branch: `BEZ $rs1, target`.
+    Branch,
+    /// Jump to the address in a register: `JR $rs1`.
+    JumpRegister,
+    /// Load or store: `LW $rd, offset($rs1)`.
+    LoadStore,
+    /// Jump: `JMP target`.
+    Jump,
+    /// Shorthand which is expanded into one or more other instructions (e.g. `PUSH $rs1`).
+    Shorthand,
+}
+
+static MNEMONICS: &[(&str, MnemonicKind)] = &[
+    ("ADD", MnemonicKind::Alu),
+    ("SUB", MnemonicKind::Alu),
+    ("MUL", MnemonicKind::Alu),
+    ("DIV", MnemonicKind::Alu),
+    ("AND", MnemonicKind::Alu),
+    ("OR", MnemonicKind::Alu),
+    ("XOR", MnemonicKind::Alu),
+    ("SLL", MnemonicKind::Alu),
+    ("SRL", MnemonicKind::Alu),
+    ("SRA", MnemonicKind::Alu),
+    ("SEQ", MnemonicKind::Alu),
+    ("SNE", MnemonicKind::Alu),
+    ("SLT", MnemonicKind::Alu),
+    ("SGT", MnemonicKind::Alu),
+    ("SLE", MnemonicKind::Alu),
+    ("SGE", MnemonicKind::Alu),
+    ("SLTU", MnemonicKind::Alu),
+    ("SGTU", MnemonicKind::Alu),
+    ("SLEU", MnemonicKind::Alu),
+    ("SGEU", MnemonicKind::Alu),
+    ("FADD", MnemonicKind::Flop),
+    ("FSUB", MnemonicKind::Flop),
+    ("FMUL", MnemonicKind::Flop),
+    ("FDIV", MnemonicKind::Flop),
+    ("FMOD", MnemonicKind::Flop),
+    ("ADDI", MnemonicKind::Immediate),
+    ("SUBI", MnemonicKind::Immediate),
+    ("MULI", MnemonicKind::Immediate),
+    ("DIVI", MnemonicKind::Immediate),
+    ("ANDI", MnemonicKind::Immediate),
+    ("ORI", MnemonicKind::Immediate),
+    ("XORI", MnemonicKind::Immediate),
+    ("SLLI", MnemonicKind::Immediate),
+    ("SRLI", MnemonicKind::Immediate),
+    ("SRAI", MnemonicKind::Immediate),
+    ("SEQI", MnemonicKind::Immediate),
+    ("SNEI", MnemonicKind::Immediate),
+    ("SLTI", MnemonicKind::Immediate),
+    ("SGTI", MnemonicKind::Immediate),
+    ("SLEI", MnemonicKind::Immediate),
+    ("SGEI", MnemonicKind::Immediate),
+    ("SLTUI", MnemonicKind::UnsignedImmediate),
+    ("SGTUI", MnemonicKind::UnsignedImmediate),
+    ("SLEUI", MnemonicKind::UnsignedImmediate),
+    ("SGEUI", MnemonicKind::UnsignedImmediate),
+    ("COPY", MnemonicKind::DestinationSource),
+    ("FLIP", MnemonicKind::DestinationSource),
+    ("ITOF", MnemonicKind::DestinationSource),
+    ("FTOI", MnemonicKind::DestinationSource),
+    ("UTOF", MnemonicKind::DestinationSource),
+    ("FTOU", MnemonicKind::DestinationSource),
+    ("LI", MnemonicKind::LoadImmediate),
+    ("LHI", MnemonicKind::LoadImmediate),
+    ("SLO", MnemonicKind::SetImmediate),
+    ("SHI", MnemonicKind::SetImmediate),
+    ("NOP", MnemonicKind::NoOperands),
+    ("HALT", MnemonicKind::NoOperands),
+    ("BEZ", MnemonicKind::Branch),
+    ("BNZ", MnemonicKind::Branch),
+    ("JR", MnemonicKind::JumpRegister),
+    ("JLR", MnemonicKind::JumpRegister),
+    ("LB", MnemonicKind::LoadStore),
+    ("LH", MnemonicKind::LoadStore),
+    ("LW", MnemonicKind::LoadStore),
+    ("SB", MnemonicKind::LoadStore),
+    ("SH", MnemonicKind::LoadStore),
+    ("SW", MnemonicKind::LoadStore),
+    ("JMP", MnemonicKind::Jump),
+    ("JL", MnemonicKind::Jump),
+    ("PUSH", MnemonicKind::Shorthand),
+    ("POP", MnemonicKind::Shorthand),
+    ("LWI", MnemonicKind::Shorthand),
+    ("LI32", MnemonicKind::Shorthand),
+    ("LDA", MnemonicKind::Shorthand),
+    ("LIA", MnemonicKind::Shorthand),
+    ("CALL", MnemonicKind::Shorthand),
+    ("RET", MnemonicKind::Shorthand),
+];
+
+/// Returns every mnemonic accepted by the assembler (in upper case) together with its kind,
+/// e.g. for syntax highlighting or autocompletion. Mnemonics are case-insensitive in VASM.
+///
+/// # Examples
+/// ```
+/// use vasm::MnemonicKind;
+///
+/// assert!(vasm::mnemonics().contains(&("LW", MnemonicKind::LoadStore)));
+/// ```
+pub fn mnemonics() -> &'static [(&'static str, MnemonicKind)] {
+    MNEMONICS
+}
@@ -0,0 +1,44 @@
+use crate::labels::LabelMap;
+use std::collections::HashMap;
+use vcpu::word_index_to_address;
+
+/// Describes the size and layout of the segments of an assembled program.
+///
+/// All label offsets are in bytes and relative to the start of their segment,
+/// i.e. `data_offset` or `instr_offset` has to be added to obtain the runtime address.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SegmentInfo {
+    pub data_offset: u32,
+    pub data_len: u32,
+    pub data_labels: HashMap<String, u32>,
+    pub instr_offset: u32,
+    pub instr_len: u32,
+    pub instr_labels: HashMap<String, u32>,
+}
+
+impl SegmentInfo {
+    pub(crate) fn new(
+        data_offset: u32,
+        data_len: u32,
+        data_labels: &LabelMap,
+        instr_offset: u32,
+        instr_len: u32,
+        instr_labels: &LabelMap,
+    ) -> SegmentInfo {
+        SegmentInfo {
+            data_offset,
+            data_len,
+            data_labels: to_owned_labels(data_labels, |offset| offset),
+            instr_offset,
+            instr_len,
+            instr_labels: to_owned_labels(instr_labels, word_index_to_address),
+        }
+    }
+}
+
+fn to_owned_labels(labels: &LabelMap, to_address: fn(u32) -> u32) -> HashMap<String, u32> {
+    labels
+        .iter()
+        .map(|(label, offset)| ((*label).to_owned(), to_address(*offset)))
+        .collect()
+}
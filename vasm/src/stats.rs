@@ -0,0 +1,15 @@
+use crate::Warning;
+
+/// Sizes of a program and the warnings about it, as returned by [`analyze`].
+///
+/// [`analyze`]: fn.analyze.html
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProgramStats {
+    /// Number of instruction words, including the ones produced by shorthand mnemonics.
+    pub instruction_count: u32,
+    /// Size of the data in bytes.
+    pub data_size: u32,
+    /// Number of data and instruction labels.
+    pub label_count: u32,
+    pub warnings: Vec<Warning>,
+}
@@ -33,7 +33,14 @@ fn large_unsigned_literal() {
     ];
 
     let pair = parse_rule(Rule::instructions, input).unwrap();
-    let (instr, _, _) = process_instructions(pair, &HashMap::new(), 0).unwrap();
+    let (instr, _, _) = process_instructions(
+        vec![pair],
+        &HashMap::new(),
+        0,
+        &HashMap::new(),
+        Limits::default().max_instr,
+    )
+    .unwrap();
 
     assert_eq!(instr, expected_instr);
 }
@@ -56,7 +63,14 @@ HALT";
     let expected_labels = HashMap::new();
 
     let pair = parse_rule(Rule::instructions, input).unwrap();
-    let (instr, labels, _) = process_instructions(pair, &HashMap::new(), 0).unwrap();
+    let (instr, labels, _) = process_instructions(
+        vec![pair],
+        &HashMap::new(),
+        0,
+        &HashMap::new(),
+        Limits::default().max_instr,
+    )
+    .unwrap();
 
     assert_eq!(instr, expected_instr);
     assert_eq!(labels, expected_labels);
@@ -96,7 +110,14 @@ end:  HALT";
     ];
 
     let pair = parse_rule(Rule::instructions, input).unwrap();
-    let (instr, labels, _) = process_instructions(pair, &HashMap::new(), 0).unwrap();
+    let (instr, labels, _) = process_instructions(
+        vec![pair],
+        &HashMap::new(),
+        0,
+        &HashMap::new(),
+        Limits::default().max_instr,
+    )
+    .unwrap();
 
     assert_eq!(instr, expected_instr);
     assert_eq!(labels, expected_labels);
@@ -328,6 +349,57 @@ HALT";
 
 // TODO: add loading macro optimizations (only use one instruction when possible)
 
+#[test]
+fn macro_push_pop_list() {
+    let input = ".data
+.block 16
+.instructions
+LI $sp, 16
+LI $t0, 1
+LI $t1, 2
+PUSH $t0, $t1
+LI $t0, 0
+LI $t1, 0
+POP $t0, $t1
+SW $t0, 0($zero)
+SW $t1, 4($zero)
+HALT";
+
+    let expected_instr = transmute_vec(vec![
+        instr_i!(LI, SP, ZERO, 16),
+        instr_i!(LI, T0, ZERO, 1),
+        instr_i!(LI, T1, ZERO, 2),
+        instr_i!(SW, T0, SP, -4),
+        instr_i!(SW, T1, SP, -8),
+        instr_i!(SUBI, SP, SP, 8),
+        instr_i!(LI, T0, ZERO, 0),
+        instr_i!(LI, T1, ZERO, 0),
+        instr_i!(LW, T0, SP, 4),
+        instr_i!(LW, T1, SP, 0),
+        instr_i!(ADDI, SP, SP, 8),
+        instr_i!(SW, T0, ZERO, 0),
+        instr_i!(SW, T1, ZERO, 4),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ]);
+
+    let (executable, source_map) = assemble(input).unwrap();
+    assert_eq!(executable.instructions(), &expected_instr[..]);
+
+    let lines: Vec<_> = source_map.iter().map(|item| item.start_line).collect();
+    assert_eq!(lines, [4, 5, 6, 7, 7, 7, 8, 9, 10, 10, 10, 11, 12, 13]);
+
+    let (exit_code, memory) = vex::run_executable(&executable, 16);
+    assert_eq!(exit_code, ExitCode::Halted);
+    assert_eq!(&memory[..8], &[1, 0, 0, 0, 2, 0, 0, 0]);
+    assert_eq!(&memory[8..], &[2, 0, 0, 0, 1, 0, 0, 0]);
+
+    assert!(
+        format_source(".data\n.instructions\npush $t0,$t1 # save\nHALT")
+            .unwrap()
+            .contains("    PUSH $T0, $T1 # save\n")
+    );
+}
+
 #[test]
 fn macro_lwi_signed() {
     let input = ".data
@@ -402,3 +474,1059 @@ HALT";
     let (executable, _) = assemble(input).unwrap();
     assert_eq!(executable.instructions(), &expected_instr[..]);
 }
+
+#[test]
+fn instr_offset() {
+    let input = ".data
+.instructions
+LIA $T4, stuff
+JMP stuff
+stuff: NOP
+HALT";
+
+    let expected_instr = transmute_vec(vec![
+        instr_i!(SLO, T4, ZERO, 0x100C),
+        instr_i!(SHI, T4, ZERO, 0),
+        instr_j!(JMP, jmp_addr_i32(1)),
+        nop!(),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ]);
+
+    let (executable, _) = assemble_full(input, 0, 0x1000).unwrap();
+    assert_eq!(executable.instructions(), &expected_instr[..]);
+}
+
+#[test]
+fn instr_offset_upper_bits() {
+    let input = ".data
+.instructions
+LIA $T4, stuff
+stuff: HALT";
+
+    let expected_instr = transmute_vec(vec![
+        instr_i!(SLO, T4, ZERO, 8),
+        instr_i!(SHI, T4, ZERO, 0x2),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ]);
+
+    let (executable, _) = assemble_full(input, 0, 0x2_0000).unwrap();
+    assert_eq!(executable.instructions(), &expected_instr[..]);
+}
+
+#[test]
+fn instr_offset_overflow() {
+    let input = ".data
+.instructions
+LIA $T4, stuff
+NOP
+NOP
+NOP
+stuff: HALT";
+
+    assert!(assemble_full(input, 0, 0xFFFF_FFF0).is_err());
+}
+
+#[test]
+fn segment_info_loop() {
+    let input = ".data
+.block 128
+buffer: .word 1, 2
+.instructions
+loop: SLTI $t2, $t0, 32
+      BEZ  $t2, end
+      SLLI $t1, $t0, 2
+      SW   $t0, 0($t1)
+      ADDI $t0, $t0, 1
+      JMP loop
+end:  HALT";
+
+    let (executable, _, info) = assemble_with_info(input, 0x40, 0x1000).unwrap();
+
+    assert_eq!(
+        info,
+        SegmentInfo {
+            data_offset: 0x40,
+            data_len: executable.data().len() as u32,
+            data_labels: hashmap!("buffer".to_owned() => 128),
+            instr_offset: 0x1000,
+            instr_len: executable.instructions().len() as u32,
+            instr_labels: hashmap!("loop".to_owned() => 0, "end".to_owned() => 24),
+        }
+    );
+    assert_eq!(info.data_len, 136);
+    assert_eq!(info.instr_len, 28);
+}
+
+#[test]
+fn data_offset_overflow() {
+    let input = ".data
+.block 16
+.instructions
+HALT";
+
+    assert!(assemble_addressed(input, 0xFFFF_FFF8).is_err());
+    assert!(assemble_addressed(input, 0xFFFF_FFE0).is_ok());
+}
+
+#[test]
+fn register_aliases() {
+    let aliases = hashmap!(
+        "arg0".to_owned() => RegisterId::A0,
+        "Counter".to_owned() => RegisterId::S3
+    );
+
+    let input = ".data
+.instructions
+ADD $arg0, $counter, $t1
+ADDI $COUNTER, $counter, 1";
+
+    let expected_instr =
+        transmute_vec(vec![instr_alu!(ADD, A0, S3, T1), instr_i!(ADDI, S3, S3, 1)]);
+
+    let (executable, _) = assemble_with_aliases(input, &aliases).unwrap();
+    assert_eq!(executable.instructions(), &expected_instr[..]);
+}
+
+#[test]
+fn unknown_register_alias() {
+    let input = ".data
+.instructions
+ADD $arg0, $t0, $t1";
+
+    assert!(assemble(input).is_err());
+}
+
+#[test]
+fn reject_zero_destination() {
+    for line in &[
+        "ADD $ZERO, $t0, $t1",
+        "FMUL $zero, $t0, $t1",
+        "ADDI $ZERO, $t0, 1",
+        "SLTUI $ZERO, $t0, 1",
+        "COPY $ZERO, $t0",
+        "LI $ZERO, 1",
+        "SLO $ZERO, 1",
+        "LW $ZERO, 0($sp)",
+        "POP $ZERO",
+        "LWI $ZERO, 70000",
+    ] {
+        let input = format!(".data\n.instructions\n{}\nHALT", line);
+        assert!(assemble(&input).is_err(), "{} was accepted", line);
+    }
+}
+
+#[test]
+fn allow_zero_source() {
+    let input = ".data
+.instructions
+SW $ZERO, 0($sp)
+PUSH $ZERO
+JR $ZERO";
+
+    let expected_instr = transmute_vec(vec![
+        instr_i!(SW, ZERO, SP, 0),
+        instr_i!(SW, ZERO, SP, -4),
+        instr_i!(SUBI, SP, SP, 4),
+        instr_i!(JR, ZERO, ZERO, 0),
+    ]);
+
+    let (executable, _) = assemble(input).unwrap();
+    assert_eq!(executable.instructions(), &expected_instr[..]);
+}
+
+#[test]
+fn macro_call_ret() {
+    let input = ".data
+.instructions
+      CALL func
+      HALT
+func: ADDI $T0, $T0, 1
+      RET";
+
+    let expected_instr = transmute_vec(vec![
+        instr_j!(JL, jmp_addr_i32(2)),
+        instr_i!(HALT, ZERO, ZERO, 0),
+        instr_i!(ADDI, T0, T0, 1),
+        instr_i!(JR, ZERO, RA, 0),
+    ]);
+
+    let (executable, source_map) = assemble(input).unwrap();
+    assert_eq!(executable.instructions(), &expected_instr[..]);
+    assert_eq!(
+        source_map,
+        vec![
+            SourceMapItem {
+                start_line: 3,
+                line_count: 1
+            },
+            SourceMapItem {
+                start_line: 4,
+                line_count: 1
+            },
+            SourceMapItem {
+                start_line: 5,
+                line_count: 1
+            },
+            SourceMapItem {
+                start_line: 6,
+                line_count: 1
+            },
+        ]
+    );
+}
+
+#[test]
+fn assemble_loop_matches_built_executable() {
+    let input = ".data
+.block 128
+.instructions
+loop: SLTI $t2, $t0, 32
+      BEZ  $t2, end
+      SLLI $t1, $t0, 2
+      SW   $t0, 0($t1)
+      ADDI $t0, $t0, 1
+      JMP loop
+end:  HALT";
+
+    let (assembled, _) = assemble(input).unwrap();
+
+    let mut built = Executable::instructions_only(Vec::new());
+    built.push_word(instr_i!(SLTI, T2, T0, 32));
+    built.push_word(instr_i!(BEZ, ZERO, T2, jmp_addr_i16(5)));
+    built.push_word(instr_i!(SLLI, T1, T0, 2));
+    built.push_word(instr_i!(SW, T0, T1, 0));
+    built.push_word(instr_i!(ADDI, T0, T0, 1));
+    built.push_word(instr_j!(JMP, jmp_addr_i32(-5)));
+    built.push_word(instr_i!(HALT, ZERO, ZERO, 0));
+    built.append_data(&[0; 128]);
+
+    assert_eq!(built, assembled);
+    assert_eq!(
+        vex::run_executable(&built, 128),
+        vex::run_executable(&assembled, 128)
+    );
+}
+
+#[test]
+fn pad_to() {
+    let input = ".data
+.instructions
+LI $t0, 5
+ADDI $t0, $t0, 1
+HALT";
+
+    let mut expected_words = vec![
+        instr_i!(LI, T0, ZERO, 5),
+        instr_i!(ADDI, T0, T0, 1),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ];
+    expected_words.resize(8, nop!());
+
+    let (executable, source_map) = assemble_padded(input, 0, Some(8)).unwrap();
+    assert_eq!(
+        executable.instructions(),
+        &transmute_vec(expected_words)[..]
+    );
+    assert_eq!(executable.instructions().len(), 8 * WORD_BYTES as usize);
+    assert_eq!(source_map.len(), 8);
+    assert_eq!(
+        source_map[2],
+        SourceMapItem {
+            start_line: 5,
+            line_count: 1
+        }
+    );
+    assert_eq!(
+        source_map[3],
+        SourceMapItem {
+            start_line: 0,
+            line_count: 0
+        }
+    );
+
+    assert!(assemble_padded(input, 0, Some(2)).is_err());
+    assert_eq!(
+        assemble_padded(input, 0, None).unwrap(),
+        assemble(input).unwrap()
+    );
+}
+
+#[test]
+fn warn_fall_through() {
+    let input = ".data
+.instructions
+LI $t0, 1
+ADD $t1, $t0, $t0";
+
+    let (_, _, warnings) = assemble_with_warnings(input, 0).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].line, 4);
+}
+
+#[test]
+fn no_warning_for_halt_or_jump() {
+    let inputs = [
+        ".data
+.instructions
+ADD $t1, $t0, $t0
+HALT",
+        ".data
+.instructions
+start: ADD $t1, $t0, $t0
+JMP start",
+        ".data
+.instructions
+ADD $t1, $t0, $t0
+JR $ra",
+    ];
+
+    for input in inputs.iter() {
+        let (_, _, warnings) = assemble_with_warnings(input, 0).unwrap();
+        assert!(warnings.is_empty(), "{}", input);
+    }
+}
+
+#[test]
+fn assembler_snippets() {
+    let mut assembler = Assembler::new(
+        ".data
+.block 8
+answer: .word 42",
+        0x100,
+    )
+    .unwrap();
+    assembler.add_alias("result".to_owned(), RegisterId::V0);
+
+    let snippets = [
+        ".instructions
+LDA $t0, answer
+LW $result, 0($t0)
+HALT",
+        ".instructions
+LDA $t1, answer
+LW $t2, 0($t1)
+ADD $result, $t2, $t2
+HALT",
+    ];
+
+    for snippet in &snippets {
+        let program = format!(
+            ".data\n.block 8\nanswer: .word 42\n{}",
+            snippet.replace("$result", "$v0")
+        );
+        let (expected, _) = assemble_addressed(&program, 0x100).unwrap();
+
+        let (executable, source_map) = assembler.assemble_snippet(snippet).unwrap();
+        assert_eq!(executable, expected);
+        assert_eq!(source_map[0].start_line, 2);
+    }
+
+    assert!(assembler
+        .assemble_snippet(".instructions\nLDA $t0, missing")
+        .is_err());
+    assert!(Assembler::new(".data\n.instructions\nHALT", 0).is_err());
+}
+
+#[test]
+fn parse_single_instruction_line() {
+    let pair = parse_instruction_line("  ADD $t0, $T1, $t2  ").unwrap();
+    assert_eq!(pair.as_rule(), Rule::labeled_instruction);
+    assert_eq!(pair.as_str(), "ADD $t0, $T1, $t2");
+
+    let instruction = pair.into_inner().next().unwrap();
+    assert_eq!(instruction.as_rule(), Rule::instruction);
+    assert_eq!(
+        instruction.into_inner().next().unwrap().as_rule(),
+        Rule::instruction_alu
+    );
+
+    assert!(parse_instruction_line("ADD $t0, $t1, $t2\nHALT").is_err());
+    assert!(parse_instruction_line("ADD $t0, $t1").is_err());
+}
+
+#[test]
+fn format_messy_source() {
+    let input = "# header comment
+.data   # data section
+  values:   .word 1,2 ,  0x3
+.block 4
+
+
+.instructions
+start:   li $t0,   10   # counter
+  # loop body
+loop:subi $T0,$t0,1
+    sw $t0,-4( $sp )
+
+
+  bnz   $t0 ,loop
+halt
+# trailing comment
+";
+
+    let expected = "# header comment
+.data # data section
+values:
+    .word 1, 2, 0x3
+    .block 4
+
+.instructions
+start:
+    LI $T0, 10 # counter
+    # loop body
+loop:
+    SUBI $T0, $T0, 1
+    SW $T0, -4($SP)
+
+    BNZ $T0, loop
+    HALT
+    # trailing comment
+";
+
+    let formatted = format_source(input).unwrap();
+    assert_eq!(formatted, expected);
+    assert_eq!(format_source(&formatted).unwrap(), expected);
+
+    let (original, _) = assemble(input).unwrap();
+    let (reformatted, _) = assemble(&formatted).unwrap();
+    assert_eq!(original, reformatted);
+
+    assert!(format_source(".instructions\nADD $t0, $t1").is_err());
+}
+
+#[test]
+fn assemble_typed_instructions() {
+    let input = ".data
+.instructions
+    LI $t0, 0
+loop:
+    ADDI $t0, $t0, 1
+    SLTI $t1, $t0, 10
+    BNZ $t1, loop
+    JMP end
+    NOP
+end:
+    HALT";
+
+    let mut labels = SymbolTable::new();
+    labels.insert("loop".to_owned(), 1);
+    labels.insert("end".to_owned(), 6);
+
+    let instrs = [
+        Instruction::Complete(make_i_instruction(
+            Opcode::LI,
+            RegisterId::T0,
+            RegisterId::ZERO,
+            0,
+        )),
+        Instruction::Complete(make_i_instruction(
+            Opcode::ADDI,
+            RegisterId::T0,
+            RegisterId::T0,
+            1,
+        )),
+        Instruction::Complete(make_i_instruction(
+            Opcode::SLTI,
+            RegisterId::T1,
+            RegisterId::T0,
+            10,
+        )),
+        Instruction::Branch {
+            opcode: Opcode::BNZ,
+            rs1: RegisterId::T1,
+            target: Target::Label("loop"),
+        },
+        Instruction::Jump {
+            opcode: Opcode::JMP,
+            target: Target::Label("end"),
+        },
+        Instruction::Complete(make_i_instruction(
+            Opcode::NOP,
+            RegisterId::ZERO,
+            RegisterId::ZERO,
+            0,
+        )),
+        Instruction::Complete(make_i_instruction(
+            Opcode::HALT,
+            RegisterId::ZERO,
+            RegisterId::ZERO,
+            0,
+        )),
+    ];
+
+    let (expected, _) = assemble(input).unwrap();
+    assert_eq!(
+        assemble_instructions_from(&instrs, &labels).unwrap(),
+        expected.instructions()
+    );
+
+    labels.remove("end");
+    assert_eq!(
+        assemble_instructions_from(&instrs, &labels),
+        Err(AssembleError::LabelNotFound {
+            index: 4,
+            label: "end".to_owned()
+        })
+    );
+}
+
+#[test]
+fn warn_stack_misalignment() {
+    let misaligned = ".data
+.instructions
+ADDI $sp, $sp, 3
+SUBI $fp, $sp, 2
+HALT";
+    let aligned = ".data
+.instructions
+ADDI $sp, $sp, 8
+SUBI $fp, $fp, -4
+ADDI $t0, $t0, 3
+HALT";
+    let diagnostics = Diagnostics {
+        stack_alignment: true,
+        ..Default::default()
+    };
+
+    let (_, _, warnings) = assemble_with_diagnostics(misaligned, 0, diagnostics).unwrap();
+    assert_eq!(
+        warnings.iter().map(|w| w.line).collect::<Vec<_>>(),
+        vec![3, 4]
+    );
+
+    let (_, _, warnings) = assemble_with_diagnostics(aligned, 0, diagnostics).unwrap();
+    assert!(warnings.is_empty());
+
+    // The lint is opt-in.
+    let (_, _, warnings) = assemble_with_warnings(misaligned, 0).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn warn_dead_code() {
+    let diagnostics = Diagnostics {
+        dead_code: true,
+        ..Default::default()
+    };
+    let warning_lines = |input: &str| {
+        let (_, _, warnings) = assemble_with_diagnostics(input, 0, diagnostics).unwrap();
+        warnings.iter().map(|w| w.line).collect::<Vec<_>>()
+    };
+
+    let dead = ".data
+.instructions
+HALT
+ADD $t0, $t1, $t2
+ADD $t0, $t1, $t2
+JMP end
+end:
+    HALT";
+    assert_eq!(warning_lines(dead), vec![4]);
+
+    let labeled = ".data
+.instructions
+    HALT
+next:
+    ADD $t0, $t1, $t2
+    HALT";
+    assert!(warning_lines(labeled).is_empty());
+
+    // Targets of numeric offsets are reachable as well.
+    let offset = ".data
+.instructions
+    BEZ $t0, 8
+    HALT
+    ADD $t0, $t1, $t2
+    HALT";
+    assert!(warning_lines(offset).is_empty());
+
+    // The lint is opt-in.
+    let (_, _, warnings) = assemble_with_warnings(dead, 0).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn assemble_from_reader() {
+    let input = ".data
+.block 4
+.instructions
+    LI $t0, 0
+loop:
+    ADDI $t0, $t0, 1
+    SLTI $t1, $t0, 10
+    BNZ $t1, loop
+    HALT";
+
+    let (executable, source_map) = assemble_reader(std::io::Cursor::new(input), 16).unwrap();
+    assert_eq!(
+        (executable, source_map),
+        assemble_addressed(input, 16).unwrap()
+    );
+
+    let invalid = std::io::Cursor::new(".data\n.instructions\nADD $t0");
+    assert!(matches!(
+        assemble_reader(invalid, 0),
+        Err(ReadError::Vasm(_))
+    ));
+
+    let not_utf8 = std::io::Cursor::new(vec![0xFFu8, 0xFE]);
+    assert!(matches!(
+        assemble_reader(not_utf8, 0),
+        Err(ReadError::Io(_))
+    ));
+}
+
+#[test]
+fn mnemonic_table() {
+    let table = mnemonics();
+    let kind_of = |name: &str| {
+        table
+            .iter()
+            .find(|(mnemonic, _)| *mnemonic == name)
+            .map(|(_, kind)| *kind)
+    };
+
+    assert_eq!(kind_of("ADD"), Some(MnemonicKind::Alu));
+    assert_eq!(kind_of("FADD"), Some(MnemonicKind::Flop));
+    assert_eq!(kind_of("PUSH"), Some(MnemonicKind::Shorthand));
+    assert_eq!(kind_of("SLTUI"), Some(MnemonicKind::UnsignedImmediate));
+    assert_eq!(kind_of("ADDX"), None);
+
+    // Every mnemonic in the table is accepted by the grammar with the operands of its kind.
+    for (mnemonic, kind) in table {
+        let operands = match kind {
+            MnemonicKind::Alu | MnemonicKind::Flop => " $t0, $t1, $t2",
+            MnemonicKind::Immediate | MnemonicKind::UnsignedImmediate => " $t0, $t1, 1",
+            MnemonicKind::DestinationSource => " $t0, $t1",
+            MnemonicKind::LoadImmediate | MnemonicKind::SetImmediate => " $t0, 1",
+            MnemonicKind::NoOperands => "",
+            MnemonicKind::Branch => " $t0, 4",
+            MnemonicKind::JumpRegister => " $t0",
+            MnemonicKind::LoadStore => " $t0, 4($t1)",
+            MnemonicKind::Jump => " 4",
+            MnemonicKind::Shorthand => continue,
+        };
+        let line = format!("{}{}", mnemonic, operands);
+        assert!(parse_instruction_line(&line).is_ok(), "{}", line);
+    }
+}
+
+fn error_line(err: &Error) -> usize {
+    match err.line_col {
+        ::pest::error::LineColLocation::Pos((line, _)) => line,
+        ::pest::error::LineColLocation::Span((line, _), _) => line,
+    }
+}
+
+#[test]
+fn assemble_within_limits() {
+    let input = ".data
+.word 1, 2
+.byte 3
+.instructions
+LI $t0, 1
+PUSH $t0
+HALT";
+
+    let limits = Limits {
+        max_data: 9,
+        max_instr: 16,
+    };
+    assert_eq!(
+        assemble_with_limits(input, 0, limits).unwrap(),
+        assemble(input).unwrap()
+    );
+
+    let err = assemble_with_limits(
+        input,
+        0,
+        Limits {
+            max_data: 8,
+            ..limits
+        },
+    )
+    .unwrap_err();
+    assert_eq!(error_line(&err), 3);
+    assert!(err
+        .to_string()
+        .contains("Data exceeds maximum size of 8 bytes"));
+
+    // PUSH expands into two instructions, which together exceed the limit.
+    let err = assemble_with_limits(
+        input,
+        0,
+        Limits {
+            max_instr: 8,
+            ..limits
+        },
+    )
+    .unwrap_err();
+    assert_eq!(error_line(&err), 6);
+}
+
+#[test]
+fn instruction_limit_error_span() {
+    let input = ".data
+.instructions
+LI $t0, 1
+NOP
+PUSH $t0
+HALT";
+
+    // The limit is crossed by the second instruction that PUSH expands into.
+    let err = assemble_with_limits(
+        input,
+        0,
+        Limits {
+            max_instr: 12,
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(error_line(&err), 5);
+    assert!(err
+        .to_string()
+        .contains("Instructions exceed maximum size of 12 bytes (4 instructions, 16 bytes)"));
+
+    let err = assemble_with_limits(
+        input,
+        0,
+        Limits {
+            max_instr: 16,
+            ..Default::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(error_line(&err), 6);
+    assert!(err.to_string().contains("(5 instructions, 20 bytes)"));
+}
+
+#[test]
+fn unsigned_float_conversions() {
+    let input = ".data
+.instructions
+UTOF $t0, $t1
+ftou $t2, $t0";
+
+    let expected = instructions_from_words(&[
+        make_i_instruction(Opcode::UTOF, RegisterId::T0, RegisterId::T1, 0),
+        make_i_instruction(Opcode::FTOU, RegisterId::T2, RegisterId::T0, 0),
+    ]);
+
+    let (executable, _) = assemble(input).unwrap();
+    assert_eq!(executable.instructions(), &expected[..]);
+}
+
+#[test]
+fn entry_directive() {
+    let input = ".data
+.block 8
+.instructions
+.entry main
+init:
+    LI $t0, 1
+    SW $t0, 0($zero)
+main:
+    LI $t0, 2
+    SW $t0, 4($zero)
+    HALT";
+
+    let (executable, source_map) = assemble(input).unwrap();
+    assert_eq!(executable.entry_point(), 2 * WORD_BYTES);
+    assert_eq!(source_map.len(), 5);
+    assert_eq!(source_map[0].start_line, 5);
+
+    let (exit_code, memory) = vex::run_executable(&executable, 8);
+    assert_eq!(exit_code, ExitCode::Halted);
+    assert_eq!(memory, [0, 0, 0, 0, 2, 0, 0, 0]);
+
+    let (default_entry, _) = assemble(".data\n.instructions\nHALT").unwrap();
+    assert_eq!(default_entry.entry_point(), 0);
+
+    assert!(assemble(".data\n.instructions\n.entry missing\nHALT").is_err());
+
+    assert!(format_source(input)
+        .unwrap()
+        .contains(".instructions\n    .entry main\ninit:\n"));
+}
+
+#[test]
+fn long_branches() {
+    // The NOPs put `far` and `back` out of the range of a branch immediate.
+    let nops = "NOP\n".repeat(9000);
+    let input = format!(
+        ".data
+.instructions
+back:
+    LI $t0, 1
+    BEZ $zero, far
+    SW $t0, 0($zero)
+    HALT
+{}far:
+    BNZ $t0, taken
+    HALT
+taken:
+    SW $t0, 4($zero)
+    BNZ $zero, back
+    HALT",
+        nops
+    );
+
+    let (executable, source_map) = assemble(&input).unwrap();
+    assert_eq!(executable.instructions().len() / WORD_BYTES as usize, 9011);
+    assert_eq!(source_map.len(), 9011);
+    assert_eq!(source_map[1].start_line, 5);
+    assert_eq!(source_map[2].start_line, 5);
+
+    let mut memory = vec![0u8; 8];
+    let mut processor = Processor::new();
+    assert_eq!(
+        processor.run(executable.instructions(), &mut memory),
+        ExitCode::Halted
+    );
+    assert_eq!(memory, [0, 0, 0, 0, 1, 0, 0, 0]);
+}
+
+#[test]
+fn multiple_sections() {
+    let input = ".data
+first: .word 1
+.instructions
+main:
+    LDA $t0, second
+    LW $t1, 0($t0)
+    JMP tail
+.data
+second: .word 2
+.instructions
+tail:
+    LDA $t2, first
+    LW $t2, 0($t2)
+    ADD $t1, $t1, $t2
+    SW $t1, 0($t0)
+    HALT";
+
+    let (executable, source_map, info) = assemble_with_info(input, 0, 0).unwrap();
+    assert_eq!(executable.data(), &[1, 0, 0, 0, 2, 0, 0, 0]);
+    assert_eq!(info.data_labels["second"], 4);
+    assert_eq!(source_map.last().unwrap().start_line, 16);
+
+    let (exit_code, memory) = vex::run_executable(&executable, 8);
+    assert_eq!(exit_code, ExitCode::Halted);
+    assert_eq!(memory, [1, 0, 0, 0, 3, 0, 0, 0]);
+
+    assert!(assemble(".instructions\nHALT\n.data").is_err());
+    assert!(
+        assemble(".data\n.instructions\n.entry a\na: NOP\n.instructions\n.entry a\nHALT").is_err()
+    );
+}
+
+#[test]
+fn assemble_loop_disassembly() {
+    let input = ".data
+.block 128
+.instructions
+loop: SLTI $t2, $t0, 32
+      BEZ  $t2, end
+      SLLI $t1, $t0, 2
+      SW   $t0, 0($t1)
+      ADDI $t0, $t0, 1
+      JMP loop
+end:  HALT";
+
+    let (executable, _) = assemble(input).unwrap();
+
+    vex::testing::assert_disassembles_to(
+        &executable,
+        &[
+            "SLTI $t2, $t0, 32",
+            "BEZ $t2, 20",
+            "SLLI $t1, $t0, 2",
+            "SW $t0, 0($t1)",
+            "ADDI $t0, $t0, 1",
+            "JMP -20",
+            "HALT",
+        ],
+    );
+}
+
+#[test]
+#[should_panic(expected = "- expected, + actual")]
+fn assemble_loop_disassembly_mismatch() {
+    let (executable, _) = assemble(".data\n.instructions\nNOP\nHALT").unwrap();
+    vex::testing::assert_disassembles_to(&executable, &["NOP"]);
+}
+
+#[test]
+fn negative_unsigned_values() {
+    let (executable, _) = assemble(".data\n.byte -1\n.instructions\nHALT").unwrap();
+    assert_eq!(executable.data(), &[0xFF]);
+
+    let err = assemble(".data\n.block -1\n.instructions\nHALT").unwrap_err();
+    assert_eq!(error_line(&err), 2);
+    assert!(err
+        .to_string()
+        .contains("Expected unsigned value, found negative value"));
+
+    let err = assemble(".data\n.instructions\nSLTUI $t0, $t1, -1\nHALT").unwrap_err();
+    assert_eq!(error_line(&err), 3);
+    assert!(err.to_string().contains("Expected unsigned value"));
+
+    assert!(assemble(".data\n.instructions\nSLTI $t0, $t1, -1\nHALT").is_ok());
+}
+
+#[test]
+fn analyze_loop() {
+    let input = ".data
+.block 128
+.instructions
+loop: SLTI $t2, $t0, 32
+      BEZ  $t2, end
+      SLLI $t1, $t0, 2
+      SW   $t0, 0($t1)
+      ADDI $t0, $t0, 1
+      JMP loop
+end:  HALT";
+
+    assert_eq!(
+        analyze(input).unwrap(),
+        ProgramStats {
+            instruction_count: 7,
+            data_size: 128,
+            label_count: 2,
+            warnings: vec![],
+        }
+    );
+
+    let err = analyze(".data\n.instructions\nADD $t0, $t1\nHALT").unwrap_err();
+    assert_eq!(error_line(&err), 3);
+
+    let err = analyze(".data\n.instructions\n.entry a\na: NOP\n.entry a\nHALT").unwrap_err();
+    assert_eq!(error_line(&err), 5);
+}
+
+#[test]
+fn assemble_li32() {
+    let input = ".data
+.instructions
+LI32 $t0, 0x12345678
+LI32 $t1, -2
+LI32 $t2, 0x8000
+HALT";
+
+    let (executable, source_map) = assemble(input).unwrap();
+
+    assert_eq!(
+        executable.instructions(),
+        &transmute_vec(vec![
+            instr_i!(SLO, T0, ZERO, 0x5678),
+            instr_i!(SHI, T0, ZERO, 0x1234),
+            instr_i!(LI, T1, ZERO, -2),
+            instr_i!(SLO, T2, ZERO, 0x8000u16 as i16),
+            instr_i!(SHI, T2, ZERO, 0),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ])[..]
+    );
+    assert_eq!(source_map[2].start_line, 4);
+
+    assert!(format_source(input)
+        .unwrap()
+        .contains("    LI32 $T0, 0x12345678\n"));
+}
+
+#[test]
+fn rept_block() {
+    let input = ".data
+.instructions
+.rept 3
+    NOP
+.endr
+.rept 2
+    ADDI $t0, $t0, 1
+    .rept 0
+        NOP
+    .endr
+.endr
+HALT";
+
+    let (executable, source_map) = assemble(input).unwrap();
+
+    assert_eq!(
+        executable.instructions(),
+        &transmute_vec(vec![
+            instr_i!(NOP, ZERO, ZERO, 0),
+            instr_i!(NOP, ZERO, ZERO, 0),
+            instr_i!(NOP, ZERO, ZERO, 0),
+            instr_i!(ADDI, T0, T0, 1),
+            instr_i!(ADDI, T0, T0, 1),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ])[..]
+    );
+    assert_eq!(
+        source_map.iter().map(|i| i.start_line).collect::<Vec<_>>(),
+        vec![4, 4, 4, 7, 7, 12]
+    );
+
+    assert!(format_source(input)
+        .unwrap()
+        .contains("    .rept 3\n    NOP\n    .endr\n"));
+
+    let err = assemble(".data\n.instructions\n.rept 2\nl: NOP\n.endr").unwrap_err();
+    assert_eq!(error_line(&err), 4);
+    let limits = Limits {
+        max_data: 0,
+        max_instr: 16,
+    };
+    let input = ".data\n.instructions\n.rept 4294967295\nNOP\n.endr";
+    assert!(assemble_with_limits(input, 0, limits).is_err());
+}
+
+#[test]
+fn assemble_loop_to_instructions() {
+    let input = ".data
+.block 128
+.instructions
+loop: SLTI $t2, $t0, 32
+      BEZ  $t2, end
+      SLLI $t1, $t0, 2
+      SW   $t0, 0($t1)
+      ADDI $t0, $t0, 1
+      JMP loop
+end:  HALT";
+
+    let (instructions, data, entry_point, source_map) = assemble_to_instructions(input, 0).unwrap();
+
+    assert_eq!(instructions.len(), 7);
+    assert_eq!(
+        instructions[1],
+        Instruction::Branch {
+            opcode: Opcode::BEZ,
+            rs1: RegisterId::T2,
+            target: Target::Relative(jmp_addr_i16(5)),
+        }
+    );
+    assert_eq!(
+        instructions[5],
+        Instruction::Jump {
+            opcode: Opcode::JMP,
+            target: Target::Relative(jmp_addr_i32(-5)),
+        }
+    );
+    assert_eq!(
+        instructions[0],
+        Instruction::Complete(instr_i!(SLTI, T2, T0, 32))
+    );
+    assert_eq!(data, vec![0; 128]);
+    assert_eq!(entry_point, 0);
+
+    let (executable, expected_source_map) = assemble(input).unwrap();
+    assert_eq!(source_map, expected_source_map);
+    assert_eq!(
+        assemble_instructions_from(&instructions, &SymbolTable::new()).unwrap(),
+        executable.instructions()
+    );
+
+    let (_, _, entry_point, _) =
+        assemble_to_instructions(".data\n.instructions\n.entry main\nNOP\nmain: HALT", 0).unwrap();
+    assert_eq!(entry_point, WORD_BYTES);
+}
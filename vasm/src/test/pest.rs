@@ -94,13 +94,13 @@ fn data_block() {
                 uint(9, 13, [ hex_uint(9, 13, [ hex_lit(11, 13) ]) ])
         ]) ]
     };
-    fails_with! {
+    parses_to! {
         parser: VASMParser,
         input: ".block -45",
         rule: Rule::data_block,
-        positives: vec![Rule::uint],
-        negatives: vec![],
-        pos: 7
+        tokens: [ data_block(0, 10, [
+                uint(7, 10, [ negative_dec_int(7, 10) ])
+        ]) ]
     };
     fails_with! {
         parser: VASMParser,
@@ -213,20 +213,25 @@ fn register() {
         tokens: [ register(0, 3, [register_id(1, 3)]) ]
     };
 
-    fails_with! {
+    parses_to! {
         parser: VASMParser,
         input: "$bla",
         rule: Rule::register,
-        positives: vec![Rule::register_id],
-        negatives: vec![],
-        pos: 1
+        tokens: [ register(0, 4, [register_alias(1, 4, [identifier(1, 4)])]) ]
+    };
+
+    parses_to! {
+        parser: VASMParser,
+        input: "$t0_x",
+        rule: Rule::register,
+        tokens: [ register(0, 5, [register_alias(1, 5, [identifier(1, 5)])]) ]
     };
 
     fails_with! {
         parser: VASMParser,
         input: "$ t0",
         rule: Rule::register,
-        positives: vec![Rule::register_id],
+        positives: vec![Rule::identifier, Rule::register_id],
         negatives: vec![],
         pos: 1
     };
@@ -245,6 +250,17 @@ fn instruction_alu() {
             register(15, 18, [ register_id(16, 18) ])
         ]) ]
     };
+    parses_to! {
+        parser: VASMParser,
+        input: "sltu $t0, $t1, $t2",
+        rule: Rule::instruction_alu,
+        tokens: [ instruction_alu(0, 18, [
+            mnemonic_alu(0, 4),
+            register(5, 8, [ register_id(6, 8) ]),
+            register(10, 13, [ register_id(11, 13) ]),
+            register(15, 18, [ register_id(16, 18) ])
+        ]) ]
+    };
 }
 
 #[test]
@@ -260,6 +276,17 @@ fn instruction_flop() {
             register(14, 17, [ register_id(15, 17) ])
         ]) ]
     };
+    parses_to! {
+        parser: VASMParser,
+        input: "fmod $t0, $t1, $t2",
+        rule: Rule::instruction_flop,
+        tokens: [ instruction_flop(0, 18, [
+            mnemonic_flop(0, 4),
+            register(5, 8, [ register_id(6, 8) ]),
+            register(10, 13, [ register_id(11, 13) ]),
+            register(15, 18, [ register_id(16, 18) ])
+        ]) ]
+    };
 }
 
 #[test]
@@ -425,7 +452,18 @@ fn instruction_push() {
         input: "PUSH  $RA ",
         rule: Rule::instruction_push,
         tokens: [ instruction_push(0, 9, [
-            register(6, 9, [ register_id(7, 9) ])
+            register_list(6, 9, [ register(6, 9, [ register_id(7, 9) ]) ])
+        ])]
+    }
+    parses_to! {
+        parser: VASMParser,
+        input: "PUSH $T0 ,$T1",
+        rule: Rule::instruction_push,
+        tokens: [ instruction_push(0, 13, [
+            register_list(5, 13, [
+                register(5, 8, [ register_id(6, 8) ]),
+                register(10, 13, [ register_id(11, 13) ])
+            ])
         ])]
     }
 }
@@ -437,7 +475,7 @@ fn instruction_pop() {
         input: "POP $V0",
         rule: Rule::instruction_pop,
         tokens: [ instruction_pop(0, 7, [
-            register(4, 7, [ register_id(5, 7) ])
+            register_list(4, 7, [ register(4, 7, [ register_id(5, 7) ]) ])
         ])]
     }
 }
@@ -481,6 +519,28 @@ fn instruction_lia() {
     };
 }
 
+#[test]
+fn instruction_call() {
+    parses_to! {
+        parser: VASMParser,
+        input: "call my_func",
+        rule: Rule::instruction_call,
+        tokens: [ instruction_call(0, 12, [
+            jump_target(5, 12, [ identifier(5, 12) ])
+        ]) ]
+    };
+}
+
+#[test]
+fn instruction_ret() {
+    parses_to! {
+        parser: VASMParser,
+        input: "RET",
+        rule: Rule::instruction_ret,
+        tokens: [ instruction_ret(0, 3) ]
+    };
+}
+
 #[test]
 fn labeled_instruction() {
     parses_to! {
@@ -0,0 +1,15 @@
+use std::fmt;
+
+/// A diagnostic about a program which assembled successfully, but probably doesn't behave as intended.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Warning {
+    /// The line the warning refers to.
+    pub line: u32,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "warning (line {}): {}", self.line, self.message)
+    }
+}
@@ -1,12 +1,13 @@
 use crate::result::VcpuResult;
 use crate::source_map::SourceMap;
-use crate::util::{destroy, into_ptr};
+use crate::util::{destroy, into_ptr, last_error_ptr, set_last_error, utf16_column, utf16_offset};
+use pest::error::{InputLocation, LineColLocation};
 use std::os::raw::c_char;
 use vasm::assemble_addressed;
 use vex::{Executable, ReadVexExt, WriteVexExt};
 
 use std::cell::Cell;
-use std::ffi::{CStr, CString};
+use std::ffi::CStr;
 use std::slice;
 
 #[no_mangle]
@@ -32,12 +33,22 @@ pub unsafe extern "C" fn vcpu_executable_assemble(
                 VcpuResult::Ok
             }
             Err(err) => {
+                let location = match err.line_col {
+                    LineColLocation::Pos(pos) => pos,
+                    LineColLocation::Span(start, _) => start,
+                };
+                LAST_ERROR_LOCATION.with(|f| f.set(Some(location)));
+
+                let position = match err.location {
+                    InputLocation::Pos(pos) => pos,
+                    InputLocation::Span((start, _)) => start,
+                };
+                let utf16_location = (utf16_column(src, position), utf16_offset(src, position));
+                LAST_ERROR_UTF16_LOCATION.with(|f| f.set(Some(utf16_location)));
+
                 if !error.is_null() {
-                    LAST_ERROR.with(|f| {
-                        let err_str = CString::new(format!("{}", err)).unwrap_or_default();
-                        *error = err_str.as_ptr();
-                        f.set(err_str);
-                    });
+                    set_last_error(format!("{}", err));
+                    *error = last_error_ptr();
                 }
                 VcpuResult::AssemblerError
             }
@@ -48,7 +59,57 @@ pub unsafe extern "C" fn vcpu_executable_assemble(
 }
 
 thread_local! {
-    static LAST_ERROR: Cell<CString> = Cell::new(Default::default());
+    static LAST_ERROR_LOCATION: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+    static LAST_ERROR_UTF16_LOCATION: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+/// Retrieves the (1-based) line and column of the last error reported by `vcpu_executable_assemble`
+/// on the current thread. Null output pointers are ignored.
+///
+/// Returns `OutOfRange` if no assembler error has occurred yet.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_get_last_error_location(
+    line: *mut u32,
+    column: *mut u32,
+) -> VcpuResult {
+    match LAST_ERROR_LOCATION.with(|f| f.get()) {
+        Some((l, c)) => {
+            if !line.is_null() {
+                *line = l as u32;
+            }
+            if !column.is_null() {
+                *column = c as u32;
+            }
+            VcpuResult::Ok
+        }
+        None => VcpuResult::OutOfRange,
+    }
+}
+
+/// Retrieves the location of the last error reported by `vcpu_executable_assemble` on the
+/// current thread in UTF-16 code units, as used by many editor protocols.
+///
+/// `column` is 1-based like in `vcpu_get_last_error_location`, `offset` is the 0-based offset
+/// from the start of the source. Null output pointers are ignored.
+///
+/// Returns `OutOfRange` if no assembler error has occurred yet.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_get_last_error_location_utf16(
+    column: *mut u32,
+    offset: *mut u32,
+) -> VcpuResult {
+    match LAST_ERROR_UTF16_LOCATION.with(|f| f.get()) {
+        Some((c, o)) => {
+            if !column.is_null() {
+                *column = c as u32;
+            }
+            if !offset.is_null() {
+                *offset = o as u32;
+            }
+            VcpuResult::Ok
+        }
+        None => VcpuResult::OutOfRange,
+    }
 }
 
 #[no_mangle]
@@ -71,6 +132,13 @@ pub unsafe extern "C" fn vcpu_executable_get_data_offset(executable: *const Exec
     (*executable).data_offset()
 }
 
+/// Returns the offset into the instructions at which execution begins
+/// (see `vcpu_processor_set_program_counter`).
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_executable_get_entry_point(executable: *const Executable) -> u32 {
+    (*executable).entry_point()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_executable_get_instructions(
     executable: *const Executable,
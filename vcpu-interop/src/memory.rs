@@ -1,11 +1,12 @@
 use crate::result::VcpuResult;
-use crate::util::{destroy, into_ptr};
+use crate::util::{destroy, into_ptr, set_last_fragment_key};
 use std::cell::RefCell;
 use std::ffi::{c_void, CStr};
 use std::ops::{Deref, DerefMut};
 use std::os::raw::c_char;
 use std::rc::Rc;
-use vcpu::{CompositeMemory, IOHandler, IOMemory, MountError, Storage, StorageMut};
+use vcpu::{CompositeMemory, FifoDevice, IOHandler, IOMemory, MountError, Storage, StorageMut};
+use vex::Executable;
 
 pub type CanWriteCallback = extern "C" fn(
     data: *const u8,
@@ -39,10 +40,63 @@ impl IOHandler for FunPtrIOHandler {
     }
 }
 
+pub type ReadCallback =
+    extern "C" fn(address: u32, size: u32, value: *mut u32, user_data: *mut c_void) -> bool;
+
+pub type WriteCallback =
+    extern "C" fn(address: u32, size: u32, value: u32, user_data: *mut c_void) -> bool;
+
+/// Storage whose accesses are forwarded to the host.
+///
+/// The callbacks are only invoked for accesses within the length of the storage
+/// and return `false` if the access failed.
+pub struct FunPtrStorage {
+    length: u32,
+    read_fn: ReadCallback,
+    write_fn: WriteCallback,
+    user_data: *mut c_void,
+}
+
+impl Storage for FunPtrStorage {
+    fn length(&self) -> u32 {
+        self.length
+    }
+
+    fn check_range(&self, address: u32, length: u32) -> bool {
+        address
+            .checked_add(length)
+            .is_some_and(|end| end <= self.length)
+    }
+
+    fn read(&self, address: u32, size: u32) -> Result<u32, ()> {
+        let mut value = 0;
+        if self.check_range(address, size)
+            && (self.read_fn)(address, size, &mut value, self.user_data)
+        {
+            Ok(value)
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl StorageMut for FunPtrStorage {
+    fn write(&mut self, address: u32, size: u32, value: u32) -> Result<(), ()> {
+        if self.check_range(address, size) && (self.write_fn)(address, size, value, self.user_data)
+        {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+}
+
 pub enum MemoryVariant {
     Plain(Vec<u8>),
     IO(IOMemory<FunPtrIOHandler>),
     Composite(CompositeMemory),
+    Fifo(FifoDevice),
+    Custom(Box<dyn StorageMut>),
 }
 
 pub struct Memory(Rc<RefCell<MemoryVariant>>);
@@ -80,6 +134,8 @@ impl Storage for Memory {
                 MemoryVariant::Plain(inner) => inner.length(),
                 MemoryVariant::IO(inner) => inner.length(),
                 MemoryVariant::Composite(inner) => inner.length(),
+                MemoryVariant::Fifo(inner) => inner.length(),
+                MemoryVariant::Custom(inner) => inner.length(),
             },
             Err(_) => 0,
         }
@@ -91,6 +147,8 @@ impl Storage for Memory {
                 MemoryVariant::Plain(inner) => inner.check_range(address, length),
                 MemoryVariant::IO(inner) => inner.check_range(address, length),
                 MemoryVariant::Composite(inner) => inner.check_range(address, length),
+                MemoryVariant::Fifo(inner) => inner.check_range(address, length),
+                MemoryVariant::Custom(inner) => inner.check_range(address, length),
             },
             Err(_) => false,
         }
@@ -101,6 +159,8 @@ impl Storage for Memory {
             MemoryVariant::Plain(inner) => inner.read(address, size),
             MemoryVariant::IO(inner) => inner.read(address, size),
             MemoryVariant::Composite(inner) => inner.read(address, size),
+            MemoryVariant::Fifo(inner) => inner.read(address, size),
+            MemoryVariant::Custom(inner) => inner.read(address, size),
         }
     }
 }
@@ -111,6 +171,20 @@ impl StorageMut for Memory {
             MemoryVariant::Plain(inner) => inner.write(address, size, value),
             MemoryVariant::IO(inner) => inner.write(address, size, value),
             MemoryVariant::Composite(inner) => inner.write(address, size, value),
+            MemoryVariant::Fifo(inner) => inner.write(address, size, value),
+            MemoryVariant::Custom(inner) => inner.write(address, size, value),
+        }
+    }
+
+    fn zero_fill(&mut self) {
+        if let Ok(mut reference) = self.0.try_borrow_mut() {
+            match reference.deref_mut() {
+                MemoryVariant::Plain(inner) => inner.zero_fill(),
+                MemoryVariant::IO(inner) => inner.zero_fill(),
+                MemoryVariant::Composite(inner) => inner.zero_fill(),
+                MemoryVariant::Fifo(inner) => inner.zero_fill(),
+                MemoryVariant::Custom(inner) => inner.zero_fill(),
+            }
         }
     }
 }
@@ -137,6 +211,27 @@ pub unsafe extern "C" fn vcpu_memory_create_io(
     ))))
 }
 
+/// Creates a memory of `length` bytes whose reads and writes are handled by `read` and `write`.
+///
+/// This allows devices to be implemented by the host. Like IO memories, custom memories are
+/// usually mounted into a composite memory.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_memory_create_custom(
+    length: u32,
+    read: ReadCallback,
+    write: WriteCallback,
+    user_data: *mut c_void,
+) -> *mut Memory {
+    into_ptr(Memory::new(MemoryVariant::Custom(Box::new(
+        FunPtrStorage {
+            length,
+            read_fn: read,
+            write_fn: write,
+            user_data,
+        },
+    ))))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_memory_get_ptr(
     memory: *mut Memory,
@@ -159,6 +254,15 @@ pub unsafe extern "C" fn vcpu_memory_get_ptr(
     })
 }
 
+/// Returns the length of the memory, i.e. one past its highest valid address.
+///
+/// Works for all kinds of memory. For composite memory, this is the end of the fragment at the
+/// highest address. Returns `0` while the memory is in use.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_memory_get_length(memory: *const Memory) -> u32 {
+    (*memory).length()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_memory_read(
     memory: *const Memory,
@@ -211,6 +315,45 @@ pub unsafe extern "C" fn vcpu_memory_write(
     })
 }
 
+/// Copies the data of `executable` into `memory` at the executable's data offset.
+///
+/// Like `vcpu_memory_write`, this bypasses the handler of IO memories.
+/// For composite memories, the data must fit into a single fragment.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_memory_load_data(
+    memory: *mut Memory,
+    executable: *const Executable,
+) -> VcpuResult {
+    let data = (*executable).data();
+    let offset = (*executable).data_offset();
+    let length = data.len() as u32;
+
+    if data.is_empty() {
+        return VcpuResult::Ok;
+    }
+
+    (*memory).try_use_mut(|variant| {
+        let result = match variant {
+            MemoryVariant::Plain(inner) => inner.borrow_slice_mut(offset, length),
+            MemoryVariant::IO(inner) => inner
+                .data_mut()
+                .get_mut(offset as usize..offset as usize + data.len())
+                .ok_or(()),
+            MemoryVariant::Composite(inner) => inner.borrow_slice_mut(offset, length),
+            MemoryVariant::Fifo(inner) => inner.borrow_slice_mut(offset, length),
+            MemoryVariant::Custom(inner) => inner.borrow_slice_mut(offset, length),
+        };
+
+        match result {
+            Ok(slice) => {
+                slice.copy_from_slice(data);
+                VcpuResult::Ok
+            }
+            Err(_) => VcpuResult::OutOfRange,
+        }
+    })
+}
+
 unsafe fn memory_get(
     memory: *const Memory,
     address: u32,
@@ -222,6 +365,8 @@ unsafe fn memory_get(
             MemoryVariant::Plain(inner) => inner.read(address, size),
             MemoryVariant::IO(inner) => inner.read(address, size),
             MemoryVariant::Composite(inner) => inner.read(address, size),
+            MemoryVariant::Fifo(inner) => inner.read(address, size),
+            MemoryVariant::Custom(inner) => inner.read(address, size),
         };
 
         match result {
@@ -273,6 +418,8 @@ unsafe fn memory_set(memory: *mut Memory, address: u32, size: u32, value: u32) -
             MemoryVariant::Plain(inner) => inner.write(address, size, value),
             MemoryVariant::IO(inner) => inner.write(address, size, value),
             MemoryVariant::Composite(inner) => inner.write(address, size, value),
+            MemoryVariant::Fifo(inner) => inner.write(address, size, value),
+            MemoryVariant::Custom(inner) => inner.write(address, size, value),
         };
 
         match result {
@@ -309,6 +456,53 @@ pub unsafe extern "C" fn vcpu_memory_set_byte(
     memory_set(memory, address, vcpu::BYTE_BYTES, value.into())
 }
 
+/// Writes `value` to an IO memory without invoking its handler.
+///
+/// `size` is the number of bytes to write (1 to 4).
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_memory_io_poke(
+    memory: *mut Memory,
+    address: u32,
+    size: u32,
+    value: u32,
+) -> VcpuResult {
+    if !(1..=4).contains(&size) {
+        return VcpuResult::OutOfRange;
+    }
+
+    (*memory).try_use_mut(|variant| match variant {
+        MemoryVariant::IO(inner) => match inner.poke(address, size, value) {
+            Ok(_) => VcpuResult::Ok,
+            Err(_) => VcpuResult::OutOfRange,
+        },
+        _ => VcpuResult::InvalidType,
+    })
+}
+
+/// Reads `size` bytes (1 to 4) from an IO memory.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_memory_io_peek(
+    memory: *const Memory,
+    address: u32,
+    size: u32,
+    value: *mut u32,
+) -> VcpuResult {
+    if !(1..=4).contains(&size) {
+        return VcpuResult::OutOfRange;
+    }
+
+    (*memory).try_use(|variant| match variant {
+        MemoryVariant::IO(inner) => match inner.peek(address, size) {
+            Ok(v) => {
+                *value = v;
+                VcpuResult::Ok
+            }
+            Err(_) => VcpuResult::OutOfRange,
+        },
+        _ => VcpuResult::InvalidType,
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_memory_resize(memory: *mut Memory, size: u32) -> VcpuResult {
     (*memory).try_use_mut(|variant| match variant {
@@ -324,6 +518,11 @@ pub unsafe extern "C" fn vcpu_memory_resize(memory: *mut Memory, size: u32) -> V
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_memory_create_fifo(capacity: u32) -> *mut Memory {
+    into_ptr(Memory::new(MemoryVariant::Fifo(FifoDevice::new(capacity))))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_memory_create_comp() -> *mut Memory {
     into_ptr(Memory::new(
@@ -347,6 +546,8 @@ pub unsafe extern "C" fn vcpu_memory_comp_mount(
                     Ok(_) => VcpuResult::Ok,
                     Err(MountError::FragmentIntersection) => VcpuResult::FragmentIntersection,
                     Err(MountError::KeyAlreadyExists) => VcpuResult::KeyAlreadyExists,
+                    // Only returned when mounting aliases.
+                    Err(MountError::TargetNotFound) => VcpuResult::UnknownError,
                 }
             }
             Err(_) => VcpuResult::UTF8Error,
@@ -372,6 +573,56 @@ pub unsafe extern "C" fn vcpu_memory_comp_unmount(
     })
 }
 
+/// Writes the number of fragments mounted in a composite memory to `out_count`.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_memory_comp_fragment_count(
+    memory: *const Memory,
+    out_count: *mut u32,
+) -> VcpuResult {
+    (*memory).try_use(|variant| match variant {
+        MemoryVariant::Composite(inner) => {
+            *out_count = inner.iter().count() as u32;
+            VcpuResult::Ok
+        }
+        _ => VcpuResult::InvalidType,
+    })
+}
+
+/// Retrieves the address, length and key of the fragment at `index`, in ascending order of
+/// address. Null output pointers are ignored.
+///
+/// The key pointer stays valid until the next call to this function on the same thread.
+///
+/// Returns `OutOfRange` if `index` is not less than the number of fragments.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_memory_comp_fragment_info(
+    memory: *const Memory,
+    index: u32,
+    out_addr: *mut u32,
+    out_len: *mut u32,
+    out_key: *mut *const c_char,
+) -> VcpuResult {
+    (*memory).try_use(|variant| match variant {
+        MemoryVariant::Composite(inner) => match inner.iter().nth(index as usize) {
+            Some((address, fragment)) => {
+                if !out_addr.is_null() {
+                    *out_addr = address;
+                }
+                if !out_len.is_null() {
+                    *out_len = fragment.length();
+                }
+                if !out_key.is_null() {
+                    *out_key =
+                        set_last_fragment_key(inner.fragment_key(index as usize).unwrap_or(""));
+                }
+                VcpuResult::Ok
+            }
+            None => VcpuResult::OutOfRange,
+        },
+        _ => VcpuResult::InvalidType,
+    })
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_memory_destroy(memory: *mut Memory) {
     destroy(memory)
@@ -1,9 +1,10 @@
 use crate::memory::{Memory, MemoryVariant};
 use crate::result::VcpuResult;
-use crate::util::{destroy, into_ptr};
+use crate::util::{destroy, into_ptr, set_last_disassembly};
 use num_traits::{FromPrimitive, ToPrimitive};
-use std::slice;
-use vcpu::Processor;
+use std::os::raw::c_char;
+use std::{ptr, slice};
+use vcpu::{ExitCode, Processor};
 
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_processor_create() -> *mut Processor {
@@ -45,6 +46,22 @@ pub unsafe extern "C" fn vcpu_processor_get_program_counter(processor: *const Pr
     (*processor).program_counter()
 }
 
+/// Sets the program counter, e.g. to the entry point of an executable before running it.
+///
+/// Returns `OutOfRange` if `pc` is not word-aligned or lies outside of the `instr_len` bytes
+/// of instructions.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_processor_set_program_counter(
+    processor: *mut Processor,
+    pc: u32,
+    instr_len: usize,
+) -> VcpuResult {
+    match (*processor).set_program_counter(pc, Some(instr_len as u32)) {
+        Ok(()) => VcpuResult::Ok,
+        Err(_) => VcpuResult::OutOfRange,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_processor_get_state(processor: *const Processor) -> i32 {
     match (*processor).state() {
@@ -53,6 +70,47 @@ pub unsafe extern "C" fn vcpu_processor_get_state(processor: *const Processor) -
     }
 }
 
+/// Describes the instruction which stopped the processor with a fault: its address, the raw
+/// instruction word and its disassembly.
+///
+/// The disassembly stays valid until the next call of this function on the same thread. For words
+/// which cannot be decoded (`InvalidOpcode`), it contains a description of the decode error.
+/// Each of the output pointers may be null.
+///
+/// Returns `InvalidType` if the processor is not stopped by a fault of an instruction, i.e. if it
+/// is still running, halted normally or stopped with `BadProgramCounter`.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_processor_get_fault_info(
+    processor: *const Processor,
+    out_pc: *mut u32,
+    out_word: *mut u32,
+    out_disasm: *mut *const c_char,
+) -> VcpuResult {
+    let processor = &*processor;
+    let word = match processor.state() {
+        None | Some(ExitCode::Halted) | Some(ExitCode::BadProgramCounter) => None,
+        Some(_) => processor.last_instruction(),
+    };
+
+    match word {
+        Some(word) => {
+            if !out_pc.is_null() {
+                *out_pc = processor.program_counter();
+            }
+            if !out_word.is_null() {
+                *out_word = word;
+            }
+            if !out_disasm.is_null() {
+                *out_disasm = set_last_disassembly(
+                    vcpu::disassemble(word).unwrap_or_else(|err| format!("{:?}", err)),
+                );
+            }
+            VcpuResult::Ok
+        }
+        None => VcpuResult::InvalidType,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_processor_destroy(processor: *mut Processor) {
     destroy(processor)
@@ -72,32 +130,128 @@ pub unsafe extern "C" fn vcpu_processor_tick(
                 MemoryVariant::Plain(inner) => inner,
                 MemoryVariant::IO(inner) => inner,
                 MemoryVariant::Composite(inner) => inner,
+                MemoryVariant::Fifo(inner) => inner,
+                MemoryVariant::Custom(inner) => inner.as_mut(),
             },
         );
         VcpuResult::Ok
     })
 }
 
+/// Runs the processor until it stops or hits a breakpoint.
+///
+/// If a breakpoint was hit, `BreakpointHit` is returned and, if `stopped_pc` is not null,
+/// the address of the breakpoint is written to it.
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_processor_run(
     processor: *mut Processor,
     instr: *const u8,
     instr_len: usize,
     memory: *mut Memory,
+    stopped_pc: *mut u32,
+) -> VcpuResult {
+    vcpu_processor_run_ex(
+        processor,
+        instr,
+        instr_len,
+        memory,
+        stopped_pc,
+        ptr::null_mut(),
+    )
+}
+
+/// Like `vcpu_processor_run`, but additionally writes the exit code to `out_exit_code`
+/// (if it is not null), or `-1` if a breakpoint was hit.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_processor_run_ex(
+    processor: *mut Processor,
+    instr: *const u8,
+    instr_len: usize,
+    memory: *mut Memory,
+    stopped_pc: *mut u32,
+    out_exit_code: *mut i32,
 ) -> VcpuResult {
     (*memory).try_use_mut(|variant| {
-        (*processor).run(
+        let exit_code = (*processor).run_to_breakpoint(
             slice::from_raw_parts(instr, instr_len),
             match variant {
                 MemoryVariant::Plain(inner) => inner,
                 MemoryVariant::IO(inner) => inner,
                 MemoryVariant::Composite(inner) => inner,
+                MemoryVariant::Fifo(inner) => inner,
+                MemoryVariant::Custom(inner) => inner.as_mut(),
             },
         );
+
+        if !out_exit_code.is_null() {
+            *out_exit_code = exit_code.map_or(-1, |code| code.to_i32().unwrap());
+        }
+
+        if exit_code.is_some() {
+            VcpuResult::Ok
+        } else {
+            if !stopped_pc.is_null() {
+                *stopped_pc = (*processor).program_counter();
+            }
+            VcpuResult::BreakpointHit
+        }
+    })
+}
+
+/// Runs the processor until it stops, but executes at most `max_ticks` instructions.
+///
+/// Writes the exit code to `out_exit` (if not null), or `-1` if the processor is still running
+/// (like `vcpu_processor_get_state`).
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_processor_run_bounded(
+    processor: *mut Processor,
+    instr: *const u8,
+    instr_len: usize,
+    memory: *mut Memory,
+    max_ticks: u64,
+    out_exit: *mut i32,
+) -> VcpuResult {
+    (*memory).try_use_mut(|variant| {
+        let exit_code = (*processor).run_bounded(
+            slice::from_raw_parts(instr, instr_len),
+            match variant {
+                MemoryVariant::Plain(inner) => inner,
+                MemoryVariant::IO(inner) => inner,
+                MemoryVariant::Composite(inner) => inner,
+                MemoryVariant::Fifo(inner) => inner,
+                MemoryVariant::Custom(inner) => inner.as_mut(),
+            },
+            max_ticks,
+        );
+
+        if !out_exit.is_null() {
+            *out_exit = exit_code.map_or(-1, |code| code.to_i32().unwrap());
+        }
         VcpuResult::Ok
     })
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_processor_set_breakpoint_addr(
+    processor: *mut Processor,
+    address: u32,
+) {
+    (*processor).set_breakpoint(address)
+}
+
+/// Removes the breakpoint at `address`. Returns `OutOfRange` if there was none.
+#[no_mangle]
+pub unsafe extern "C" fn vcpu_processor_clear_breakpoint(
+    processor: *mut Processor,
+    address: u32,
+) -> VcpuResult {
+    if (*processor).clear_breakpoint(address) {
+        VcpuResult::Ok
+    } else {
+        VcpuResult::OutOfRange
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_processor_reset(processor: *mut Processor) {
     (*processor).reset()
@@ -18,6 +18,7 @@ pub enum VcpuResult {
     OutOfRange = 7,
     ExecutableLoadFailed = 8,
     ExecutableSaveFailed = 9,
+    BreakpointHit = 10,
 }
 
 #[no_mangle]
@@ -4,6 +4,7 @@ use crate::memory::*;
 use crate::processor::*;
 use crate::register::*;
 use crate::result::*;
+use crate::util::*;
 use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
 use std::ptr::{null, null_mut};
@@ -49,7 +50,13 @@ fn run_simple() {
         ]);
 
         assert_eq!(
-            vcpu_processor_run(processor, instructions.as_ptr(), instructions.len(), memory),
+            vcpu_processor_run(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                null_mut()
+            ),
             VcpuResult::Ok
         );
 
@@ -72,6 +79,58 @@ fn run_simple() {
     }
 }
 
+#[test]
+fn run_ex_exit_code() {
+    unsafe {
+        let memory = vcpu_memory_create_plain(128);
+        let processor = vcpu_processor_create();
+
+        let instructions = instructions_from_words(&[
+            instr_i!(SLTI, T2, T0, 32),
+            instr_i!(BEZ, ZERO, T2, jmp_addr_i16(5)),
+            instr_i!(SLLI, T1, T0, 2),
+            instr_i!(SW, T0, T1, 0),
+            instr_i!(ADDI, T0, T0, 1),
+            instr_j!(JMP, jmp_addr_i32(-5)),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+
+        vcpu_processor_set_breakpoint_addr(processor, 16);
+
+        let mut exit_code = 0i32;
+        assert_eq!(
+            vcpu_processor_run_ex(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                null_mut(),
+                &mut exit_code
+            ),
+            VcpuResult::BreakpointHit
+        );
+        assert_eq!(exit_code, -1);
+
+        vcpu_processor_clear_breakpoint(processor, 16);
+
+        assert_eq!(
+            vcpu_processor_run_ex(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                null_mut(),
+                &mut exit_code
+            ),
+            VcpuResult::Ok
+        );
+        assert_eq!(exit_code, ExitCode::Halted as i32);
+
+        vcpu_processor_destroy(processor);
+        vcpu_memory_destroy(memory);
+    }
+}
+
 #[test]
 fn run_assembled() {
     unsafe {
@@ -108,7 +167,7 @@ end:  HALT";
         assert_ne!(instr_len, 0);
 
         assert_eq!(
-            vcpu_processor_run(processor, instr, instr_len, memory),
+            vcpu_processor_run(processor, instr, instr_len, memory, null_mut()),
             VcpuResult::Ok
         );
 
@@ -151,6 +210,105 @@ HALT";
     }
 }
 
+#[test]
+fn assemble_consecutive_errors() {
+    unsafe {
+        let first = get_c_str(".data\n.instructions\nFOO $t0");
+        let second = get_c_str(".data\n.instructions\nHALT\nBAR");
+        let mut executable: *mut Executable = null_mut();
+        let mut error: *const c_char = null();
+
+        assert_eq!(
+            vcpu_executable_assemble(first.as_ptr(), 0, &mut executable, null_mut(), &mut error),
+            VcpuResult::AssemblerError
+        );
+        let first_message = CStr::from_ptr(error).to_str().unwrap();
+        assert!(first_message.contains("FOO $t0"), "{}", first_message);
+
+        assert_eq!(
+            vcpu_executable_assemble(second.as_ptr(), 0, &mut executable, null_mut(), &mut error),
+            VcpuResult::AssemblerError
+        );
+        let second_message = CStr::from_ptr(error).to_str().unwrap();
+        assert!(second_message.contains("BAR"), "{}", second_message);
+        assert!(!second_message.contains("FOO"), "{}", second_message);
+    }
+}
+
+#[test]
+fn assemble_error_location() {
+    unsafe {
+        let source_str = ".data
+.instructions
+  FOO $t0, $t1
+HALT";
+
+        let source = get_c_str(source_str);
+        let mut executable: *mut Executable = null_mut();
+
+        assert_eq!(
+            vcpu_executable_assemble(source.as_ptr(), 0, &mut executable, null_mut(), null_mut()),
+            VcpuResult::AssemblerError
+        );
+
+        let mut line = 0u32;
+        let mut column = 0u32;
+
+        assert_eq!(
+            vcpu_get_last_error_location(&mut line, &mut column),
+            VcpuResult::Ok
+        );
+        assert_eq!(line, 3);
+        assert_eq!(column, 3);
+
+        column = 0;
+        assert_eq!(
+            vcpu_get_last_error_location(std::ptr::null_mut(), &mut column),
+            VcpuResult::Ok
+        );
+        assert_eq!(column, 3);
+    }
+}
+
+#[test]
+fn assemble_error_location_utf16() {
+    unsafe {
+        // The emoji is two UTF-16 code units long, but a single char.
+        let source_str = ".data # \u{1F600}
+.instructions
+  FOO $t0, $t1
+HALT";
+
+        let source = get_c_str(source_str);
+        let mut executable: *mut Executable = null_mut();
+
+        assert_eq!(
+            vcpu_executable_assemble(source.as_ptr(), 0, &mut executable, null_mut(), null_mut()),
+            VcpuResult::AssemblerError
+        );
+
+        let mut column = 0u32;
+        let mut offset = 0u32;
+
+        assert_eq!(
+            vcpu_get_last_error_location_utf16(&mut column, &mut offset),
+            VcpuResult::Ok
+        );
+        assert_eq!(column, 3);
+        assert_eq!(offset, 27);
+    }
+
+    let line = "LI $t0, 1 # \u{E9}\u{1F600} x";
+    let position = line.find('x').unwrap();
+    assert_eq!(utf16_column(line, position), 17);
+    assert_eq!(utf16_offset(line, position), 16);
+
+    let prefix = "# \u{1F600}\n";
+    let source = format!("{}{}", prefix, line);
+    assert_eq!(utf16_column(&source, prefix.len() + position), 17);
+    assert_eq!(utf16_offset(&source, prefix.len() + position), 21);
+}
+
 #[test]
 fn get_register_name_valid() {
     unsafe {
@@ -253,7 +411,7 @@ halt";
         let processor = vcpu_processor_create();
 
         assert_eq!(
-            vcpu_processor_run(processor, instr, instr_len, comp_mem),
+            vcpu_processor_run(processor, instr, instr_len, comp_mem, null_mut()),
             VcpuResult::Ok
         );
 
@@ -336,3 +494,420 @@ fn get_exit_code_desc() {
         assert_eq!(CStr::from_ptr(name).to_str(), Ok("BadProgramCounter"));
     }
 }
+
+#[test]
+fn load_data() {
+    unsafe {
+        let source = get_c_str(
+            ".data
+.block 4
+value: .word 0xDEADBEEF
+.instructions
+HALT",
+        );
+        let mut executable: *mut Executable = null_mut();
+
+        assert_eq!(
+            vcpu_executable_assemble(source.as_ptr(), 8, &mut executable, null_mut(), null_mut()),
+            VcpuResult::Ok
+        );
+
+        let memory = vcpu_memory_create_plain(16);
+        assert_eq!(vcpu_memory_load_data(memory, executable), VcpuResult::Ok);
+
+        let mut value = 0u32;
+        assert_eq!(vcpu_memory_get_word(memory, 12, &mut value), VcpuResult::Ok);
+        assert_eq!(value, 0xDEAD_BEEF);
+
+        let small = vcpu_memory_create_plain(12);
+        assert_eq!(
+            vcpu_memory_load_data(small, executable),
+            VcpuResult::OutOfRange
+        );
+
+        vcpu_memory_destroy(small);
+        vcpu_memory_destroy(memory);
+        vcpu_executable_destroy(executable);
+    }
+}
+
+#[test]
+fn breakpoints() {
+    unsafe {
+        let processor = vcpu_processor_create();
+        let memory = vcpu_memory_create_plain(0);
+
+        let instructions = instructions_from_words(&[
+            instr_i!(LI, T0, ZERO, 1),
+            instr_i!(LI, T1, ZERO, 2),
+            instr_i!(LI, T2, ZERO, 3),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+
+        vcpu_processor_set_breakpoint_addr(processor, 8);
+
+        let mut stopped_pc = 0u32;
+        assert_eq!(
+            vcpu_processor_run(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                &mut stopped_pc
+            ),
+            VcpuResult::BreakpointHit
+        );
+        assert_eq!(stopped_pc, 8);
+        assert_eq!(vcpu_processor_get_state(processor), -1);
+
+        assert_eq!(
+            vcpu_processor_clear_breakpoint(processor, 8),
+            VcpuResult::Ok
+        );
+        assert_eq!(
+            vcpu_processor_clear_breakpoint(processor, 8),
+            VcpuResult::OutOfRange
+        );
+
+        assert_eq!(
+            vcpu_processor_run(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                null_mut()
+            ),
+            VcpuResult::Ok
+        );
+        assert_eq!(vcpu_processor_get_state(processor), ExitCode::Halted as i32);
+
+        vcpu_processor_destroy(processor);
+        vcpu_memory_destroy(memory);
+    }
+}
+
+#[test]
+fn run_bounded() {
+    unsafe {
+        let processor = vcpu_processor_create();
+        let memory = vcpu_memory_create_plain(0);
+
+        let instructions =
+            instructions_from_words(&[instr_i!(ADDI, T0, T0, 1), instr_j!(JMP, jmp_addr_i32(-1))]);
+
+        let mut exit = 0i32;
+        assert_eq!(
+            vcpu_processor_run_bounded(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                100,
+                &mut exit
+            ),
+            VcpuResult::Ok
+        );
+        assert_eq!(exit, -1);
+        assert_eq!(vcpu_processor_get_state(processor), -1);
+
+        let mut value = 0i32;
+        vcpu_processor_get_register(processor, RegisterId::T0 as u32, &mut value);
+        assert_eq!(value, 50);
+
+        assert_eq!(
+            vcpu_processor_run_bounded(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                100,
+                &mut exit
+            ),
+            VcpuResult::Ok
+        );
+        assert_eq!(exit, -1);
+        vcpu_processor_get_register(processor, RegisterId::T0 as u32, &mut value);
+        assert_eq!(value, 100);
+
+        assert_eq!(
+            vcpu_processor_run_bounded(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                100,
+                std::ptr::null_mut()
+            ),
+            VcpuResult::Ok
+        );
+        vcpu_processor_get_register(processor, RegisterId::T0 as u32, &mut value);
+        assert_eq!(value, 150);
+
+        let instructions = instructions_from_words(&[instr_i!(HALT, ZERO, ZERO, 0)]);
+        vcpu_processor_reset(processor);
+        assert_eq!(
+            vcpu_processor_run_bounded(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                100,
+                &mut exit
+            ),
+            VcpuResult::Ok
+        );
+        assert_eq!(exit, ExitCode::Halted as i32);
+
+        vcpu_processor_destroy(processor);
+        vcpu_memory_destroy(memory);
+    }
+}
+
+#[test]
+fn fifo_memory() {
+    unsafe {
+        let fifo = vcpu_memory_create_fifo(4);
+        let composite = vcpu_memory_create_comp();
+        let key = get_c_str("fifo");
+
+        assert_eq!(
+            vcpu_memory_comp_mount(composite, 0x10, key.as_ptr(), fifo),
+            VcpuResult::Ok
+        );
+
+        assert_eq!(vcpu_memory_set_byte(composite, 0x10, 7), VcpuResult::Ok);
+        assert_eq!(vcpu_memory_set_byte(composite, 0x10, 9), VcpuResult::Ok);
+
+        let mut status = 0u32;
+        assert_eq!(vcpu_memory_get_word(fifo, 4, &mut status), VcpuResult::Ok);
+        assert_eq!(status, 0);
+
+        let mut value = 0u8;
+        assert_eq!(
+            vcpu_memory_get_byte(composite, 0x10, &mut value),
+            VcpuResult::Ok
+        );
+        assert_eq!(value, 7);
+        assert_eq!(vcpu_memory_get_byte(fifo, 0, &mut value), VcpuResult::Ok);
+        assert_eq!(value, 9);
+
+        assert_eq!(
+            vcpu_memory_get_word(composite, 0x14, &mut status),
+            VcpuResult::Ok
+        );
+        assert_eq!(status, FifoDevice::STATUS_EMPTY);
+
+        vcpu_memory_destroy(composite);
+        vcpu_memory_destroy(fifo);
+    }
+}
+
+#[test]
+fn poke_io_memory() {
+    unsafe {
+        let io_mem = vcpu_memory_create_io(8, can_write_dummy, on_write_dummy, null_mut());
+        let plain_mem = vcpu_memory_create_plain(8);
+        let mut value = 0u32;
+
+        assert_eq!(vcpu_memory_io_poke(io_mem, 4, 2, 0xBEEF), VcpuResult::Ok);
+        assert_eq!(
+            vcpu_memory_io_peek(io_mem, 4, 2, &mut value),
+            VcpuResult::Ok
+        );
+        assert_eq!(value, 0xBEEF);
+        assert_eq!((*io_mem).read_half(4), Ok(0xBEEF));
+
+        assert_eq!(vcpu_memory_io_poke(io_mem, 6, 4, 1), VcpuResult::OutOfRange);
+        assert_eq!(vcpu_memory_io_poke(io_mem, 0, 5, 1), VcpuResult::OutOfRange);
+        assert_eq!(
+            vcpu_memory_io_peek(io_mem, 0, 0, &mut value),
+            VcpuResult::OutOfRange
+        );
+        assert_eq!(
+            vcpu_memory_io_poke(plain_mem, 0, 4, 1),
+            VcpuResult::InvalidType
+        );
+
+        vcpu_memory_destroy(plain_mem);
+        vcpu_memory_destroy(io_mem);
+    }
+}
+
+extern "C" fn custom_read(
+    address: u32,
+    size: u32,
+    value: *mut u32,
+    user_data: *mut c_void,
+) -> bool {
+    let buffer = unsafe { &*(user_data as *const [u8; 16]) };
+    match (&buffer[..]).read(address, size) {
+        Ok(v) => {
+            unsafe { *value = v };
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+extern "C" fn custom_write(address: u32, size: u32, value: u32, user_data: *mut c_void) -> bool {
+    let buffer = unsafe { &mut *(user_data as *mut [u8; 16]) };
+    (&mut buffer[..]).write(address, size, value).is_ok()
+}
+
+#[test]
+fn custom_memory() {
+    unsafe {
+        let mut buffer = [0u8; 16];
+        let custom = vcpu_memory_create_custom(
+            16,
+            custom_read,
+            custom_write,
+            &mut buffer as *mut [u8; 16] as *mut c_void,
+        );
+        let processor = vcpu_processor_create();
+
+        let instructions = instructions_from_words(&[
+            instr_i!(LI, T0, ZERO, 0x1234),
+            instr_i!(SW, T0, ZERO, 8),
+            instr_i!(LH, T1, ZERO, 8),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+
+        assert_eq!(
+            vcpu_processor_run(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                custom,
+                null_mut()
+            ),
+            VcpuResult::Ok
+        );
+        assert_eq!(vcpu_processor_get_state(processor), ExitCode::Halted as i32);
+
+        let mut value = 0i32;
+        vcpu_processor_get_register(processor, register_index(RegisterId::T1) as u32, &mut value);
+        assert_eq!(value, 0x1234);
+        assert_eq!(&buffer[8..12], &[0x34, 0x12, 0, 0]);
+
+        let mut word = 0u32;
+        assert_eq!(
+            vcpu_memory_get_word(custom, 14, &mut word),
+            VcpuResult::OutOfRange
+        );
+
+        vcpu_processor_destroy(processor);
+        vcpu_memory_destroy(custom);
+    }
+}
+
+#[test]
+fn fault_info() {
+    unsafe {
+        let memory = vcpu_memory_create_plain(0);
+        let processor = vcpu_processor_create();
+
+        let instructions = instructions_from_words(&[
+            instr_i!(LI, T0, ZERO, 7),
+            instr_alu!(DIV, T1, T0, ZERO),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]);
+
+        let mut pc = 0u32;
+        let mut word = 0u32;
+        let mut disasm: *const c_char = null();
+
+        assert_eq!(
+            vcpu_processor_get_fault_info(processor, &mut pc, &mut word, &mut disasm),
+            VcpuResult::InvalidType
+        );
+
+        assert_eq!(
+            vcpu_processor_run(
+                processor,
+                instructions.as_ptr(),
+                instructions.len(),
+                memory,
+                null_mut()
+            ),
+            VcpuResult::Ok
+        );
+        assert_eq!(
+            vcpu_processor_get_state(processor),
+            ExitCode::DivisionByZero as i32
+        );
+
+        assert_eq!(
+            vcpu_processor_get_fault_info(processor, &mut pc, &mut word, &mut disasm),
+            VcpuResult::Ok
+        );
+        assert_eq!(pc, 4);
+        assert_eq!(word, instr_alu!(DIV, T1, T0, ZERO));
+        assert_eq!(CStr::from_ptr(disasm).to_str(), Ok("DIV $T1, $T0, $ZERO"));
+
+        vcpu_processor_destroy(processor);
+        vcpu_memory_destroy(memory);
+    }
+}
+
+#[test]
+fn comp_fragment_info() {
+    unsafe {
+        let composite = vcpu_memory_create_comp();
+        let ram = vcpu_memory_create_plain(64);
+        let device = vcpu_memory_create_plain(8);
+
+        let ram_key = get_c_str("ram");
+        let device_key = get_c_str("device");
+
+        assert_eq!(
+            vcpu_memory_comp_mount(composite, 0x100, device_key.as_ptr(), device),
+            VcpuResult::Ok
+        );
+        assert_eq!(
+            vcpu_memory_comp_mount(composite, 0, ram_key.as_ptr(), ram),
+            VcpuResult::Ok
+        );
+
+        assert_eq!(vcpu_memory_get_length(composite), 0x108);
+        assert_eq!(vcpu_memory_get_length(ram), 64);
+
+        let mut count = 0u32;
+        assert_eq!(
+            vcpu_memory_comp_fragment_count(composite, &mut count),
+            VcpuResult::Ok
+        );
+        assert_eq!(count, 2);
+        assert_eq!(
+            vcpu_memory_comp_fragment_count(ram, &mut count),
+            VcpuResult::InvalidType
+        );
+
+        let mut address = 0u32;
+        let mut length = 0u32;
+        let mut key: *const c_char = null();
+
+        assert_eq!(
+            vcpu_memory_comp_fragment_info(composite, 0, &mut address, &mut length, &mut key),
+            VcpuResult::Ok
+        );
+        assert_eq!((address, length), (0, 64));
+        assert_eq!(CStr::from_ptr(key).to_str(), Ok("ram"));
+
+        assert_eq!(
+            vcpu_memory_comp_fragment_info(composite, 1, &mut address, &mut length, &mut key),
+            VcpuResult::Ok
+        );
+        assert_eq!((address, length), (0x100, 8));
+        assert_eq!(CStr::from_ptr(key).to_str(), Ok("device"));
+
+        assert_eq!(
+            vcpu_memory_comp_fragment_info(composite, 2, &mut address, &mut length, &mut key),
+            VcpuResult::OutOfRange
+        );
+
+        vcpu_memory_destroy(device);
+        vcpu_memory_destroy(ram);
+        vcpu_memory_destroy(composite);
+    }
+}
@@ -1,4 +1,12 @@
-use std::ffi::c_void;
+use std::cell::RefCell;
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(Default::default());
+    static LAST_DISASSEMBLY: RefCell<CString> = RefCell::new(Default::default());
+    static LAST_FRAGMENT_KEY: RefCell<CString> = RefCell::new(Default::default());
+}
 
 pub unsafe fn into_ptr<T>(t: T) -> *mut T {
     Box::into_raw(Box::new(t))
@@ -8,6 +16,54 @@ pub unsafe fn destroy<T>(ptr: *mut T) {
     drop(Box::from_raw(ptr))
 }
 
+/// Stores `message` as the last error message of the current thread.
+pub fn set_last_error(message: String) {
+    let message = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+/// Returns the last error message of the current thread.
+///
+/// The pointer stays valid until the next call to `set_last_error` on the same thread.
+pub fn last_error_ptr() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
+
+/// Stores `disassembly` for the current thread and returns a pointer to it.
+///
+/// The pointer stays valid until the next call to `set_last_disassembly` on the same thread.
+pub fn set_last_disassembly(disassembly: String) -> *const c_char {
+    let disassembly = CString::new(disassembly).unwrap_or_default();
+    LAST_DISASSEMBLY.with(|d| {
+        *d.borrow_mut() = disassembly;
+        d.borrow().as_ptr()
+    })
+}
+
+/// Stores the fragment `key` for the current thread and returns a pointer to it.
+///
+/// The pointer stays valid until the next call to `set_last_fragment_key` on the same thread.
+pub fn set_last_fragment_key(key: &str) -> *const c_char {
+    let key = CString::new(key).unwrap_or_default();
+    LAST_FRAGMENT_KEY.with(|k| {
+        *k.borrow_mut() = key;
+        k.borrow().as_ptr()
+    })
+}
+
+/// Converts the byte offset `position` in `source` to an offset in UTF-16 code units, as used
+/// by many editor protocols.
+pub fn utf16_offset(source: &str, position: usize) -> usize {
+    source[..position].encode_utf16().count()
+}
+
+/// Returns the (1-based) column of the byte offset `position` in `source`, counted in UTF-16
+/// code units.
+pub fn utf16_column(source: &str, position: usize) -> usize {
+    let line_start = source[..position].rfind('\n').map_or(0, |i| i + 1);
+    utf16_offset(&source[line_start..], position - line_start) + 1
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn vcpu_memcpy(
     dst: *mut c_void,
@@ -0,0 +1,122 @@
+use crate::Executable;
+use vcpu::Word;
+
+/// An instruction word together with its disassembly (see [`vcpu::disassemble`]).
+///
+/// [`vcpu::disassemble`]: ../vcpu/fn.disassemble.html
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DisassembledWord {
+    pub word: Word,
+    /// The disassembly of `word`, or the error message if it cannot be decoded.
+    pub text: String,
+}
+
+impl DisassembledWord {
+    fn new(word: Word) -> DisassembledWord {
+        DisassembledWord {
+            word,
+            text: vcpu::disassemble(word).unwrap_or_else(|err| format!("{:?}", err)),
+        }
+    }
+}
+
+/// A difference between two executables, as reported by [`diff`].
+///
+/// [`diff`]: ./fn.diff.html
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ExecutableDiff {
+    /// The data offsets differ.
+    DataOffset { left: u32, right: u32 },
+    /// The entry points differ.
+    EntryPoint { left: u32, right: u32 },
+    /// The instruction words at `index` differ. If only one of the executables has an
+    /// instruction at `index`, the other side is `None`.
+    Instruction {
+        index: usize,
+        left: Option<DisassembledWord>,
+        right: Option<DisassembledWord>,
+    },
+    /// The data bytes in the range `[start..end]` differ.
+    /// Bytes that exist in only one of the executables count as different.
+    Data { start: usize, end: usize },
+}
+
+/// Compares two executables, e.g. to check that a change to the assembler doesn't change its output.
+///
+/// Returns an empty vector if the executables are equal. Otherwise, the differences are ordered
+/// by data offset, then entry point, then instructions (by index), then data ranges (by start).
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+/// use vex::{diff, Executable, ExecutableDiff};
+///
+/// let a = Executable::from_words(vec![instr_i!(LI, T0, ZERO, 1), instr_i!(HALT, ZERO, ZERO, 0)]);
+/// let b = Executable::from_words(vec![instr_i!(LI, T0, ZERO, 2), instr_i!(HALT, ZERO, ZERO, 0)]);
+///
+/// match &diff(&a, &b)[..] {
+///     [ExecutableDiff::Instruction { index: 0, left: Some(left), right: Some(right) }] => {
+///         assert_eq!(left.text, "LI $T0, 1");
+///         assert_eq!(right.text, "LI $T0, 2");
+///     }
+///     other => panic!("unexpected diff: {:?}", other),
+/// }
+/// ```
+pub fn diff(a: &Executable, b: &Executable) -> Vec<ExecutableDiff> {
+    let mut diffs = Vec::new();
+
+    if a.data_offset() != b.data_offset() {
+        diffs.push(ExecutableDiff::DataOffset {
+            left: a.data_offset(),
+            right: b.data_offset(),
+        });
+    }
+
+    if a.entry_point() != b.entry_point() {
+        diffs.push(ExecutableDiff::EntryPoint {
+            left: a.entry_point(),
+            right: b.entry_point(),
+        });
+    }
+
+    let a_words: Vec<Word> = a.instruction_words().collect();
+    let b_words: Vec<Word> = b.instruction_words().collect();
+
+    for index in 0..a_words.len().max(b_words.len()) {
+        let left = a_words.get(index).copied();
+        let right = b_words.get(index).copied();
+
+        if left != right {
+            diffs.push(ExecutableDiff::Instruction {
+                index,
+                left: left.map(DisassembledWord::new),
+                right: right.map(DisassembledWord::new),
+            });
+        }
+    }
+
+    let data_len = a.data().len().max(b.data().len());
+    let mut range_start = None;
+
+    for i in 0..data_len {
+        let differs = a.data().get(i) != b.data().get(i);
+
+        match (range_start, differs) {
+            (None, true) => range_start = Some(i),
+            (Some(start), false) => {
+                diffs.push(ExecutableDiff::Data { start, end: i });
+                range_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(start) = range_start {
+        diffs.push(ExecutableDiff::Data {
+            start,
+            end: data_len,
+        });
+    }
+
+    diffs
+}
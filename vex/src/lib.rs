@@ -1,10 +1,21 @@
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use byteorder::{ByteOrder, WriteBytesExt};
 use std::fs::File;
 use std::io::prelude::*;
 use std::io::{BufReader, BufWriter};
 use std::mem;
 use std::path::Path;
 use util::Endian;
+use vcpu::{DecodeError, Word, WORD_BYTES};
+
+mod diff;
+mod run;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod writer;
+
+pub use diff::*;
+pub use run::*;
+pub use writer::*;
 
 // TODO: use proper binary serialization using serde/bincode
 
@@ -13,6 +24,7 @@ pub struct Executable {
     data_offset: u32,
     instructions: Vec<u8>,
     data: Vec<u8>,
+    entry_point: u32,
 }
 
 impl Executable {
@@ -21,6 +33,7 @@ impl Executable {
             data_offset,
             instructions,
             data,
+            entry_point: 0,
         }
     }
 
@@ -29,13 +42,79 @@ impl Executable {
             data_offset,
             instructions: Vec::from(instructions),
             data: Vec::from(data),
+            entry_point: 0,
         }
     }
 
+    /// Constructs an executable from `instructions` alone, without any data.
+    pub fn instructions_only(instructions: Vec<u8>) -> Executable {
+        Executable::from(0u32, instructions, Vec::new())
+    }
+
+    /// Constructs an executable without any data from a sequence of instruction words.
+    ///
+    /// # Examples
+    /// ```
+    /// use vcpu::*;
+    /// use vex::Executable;
+    ///
+    /// let executable = Executable::from_words(vec![instr_i!(HALT, ZERO, ZERO, 0)]);
+    /// assert_eq!(executable.instructions().len(), 4);
+    /// assert!(executable.data().is_empty());
+    /// ```
+    pub fn from_words<I: IntoIterator<Item = Word>>(words: I) -> Executable {
+        let words: Vec<Word> = words.into_iter().collect();
+        Executable::instructions_only(vcpu::instructions_from_words(&words))
+    }
+
     pub fn data_offset(&self) -> u32 {
         self.data_offset
     }
 
+    pub fn set_data_offset(&mut self, data_offset: u32) {
+        self.data_offset = data_offset;
+    }
+
+    /// Returns the offset (in bytes) into the instructions at which execution begins.
+    /// This is `0` unless set otherwise, e.g. with the `.entry` directive of the assembler.
+    pub fn entry_point(&self) -> u32 {
+        self.entry_point
+    }
+
+    pub fn set_entry_point(&mut self, entry_point: u32) {
+        self.entry_point = entry_point;
+    }
+
+    /// Appends a single instruction word.
+    ///
+    /// # Panics
+    /// Panics if the current length of the instructions is not a multiple of [`WORD_BYTES`].
+    ///
+    /// [`WORD_BYTES`]: ../vcpu/constant.WORD_BYTES.html
+    pub fn push_word(&mut self, word: Word) {
+        self.append_instructions(&[word]);
+    }
+
+    /// Appends a sequence of instruction words.
+    ///
+    /// # Panics
+    /// Panics if the current length of the instructions is not a multiple of [`WORD_BYTES`].
+    ///
+    /// [`WORD_BYTES`]: ../vcpu/constant.WORD_BYTES.html
+    pub fn append_instructions(&mut self, words: &[Word]) {
+        assert!(
+            self.instructions.len().is_multiple_of(WORD_BYTES as usize),
+            "Instructions are not aligned to word boundaries."
+        );
+        self.instructions
+            .extend(vcpu::instructions_from_words(words));
+    }
+
+    /// Appends `bytes` to the data.
+    pub fn append_data(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
     pub fn instructions(&self) -> &[u8] {
         &self.instructions[..]
     }
@@ -44,34 +123,245 @@ impl Executable {
         &self.data[..]
     }
 
+    /// Returns the number of bytes written by [`write`].
+    ///
+    /// [`write`]: fn.write.html
     pub fn required_size(&self) -> usize {
-        mem::size_of::<u32>() * 3 + self.instructions.len() + self.data.len()
+        mem::size_of::<u32>() * 6 + self.instructions.len() + self.data.len()
+    }
+
+    /// Returns whether a memory of `mem_size` bytes is large enough to hold the data at the
+    /// data offset.
+    pub fn fits_in_memory(&self, mem_size: u32) -> bool {
+        u64::from(self.data_offset) + self.data.len() as u64 <= u64::from(mem_size)
+    }
+
+    /// Returns an iterator over the instruction words.
+    ///
+    /// Trailing bytes that don't form a complete word are ignored.
+    pub fn instruction_words(&self) -> impl Iterator<Item = Word> + '_ {
+        self.instructions
+            .chunks_exact(WORD_BYTES as usize)
+            .map(Endian::read_u32)
+    }
+
+    /// Checks every instruction word with [`vcpu::validate_instruction`], so that unknown
+    /// opcodes or functs are caught when loading instead of at runtime.
+    ///
+    /// Trailing bytes that don't form a complete word are ignored.
+    ///
+    /// # Errors
+    /// Returns the index of the first invalid instruction word together with the decode error.
+    ///
+    /// [`vcpu::validate_instruction`]: ../vcpu/fn.validate_instruction.html
+    pub fn validate(&self) -> Result<(), (usize, DecodeError)> {
+        self.instruction_words()
+            .enumerate()
+            .try_for_each(|(i, word)| vcpu::validate_instruction(word).map_err(|err| (i, err)))
     }
 }
 
+impl From<Vec<u8>> for Executable {
+    fn from(instructions: Vec<u8>) -> Executable {
+        Executable::instructions_only(instructions)
+    }
+}
+
+/// Reads an executable in the `.vex` format (see [`write`]).
+///
+/// Files written by older versions, which end after the data or the entry point, can still be
+/// read. They have no checksum, so they are not verified. A trailer that starts with
+/// [`TRAILER_MARKER`] must be complete, so cutting off the checksum is detected.
+///
+/// # Errors
+/// If the input ends early, the returned error has the kind `UnexpectedEof` and a message
+/// saying whether the header, the instructions, the data or the trailer are truncated.
+///
+/// If the checksum doesn't match the instructions and data, the returned error has the kind
+/// `InvalidData`.
+///
+/// [`write`]: fn.write.html
+/// [`TRAILER_MARKER`]: constant.TRAILER_MARKER.html
 pub fn read<R: Read>(reader: &mut R) -> std::io::Result<Executable> {
-    let instr_len = reader.read_u32::<Endian>()?;
-    let data_length = reader.read_u32::<Endian>()?;
-    let data_offset = reader.read_u32::<Endian>()?;
+    let mut header = [0; 12];
+    let header_len = read_available(reader, &mut header)?;
+    if header_len < header.len() {
+        return Err(truncated_error(format!(
+            "truncated header: expected {} bytes but only {} available",
+            header.len(),
+            header_len
+        )));
+    }
+
+    let instr_len = Endian::read_u32(&header[0..4]);
+    let data_length = Endian::read_u32(&header[4..8]);
+    let data_offset = Endian::read_u32(&header[8..12]);
 
     let mut instructions = vec![0; instr_len as usize];
     let mut data = vec![0; data_length as usize];
 
-    reader.read_exact(&mut instructions)?;
-    reader.read_exact(&mut data)?;
+    let available = read_available(reader, &mut instructions)?;
+    if available < instructions.len() {
+        return Err(truncated_error(format!(
+            "truncated instructions: declared {} instruction bytes but only {} available",
+            instr_len, available
+        )));
+    }
+
+    let available = read_available(reader, &mut data)?;
+    if available < data.len() {
+        return Err(truncated_error(format!(
+            "truncated data: declared {} data bytes but only {} available",
+            data_length, available
+        )));
+    }
+
+    // The entry point and the checksum are optional, so that files written before they existed
+    // can still be read. Such files end with at most the entry point, which is never equal to
+    // the marker.
+    let mut trailer = [0; 12];
+    let entry_point = match read_available(reader, &mut trailer)? {
+        0 => 0,
+        4 if Endian::read_u32(&trailer[0..4]) != TRAILER_MARKER => Endian::read_u32(&trailer[0..4]),
+        12 => {
+            let marker = Endian::read_u32(&trailer[0..4]);
+            if marker != TRAILER_MARKER {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("unknown trailer marker {:08x}", marker),
+                ));
+            }
 
-    Ok(Executable::from(data_offset, instructions, data))
+            let expected = Endian::read_u32(&trailer[8..12]);
+            let actual = checksum(&instructions, &data);
+            if actual != expected {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "checksum mismatch: expected {:08x} but computed {:08x}",
+                        expected, actual
+                    ),
+                ));
+            }
+            Endian::read_u32(&trailer[4..8])
+        }
+        available => {
+            return Err(truncated_error(format!(
+                "truncated trailer: expected 12 bytes but only {} available",
+                available
+            )))
+        }
+    };
+
+    let mut executable = Executable::from(data_offset, instructions, data);
+    executable.set_entry_point(entry_point);
+    Ok(executable)
 }
 
+/// Starts the trailer of `.vex` files which have a checksum, followed by the entry point and the
+/// checksum.
+///
+/// It isn't aligned to a word, so it can't be mistaken for the entry point of an older file.
+pub const TRAILER_MARKER: u32 = 0x3143_5243;
+
+/// Computes the CRC32 checksum over the instructions and the data, as stored at the end of a
+/// `.vex` file.
+fn checksum(instructions: &[u8], data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(instructions);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Like `read_exact`, but returns the number of bytes read instead of failing at the end of input.
+fn read_available<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(total)
+}
+
+fn truncated_error(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::UnexpectedEof, message)
+}
+
+/// Writes `executable` in the `.vex` format: the lengths of the instructions and the data and
+/// the data offset, followed by the instructions and the data.
+///
+/// The [`TRAILER_MARKER`], the entry point and a CRC32 checksum of the instructions and the data
+/// are appended at the end, so that [`read`] can detect corrupted files.
+///
+/// [`TRAILER_MARKER`]: constant.TRAILER_MARKER.html
+/// [`read`]: fn.read.html
 pub fn write<W: Write>(writer: &mut W, executable: &Executable) -> std::io::Result<()> {
     writer.write_u32::<Endian>(executable.instructions.len() as u32)?;
     writer.write_u32::<Endian>(executable.data.len() as u32)?;
     writer.write_u32::<Endian>(executable.data_offset)?;
     writer.write_all(&executable.instructions[..])?;
     writer.write_all(&executable.data[..])?;
+    writer.write_u32::<Endian>(TRAILER_MARKER)?;
+    writer.write_u32::<Endian>(executable.entry_point)?;
+    writer.write_u32::<Endian>(checksum(&executable.instructions, &executable.data))?;
     Ok(())
 }
 
+/// Writes only the instructions of `executable` as raw bytes, without any header or data,
+/// e.g. for flashing them into an instruction ROM.
+pub fn write_instructions<W: Write>(
+    writer: &mut W,
+    executable: &Executable,
+) -> std::io::Result<()> {
+    writer.write_all(&executable.instructions[..])
+}
+
+/// Reads raw instruction bytes (as written by [`write_instructions`]) until the end of `reader`
+/// into an executable without data.
+///
+/// [`write_instructions`]: fn.write_instructions.html
+pub fn read_instructions<R: Read>(reader: &mut R) -> std::io::Result<Executable> {
+    let mut instructions = Vec::new();
+    reader.read_to_end(&mut instructions)?;
+    Ok(Executable::instructions_only(instructions))
+}
+
+/// Lays out `executable` as a single contiguous image, which can be copied into memory as is:
+/// the instructions at address `0` and the data at the data offset, with any gap between them
+/// filled with zeros.
+///
+/// # Errors
+/// Returns an error if the data overlaps the instructions.
+///
+/// # Examples
+/// ```
+/// use vex::Executable;
+///
+/// let executable = Executable::from(6, vec![1, 2, 3, 4], vec![5, 6]);
+/// assert_eq!(vex::flat_image(&executable), Ok(vec![1, 2, 3, 4, 0, 0, 5, 6]));
+///
+/// let executable = Executable::from(2, vec![1, 2, 3, 4], vec![5, 6]);
+/// assert_eq!(vex::flat_image(&executable), Err(()));
+/// ```
+pub fn flat_image(executable: &Executable) -> Result<Vec<u8>, ()> {
+    let instr_len = executable.instructions.len();
+    let data_start = executable.data_offset as usize;
+    let data_end = data_start + executable.data.len();
+
+    if !executable.data.is_empty() && data_start < instr_len {
+        return Err(());
+    }
+
+    let mut image = vec![0; instr_len.max(data_end)];
+    image[..instr_len].copy_from_slice(&executable.instructions);
+    image[data_start..data_end].copy_from_slice(&executable.data);
+    Ok(image)
+}
+
 pub trait ReadVexExt: Read + Sized {
     fn read_vex(&mut self) -> std::io::Result<Executable> {
         read(self)
@@ -84,6 +374,23 @@ pub trait WriteVexExt: Write + Sized {
     fn write_vex(&mut self, executable: &Executable) -> std::io::Result<()> {
         write(self, executable)
     }
+
+    /// Starts writing an executable incrementally, see [`ExecutableWriter`].
+    ///
+    /// [`ExecutableWriter`]: struct.ExecutableWriter.html
+    fn vex_writer(self, data_offset: u32) -> std::io::Result<ExecutableWriter<Self>>
+    where
+        Self: Seek,
+    {
+        ExecutableWriter::new(self, data_offset)
+    }
+
+    /// Starts writing an executable incrementally, see [`BufferedExecutableWriter`].
+    ///
+    /// [`BufferedExecutableWriter`]: struct.BufferedExecutableWriter.html
+    fn buffered_vex_writer(self, data_offset: u32) -> BufferedExecutableWriter<Self> {
+        BufferedExecutableWriter::new(self, data_offset)
+    }
 }
 
 impl<W: Write + Sized> WriteVexExt for W {}
@@ -0,0 +1,113 @@
+use crate::Executable;
+use std::thread;
+use vcpu::{CompositeMemory, ExitCode, Processor, StorageMut};
+
+/// Runs `executable` on a new [`Processor`] with a plain memory of `mem_size` bytes.
+///
+/// The executable's data is copied into the memory at its data offset before execution starts
+/// at its entry point. Returns the exit code and the final contents of the memory.
+///
+/// # Panics
+/// Panics if the executable's data does not fit into `mem_size` bytes at its data offset.
+///
+/// [`Processor`]: ../vcpu/struct.Processor.html
+pub fn run_executable(executable: &Executable, mem_size: u32) -> (ExitCode, Vec<u8>) {
+    assert!(
+        executable.fits_in_memory(mem_size),
+        "Executable data does not fit into memory."
+    );
+
+    let mut memory = vec![0u8; mem_size as usize];
+
+    let data_start = executable.data_offset() as usize;
+    let data_end = data_start + executable.data().len();
+    memory[data_start..data_end].copy_from_slice(executable.data());
+
+    let mut processor = Processor::new();
+    let instr_len = executable.instructions().len() as u32;
+    if let Err(exit_code) = processor.set_program_counter(executable.entry_point(), Some(instr_len))
+    {
+        return (exit_code, memory);
+    }
+
+    let exit_code = processor.run(executable.instructions(), &mut memory);
+
+    (exit_code, memory)
+}
+
+/// Runs `executable` on a new [`Processor`] with `memory`, which may contain IO fragments.
+///
+/// The executable's data is copied into the fragment mounted at its data offset before
+/// execution starts at its entry point.
+///
+/// # Panics
+/// Panics if the executable's data does not fit into a single plain fragment at its data offset.
+///
+/// [`Processor`]: ../vcpu/struct.Processor.html
+pub fn run_program(executable: &Executable, memory: &mut CompositeMemory) -> ExitCode {
+    let data = executable.data();
+    if !data.is_empty() {
+        memory
+            .borrow_slice_mut(executable.data_offset(), data.len() as u32)
+            .expect("Executable data does not fit into a fragment.")
+            .copy_from_slice(data);
+    }
+
+    let mut processor = Processor::new();
+    let instr_len = executable.instructions().len() as u32;
+    if let Err(exit_code) = processor.set_program_counter(executable.entry_point(), Some(instr_len))
+    {
+        return exit_code;
+    }
+
+    processor.run(executable.instructions(), memory)
+}
+
+/// Returns the process exit status to report for a program that stopped with `exit_code`.
+///
+/// This is `0` for [`ExitCode::Halted`] and a distinct non-zero status for every fault, so
+/// scripts running a program can tell a normal shutdown from a faulted one.
+///
+/// [`ExitCode::Halted`]: ../vcpu/enum.ExitCode.html#variant.Halted
+pub fn exit_status(exit_code: ExitCode) -> i32 {
+    // The variants are declared with `Halted` first, followed by the faults.
+    exit_code as i32
+}
+
+/// Runs each of the `programs` like [`run_executable`] and returns the results in the same order.
+///
+/// The programs are distributed across as many threads as the system supports, each program
+/// getting its own [`Processor`] and memory.
+///
+/// # Panics
+/// Panics if the data of any program does not fit into `mem_size` bytes at its data offset.
+///
+/// [`run_executable`]: ./fn.run_executable.html
+/// [`Processor`]: ../vcpu/struct.Processor.html
+pub fn run_batch(programs: &[Executable], mem_size: u32) -> Vec<(ExitCode, Vec<u8>)> {
+    if programs.is_empty() {
+        return Vec::new();
+    }
+
+    let thread_count = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = programs.len().div_ceil(thread_count);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = programs
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|program| run_executable(program, mem_size))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
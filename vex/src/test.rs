@@ -1,4 +1,5 @@
 use super::*;
+use vcpu::*;
 
 #[test]
 fn write_read() {
@@ -19,3 +20,454 @@ fn write_read() {
 
     std::fs::remove_file(path).unwrap();
 }
+
+#[test]
+fn validate_valid() {
+    let instructions =
+        instructions_from_words(&[instr_alu!(ADD, T0, T1, T2), instr_i!(HALT, ZERO, ZERO, 0)]);
+
+    let executable = Executable::from(0, instructions, vec![]);
+    assert_eq!(executable.validate(), Ok(()));
+}
+
+#[test]
+fn validate_invalid_alu_funct() {
+    let instructions = instructions_from_words(&[nop!(), instr_r!(ALU, T0, T1, T2, 50)]);
+
+    let executable = Executable::from(0, instructions, vec![]);
+    assert_eq!(
+        executable.validate(),
+        Err((1, DecodeError::InvalidAluFunct(50)))
+    );
+}
+
+fn loop_executable(iterations: i16) -> Executable {
+    let instructions = instructions_from_words(&[
+        instr_i!(SLTI, T2, T0, iterations),
+        instr_i!(BEZ, ZERO, T2, jmp_addr_i16(5)),
+        instr_i!(SLLI, T1, T0, 2),
+        instr_i!(SW, T0, T1, 0),
+        instr_i!(ADDI, T0, T0, 1),
+        instr_j!(JMP, jmp_addr_i32(-5)),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ]);
+
+    Executable::from(0, instructions, vec![])
+}
+
+#[test]
+fn run_executable_loop() {
+    let (exit_code, memory) = run_executable(&loop_executable(32), 128);
+
+    assert_eq!(exit_code, ExitCode::Halted);
+    for i in 0..32 {
+        assert_eq!(memory.read_word(i * WORD_BYTES), Ok(i));
+    }
+}
+
+#[test]
+fn run_executable_loads_data() {
+    let instructions = instructions_from_words(&[
+        instr_i!(LW, T0, ZERO, 8),
+        instr_i!(SW, T0, ZERO, 0),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ]);
+    let executable = Executable::from(8, instructions, vec![1, 2, 3, 4]);
+
+    let (exit_code, memory) = run_executable(&executable, 16);
+
+    assert_eq!(exit_code, ExitCode::Halted);
+    assert_eq!(&memory[0..4], &[1, 2, 3, 4]);
+}
+
+#[test]
+fn run_batch_matches_single() {
+    let programs: Vec<_> = (0..64).map(|i| loop_executable(i % 32)).collect();
+
+    let expected: Vec<_> = programs.iter().map(|p| run_executable(p, 128)).collect();
+    let results = run_batch(&programs, 128);
+
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn from_words_runs() {
+    let words: Vec<Word> = vec![
+        instr_i!(LI, T0, ZERO, 42),
+        instr_i!(SW, T0, ZERO, 0),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ];
+
+    let executable = Executable::from_words(words);
+    assert_eq!(executable.data_offset(), 0);
+    assert!(executable.data().is_empty());
+
+    let (exit_code, memory) = run_executable(&executable, 4);
+    assert_eq!(exit_code, ExitCode::Halted);
+    assert_eq!(memory.read_word(0), Ok(42));
+}
+
+#[test]
+fn words_round_trip() {
+    let words: Vec<Word> = vec![instr_alu!(ADD, T0, T1, T2), instr_j!(JMP, -4), 0xFFFF_FFFF];
+
+    let executable = Executable::from_words(words.clone());
+    assert_eq!(executable.instruction_words().collect::<Vec<_>>(), words);
+}
+
+#[test]
+fn instructions_only() {
+    let instructions = instructions_from_words(&[instr_i!(HALT, ZERO, ZERO, 0)]);
+
+    let executable: Executable = instructions.clone().into();
+    assert_eq!(
+        executable,
+        Executable::from(0, instructions.clone(), Vec::new())
+    );
+    assert_eq!(
+        Executable::instructions_only(instructions.clone()),
+        Executable::from(0, instructions, Vec::new())
+    );
+}
+
+#[test]
+fn append() {
+    let mut executable = Executable::instructions_only(Vec::new());
+    executable.push_word(instr_i!(LW, T0, ZERO, 4));
+    executable.append_instructions(&[
+        instr_i!(ADDI, T0, T0, 1),
+        instr_i!(SW, T0, ZERO, 8),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ]);
+    executable.append_data(&[5, 0, 0, 0]);
+    executable.append_data(&[0, 0, 0, 0]);
+    executable.set_data_offset(4);
+
+    assert_eq!(executable.instructions().len(), 16);
+    assert_eq!(executable.data(), &[5, 0, 0, 0, 0, 0, 0, 0]);
+
+    let (exit_code, memory) = run_executable(&executable, 12);
+    assert_eq!(exit_code, ExitCode::Halted);
+    assert_eq!(memory.read_word(8), Ok(6));
+}
+
+#[test]
+#[should_panic]
+fn push_word_unaligned() {
+    let mut executable = Executable::instructions_only(vec![0, 0]);
+    executable.push_word(instr_i!(HALT, ZERO, ZERO, 0));
+}
+
+#[test]
+fn diff_equal() {
+    assert!(diff(&loop_executable(10), &loop_executable(10)).is_empty());
+}
+
+#[test]
+fn diff_single_instruction() {
+    let a = loop_executable(10);
+    let b = loop_executable(12);
+
+    let diffs = diff(&a, &b);
+    assert_eq!(diffs.len(), 1);
+
+    if let ExecutableDiff::Instruction { index, left, right } = &diffs[0] {
+        let left = left.as_ref().unwrap();
+        let right = right.as_ref().unwrap();
+
+        assert_eq!(*index, 0);
+        assert_eq!(left.word, a.instruction_words().next().unwrap());
+        assert_eq!(left.text, "SLTI $T2, $T0, 10");
+        assert_eq!(right.text, "SLTI $T2, $T0, 12");
+    } else {
+        panic!("unexpected diff: {:?}", diffs[0]);
+    }
+}
+
+#[test]
+fn diff_data_and_length() {
+    let a = Executable::from(
+        0,
+        instructions_from_words(&[instr_i!(HALT, ZERO, ZERO, 0)]),
+        vec![1, 2, 3, 4, 5, 6],
+    );
+    let b = Executable::from(
+        16,
+        instructions_from_words(&[instr_i!(HALT, ZERO, ZERO, 0), 0xFFFF_FFFF]),
+        vec![1, 0, 0, 4, 5, 6, 7, 8],
+    );
+
+    assert_eq!(
+        diff(&a, &b),
+        vec![
+            ExecutableDiff::DataOffset { left: 0, right: 16 },
+            ExecutableDiff::Instruction {
+                index: 1,
+                left: None,
+                right: Some(DisassembledWord {
+                    word: 0xFFFF_FFFF,
+                    text: "InvalidOpcode(63)".to_owned(),
+                }),
+            },
+            ExecutableDiff::Data { start: 1, end: 3 },
+            ExecutableDiff::Data { start: 6, end: 8 },
+        ]
+    );
+}
+
+#[test]
+fn exit_status_of_fault() {
+    let instructions = instructions_from_words(&[
+        instr_i!(ADDI, T0, ZERO, 7),
+        instr_alu!(DIV, T1, T0, ZERO),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ]);
+
+    let (exit_code, _) = run_executable(&Executable::from(0, instructions, vec![]), 0);
+    assert_eq!(exit_code, ExitCode::DivisionByZero);
+    assert_ne!(exit_status(exit_code), 0);
+
+    let (exit_code, _) = run_executable(&loop_executable(4), 16);
+    assert_eq!(exit_status(exit_code), 0);
+
+    assert_ne!(
+        exit_status(ExitCode::DivisionByZero),
+        exit_status(ExitCode::BadMemoryAccess)
+    );
+}
+
+#[test]
+fn entry_point() {
+    let instructions = instructions_from_words(&[
+        instr_i!(LI, T0, ZERO, 1),
+        instr_i!(SW, T0, ZERO, 0),
+        instr_i!(LI, T0, ZERO, 2),
+        instr_i!(SW, T0, ZERO, 4),
+        instr_i!(HALT, ZERO, ZERO, 0),
+    ]);
+
+    let mut executable = Executable::from(0, instructions, vec![]);
+    let mut bytes = Vec::new();
+    write(&mut bytes, &executable).unwrap();
+    assert_eq!(bytes.len(), executable.required_size());
+
+    // Older versions wrote neither the entry point nor the checksum.
+    let legacy = bytes[..bytes.len() - 12].to_vec();
+
+    executable.set_entry_point(2 * WORD_BYTES);
+    let (exit_code, memory) = run_executable(&executable, 8);
+    assert_eq!(exit_code, ExitCode::Halted);
+    assert_eq!(memory, [0, 0, 0, 0, 2, 0, 0, 0]);
+
+    let mut bytes = Vec::new();
+    write(&mut bytes, &executable).unwrap();
+    assert_eq!(bytes.len(), executable.required_size());
+    assert_eq!(read(&mut &bytes[..]).unwrap(), executable);
+
+    // Files without an entry point start at the first instruction.
+    assert_eq!(read(&mut &legacy[..]).unwrap().entry_point(), 0);
+
+    // Files without a checksum are read without verification.
+    let mut unchecked = legacy.clone();
+    unchecked.extend_from_slice(&bytes[bytes.len() - 8..bytes.len() - 4]);
+    assert_eq!(read(&mut &unchecked[..]).unwrap(), executable);
+
+    executable.set_entry_point(2);
+    assert_eq!(run_executable(&executable, 8).0, ExitCode::BadAlignment);
+}
+
+#[test]
+fn write_incrementally() {
+    let mut expected = loop_executable(4);
+    expected.set_data_offset(64);
+    expected.append_data(&[1, 2, 3, 4, 5]);
+    expected.set_entry_point(8);
+
+    // Start after some existing bytes, which must be left alone.
+    let mut cursor = std::io::Cursor::new(vec![0xff; 2]);
+    cursor.set_position(2);
+    let mut writer = cursor.vex_writer(64).unwrap();
+    for word in expected.instruction_words() {
+        writer.write_word(word).unwrap();
+    }
+    writer.write_data(&[1, 2]).unwrap();
+    writer.write_data(&[3, 4, 5]).unwrap();
+    writer.set_entry_point(8);
+
+    let mut cursor = writer.finish().unwrap();
+    assert_eq!(cursor.position() as usize, expected.required_size() + 2);
+    assert_eq!(&cursor.get_ref()[..2], &[0xff, 0xff]);
+    cursor.set_position(2);
+    assert_eq!(read(&mut cursor).unwrap(), expected);
+
+    let mut writer = Vec::new().buffered_vex_writer(64);
+    writer.write_instructions(expected.instructions()).unwrap();
+    writer.write_data(&[1, 2]).unwrap();
+    writer.write_data(&[3, 4, 5]).unwrap();
+    writer.set_entry_point(8);
+
+    let buffer = writer.finish().unwrap();
+    assert_eq!(read(&mut &buffer[..]).unwrap(), expected);
+}
+
+#[test]
+#[should_panic]
+fn write_instructions_after_data() {
+    let mut writer = std::io::Cursor::new(Vec::new()).vex_writer(0).unwrap();
+    writer.write_data(&[1]).unwrap();
+    writer.write_word(nop!()).unwrap();
+}
+
+#[test]
+fn run_program_with_io() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let executable = Executable::from(
+        8,
+        instructions_from_words(&[
+            instr_i!(LB, T0, ZERO, 8),
+            instr_i!(LHI, T1, ZERO, 0xf1edu16 as i16),
+            instr_i!(SB, T0, T1, 0),
+            instr_i!(HALT, ZERO, ZERO, 0),
+        ]),
+        vec![42],
+    );
+
+    let written = Rc::new(Cell::new(None));
+    let written_ref = Rc::clone(&written);
+    let handler = DelegateIOHandler::new(
+        |_, _, _| true,
+        move |memory: &[u8], address, _| written_ref.set(Some(memory[address as usize])),
+    );
+
+    let mut memory = CompositeMemory::new();
+    memory.mount(0, "main", vec![0u8; 1024]).unwrap();
+    memory
+        .mount(0xF1ED_0000, "io", IOMemory::new(1, handler))
+        .unwrap();
+
+    assert_eq!(run_program(&executable, &mut memory), ExitCode::Halted);
+    assert_eq!(written.get(), Some(42));
+    assert_eq!(memory.read_byte(8), Ok(42));
+}
+
+#[test]
+fn flat_image_with_gap() {
+    let executable = Executable::from(
+        16,
+        loop_executable(4).instructions()[..8].to_vec(),
+        vec![7; 4],
+    );
+    let image = flat_image(&executable).unwrap();
+
+    assert_eq!(image.len(), 20);
+    assert_eq!(&image[..8], executable.instructions());
+    assert_eq!(&image[8..16], &[0; 8]);
+    assert_eq!(&image[16..], executable.data());
+
+    let mut executable = loop_executable(4);
+    assert_eq!(flat_image(&executable).unwrap(), executable.instructions());
+
+    executable.set_data_offset(4);
+    assert_eq!(
+        flat_image(&executable),
+        Ok(executable.instructions().to_vec())
+    );
+    executable.append_data(&[1]);
+    assert_eq!(flat_image(&executable), Err(()));
+}
+
+#[test]
+fn fits_in_memory() {
+    let executable = Executable::from(12, vec![], vec![1, 2, 3, 4, 5]);
+
+    assert!(!executable.fits_in_memory(16));
+    assert!(executable.fits_in_memory(17));
+    assert!(executable.fits_in_memory(1024));
+
+    let executable = Executable::from(u32::MAX, vec![], vec![1]);
+    assert!(!executable.fits_in_memory(u32::MAX));
+}
+
+#[test]
+fn raw_instructions_round_trip() {
+    let mut executable = loop_executable(4);
+    executable.append_data(&[1, 2, 3]);
+
+    let mut buffer = Vec::new();
+    write_instructions(&mut buffer, &executable).unwrap();
+    assert_eq!(buffer, executable.instructions());
+
+    let read = read_instructions(&mut &buffer[..]).unwrap();
+    assert_eq!(read, Executable::instructions_only(buffer));
+}
+
+#[test]
+fn read_truncated() {
+    let mut bytes = Vec::new();
+    write(
+        &mut bytes,
+        &Executable::from(0, vec![1, 2, 3, 4], vec![5, 6]),
+    )
+    .unwrap();
+
+    let read_error = |len: usize| read(&mut &bytes[..len]).unwrap_err();
+
+    let err = read_error(7);
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(
+        err.to_string(),
+        "truncated header: expected 12 bytes but only 7 available"
+    );
+
+    let err = read_error(13);
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(
+        err.to_string(),
+        "truncated instructions: declared 4 instruction bytes but only 1 available"
+    );
+
+    let err = read_error(17);
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(
+        err.to_string(),
+        "truncated data: declared 2 data bytes but only 1 available"
+    );
+
+    assert!(read(&mut &bytes[..]).is_ok());
+}
+
+#[test]
+fn read_checksum_mismatch() {
+    let executable = Executable::from(0, vec![1, 2, 3, 4], vec![5, 6]);
+    let mut bytes = Vec::new();
+    write(&mut bytes, &executable).unwrap();
+
+    assert_eq!(read(&mut &bytes[..]).unwrap(), executable);
+
+    // Flip a bit of the last data byte.
+    bytes[17] ^= 1;
+    let err = read(&mut &bytes[..]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let err = read(&mut &bytes[..bytes.len() - 2]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(
+        err.to_string(),
+        "truncated trailer: expected 12 bytes but only 10 available"
+    );
+}
+
+#[test]
+fn read_cut_checksum() {
+    let executable = Executable::from(0, vec![1, 2, 3, 4], vec![5, 6]);
+    let mut bytes = Vec::new();
+    write(&mut bytes, &executable).unwrap();
+
+    // Without the marker, these would look like files written by older versions.
+    let err = read(&mut &bytes[..bytes.len() - 4]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    let err = read(&mut &bytes[..bytes.len() - 8]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
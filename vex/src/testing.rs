@@ -0,0 +1,61 @@
+//! Helpers for testing code which produces executables, e.g. assemblers.
+//!
+//! Only available with the `testing` feature.
+
+use crate::Executable;
+
+/// Asserts that the instructions of `executable` disassemble to `expected`, one line per
+/// instruction word (see [`vcpu::disassemble`]).
+///
+/// Lines are compared case-insensitively, since assembly is case-insensitive, so registers can
+/// be written as e.g. `$t0`.
+///
+/// # Panics
+/// Panics with a line-by-line diff if the disassembly differs from `expected`.
+///
+/// # Examples
+/// ```
+/// use vcpu::*;
+/// use vex::Executable;
+///
+/// let executable = Executable::from_words(vec![
+///     instr_alu!(ADD, T0, T1, T2),
+///     instr_i!(HALT, ZERO, ZERO, 0),
+/// ]);
+/// vex::testing::assert_disassembles_to(&executable, &["ADD $t0, $t1, $t2", "HALT"]);
+/// ```
+/// [`vcpu::disassemble`]: ../../vcpu/fn.disassemble.html
+pub fn assert_disassembles_to(executable: &Executable, expected: &[&str]) {
+    let actual: Vec<String> = executable
+        .instruction_words()
+        .map(|word| vcpu::disassemble(word).unwrap_or_else(|err| format!("<{:?}>", err)))
+        .collect();
+
+    let mut diff = String::new();
+    for i in 0..actual.len().max(expected.len()) {
+        let actual_line = actual.get(i).map(String::as_str);
+        let expected_line = expected.get(i).copied();
+        let equal = match (actual_line, expected_line) {
+            (Some(a), Some(e)) => a.trim().eq_ignore_ascii_case(e.trim()),
+            _ => false,
+        };
+
+        if equal {
+            diff.push_str(&format!("  {:4}  {}\n", i, actual_line.unwrap()));
+        } else {
+            if let Some(e) = expected_line {
+                diff.push_str(&format!("- {:4}  {}\n", i, e));
+            }
+            if let Some(a) = actual_line {
+                diff.push_str(&format!("+ {:4}  {}\n", i, a));
+            }
+        }
+    }
+
+    if diff.lines().any(|line| !line.starts_with(' ')) {
+        panic!(
+            "Disassembly differs from expected (- expected, + actual):\n{}",
+            diff
+        );
+    }
+}
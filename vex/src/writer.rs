@@ -0,0 +1,178 @@
+use crate::{Executable, TRAILER_MARKER};
+use byteorder::WriteBytesExt;
+use std::io::prelude::*;
+use std::io::{Result, SeekFrom};
+use util::Endian;
+use vcpu::Word;
+
+/// Writes an executable incrementally, without keeping its instructions and data in memory.
+///
+/// The header is written with placeholder lengths, which are patched by [`finish`] once
+/// everything has been written. All instructions have to be written before any data.
+///
+/// If the underlying writer can't seek, use a [`BufferedExecutableWriter`] instead.
+///
+/// # Examples
+/// ```
+/// use std::io::Cursor;
+/// use vcpu::*;
+/// use vex::WriteVexExt;
+///
+/// let mut writer = Cursor::new(Vec::new()).vex_writer(0).unwrap();
+/// writer.write_word(instr_i!(HALT, ZERO, ZERO, 0)).unwrap();
+/// writer.write_data(&[1, 2, 3]).unwrap();
+///
+/// let mut cursor = writer.finish().unwrap();
+/// cursor.set_position(0);
+///
+/// let executable = vex::read(&mut cursor).unwrap();
+/// assert_eq!(executable.instructions().len(), 4);
+/// assert_eq!(executable.data(), &[1, 2, 3]);
+/// ```
+/// [`finish`]: #method.finish
+/// [`BufferedExecutableWriter`]: struct.BufferedExecutableWriter.html
+pub struct ExecutableWriter<W: Write + Seek> {
+    writer: W,
+    start: u64,
+    instr_len: u32,
+    data_len: u32,
+    entry_point: u32,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write + Seek> ExecutableWriter<W> {
+    /// Writes the header of an executable whose data is loaded at `data_offset`
+    /// at the current position of `writer`.
+    pub fn new(mut writer: W, data_offset: u32) -> Result<ExecutableWriter<W>> {
+        let start = writer.stream_position()?;
+        writer.write_u32::<Endian>(0)?;
+        writer.write_u32::<Endian>(0)?;
+        writer.write_u32::<Endian>(data_offset)?;
+
+        Ok(ExecutableWriter {
+            writer,
+            start,
+            instr_len: 0,
+            data_len: 0,
+            entry_point: 0,
+            hasher: crc32fast::Hasher::new(),
+        })
+    }
+
+    /// Appends instruction bytes.
+    ///
+    /// # Panics
+    /// Panics if any data has been written already.
+    pub fn write_instructions(&mut self, bytes: &[u8]) -> Result<()> {
+        assert_eq!(
+            self.data_len, 0,
+            "Instructions cannot be written after data."
+        );
+        self.writer.write_all(bytes)?;
+        self.hasher.update(bytes);
+        self.instr_len += bytes.len() as u32;
+        Ok(())
+    }
+
+    /// Appends a single instruction word.
+    ///
+    /// # Panics
+    /// Panics if any data has been written already.
+    pub fn write_word(&mut self, word: Word) -> Result<()> {
+        self.write_instructions(&vcpu::instructions_from_words(&[word]))
+    }
+
+    /// Appends data bytes. No more instructions can be written afterwards.
+    pub fn write_data(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer.write_all(bytes)?;
+        self.hasher.update(bytes);
+        self.data_len += bytes.len() as u32;
+        Ok(())
+    }
+
+    /// Sets the entry point, which is written by [`finish`].
+    ///
+    /// [`finish`]: #method.finish
+    pub fn set_entry_point(&mut self, entry_point: u32) {
+        self.entry_point = entry_point;
+    }
+
+    /// Writes the trailer marker, the entry point and the checksum and patches the lengths in the
+    /// header.
+    ///
+    /// Returns the underlying writer, positioned at the end of the executable.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.write_u32::<Endian>(TRAILER_MARKER)?;
+        self.writer.write_u32::<Endian>(self.entry_point)?;
+        self.writer.write_u32::<Endian>(self.hasher.finalize())?;
+
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.start))?;
+        self.writer.write_u32::<Endian>(self.instr_len)?;
+        self.writer.write_u32::<Endian>(self.data_len)?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+/// Fallback for [`ExecutableWriter`] if the underlying writer can't seek.
+///
+/// Everything is kept in memory and written at once by [`finish`].
+///
+/// [`ExecutableWriter`]: struct.ExecutableWriter.html
+/// [`finish`]: #method.finish
+pub struct BufferedExecutableWriter<W: Write> {
+    writer: W,
+    executable: Executable,
+}
+
+impl<W: Write> BufferedExecutableWriter<W> {
+    /// Constructs a writer for an executable whose data is loaded at `data_offset`.
+    pub fn new(writer: W, data_offset: u32) -> BufferedExecutableWriter<W> {
+        BufferedExecutableWriter {
+            writer,
+            executable: Executable::from(data_offset, Vec::new(), Vec::new()),
+        }
+    }
+
+    /// Appends instruction bytes.
+    ///
+    /// # Panics
+    /// Panics if any data has been written already.
+    pub fn write_instructions(&mut self, bytes: &[u8]) -> Result<()> {
+        assert!(
+            self.executable.data.is_empty(),
+            "Instructions cannot be written after data."
+        );
+        self.executable.instructions.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Appends a single instruction word.
+    ///
+    /// # Panics
+    /// Panics if any data has been written already.
+    pub fn write_word(&mut self, word: Word) -> Result<()> {
+        self.write_instructions(&vcpu::instructions_from_words(&[word]))
+    }
+
+    /// Appends data bytes. No more instructions can be written afterwards.
+    pub fn write_data(&mut self, bytes: &[u8]) -> Result<()> {
+        self.executable.append_data(bytes);
+        Ok(())
+    }
+
+    /// Sets the entry point of the executable.
+    pub fn set_entry_point(&mut self, entry_point: u32) {
+        self.executable.set_entry_point(entry_point);
+    }
+
+    /// Writes the executable and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        crate::write(&mut self.writer, &self.executable)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}